@@ -0,0 +1,64 @@
+//! Runtime API definition for the DEX pallet.
+//!
+//! Lets clients quote swaps and read pools without submitting the `price_oracle` extrinsic.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_dex::{NativeOrAsset, PoolInfo, Quote};
+
+sp_api::decl_runtime_apis! {
+	pub trait DexApi<AssetId, AssetBalance>
+	where
+		AssetId: Codec,
+		AssetBalance: Codec,
+	{
+		/// Quotes swapping an exact `amount_in` of `asset_in` for `asset_out` in the pool with
+		/// `fee_tier`.
+		fn quote_exact_in(
+			asset_in: NativeOrAsset<AssetId>,
+			asset_out: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+			amount_in: AssetBalance,
+		) -> Option<Quote<AssetBalance>>;
+
+		/// Quotes swapping `asset_in` for an exact `amount_out` of `asset_out` in the pool with
+		/// `fee_tier`.
+		fn quote_exact_out(
+			asset_in: NativeOrAsset<AssetId>,
+			asset_out: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+			amount_out: AssetBalance,
+		) -> Option<Quote<AssetBalance>>;
+
+		/// Returns the reserves of `asset_a` and `asset_b` in the pool with `fee_tier`, in that
+		/// order.
+		fn get_reserves(
+			asset_a: NativeOrAsset<AssetId>,
+			asset_b: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+		) -> Option<(AssetBalance, AssetBalance)>;
+
+		/// Returns the LP token id of the pool of `asset_a` and `asset_b` with `fee_tier`.
+		fn lp_id(
+			asset_a: NativeOrAsset<AssetId>,
+			asset_b: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+		) -> Option<AssetId>;
+
+		/// Returns a snapshot of the pool of `asset_a` and `asset_b` with `fee_tier`.
+		fn pool_info(
+			asset_a: NativeOrAsset<AssetId>,
+			asset_b: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+		) -> Option<PoolInfo<AssetId, AssetBalance>>;
+
+		/// Returns the fee tiers, in basis points, new pools can be created with.
+		fn fee_tiers() -> Vec<u16>;
+
+		/// Returns a snapshot of every pool.
+		fn pools() -> Vec<PoolInfo<AssetId, AssetBalance>>;
+	}
+}
@@ -0,0 +1,203 @@
+//! RPC interface for the DEX pallet.
+//!
+//! Every method takes an optional block hash and falls back to the best block when it is not
+//! given.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+
+pub use pallet_dex_rpc_runtime_api::{DexApi as DexRuntimeApi, NativeOrAsset, PoolInfo, Quote};
+
+#[rpc(client, server)]
+pub trait DexApi<BlockHash, AssetId, AssetBalance> {
+	/// Quotes swapping an exact `amount_in` of `asset_in` for `asset_out` in the pool with
+	/// `fee_tier`.
+	#[method(name = "dex_quoteExactIn")]
+	fn quote_exact_in(
+		&self,
+		asset_in: NativeOrAsset<AssetId>,
+		asset_out: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		amount_in: AssetBalance,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<Quote<AssetBalance>>>;
+
+	/// Quotes swapping `asset_in` for an exact `amount_out` of `asset_out` in the pool with
+	/// `fee_tier`.
+	#[method(name = "dex_quoteExactOut")]
+	fn quote_exact_out(
+		&self,
+		asset_in: NativeOrAsset<AssetId>,
+		asset_out: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		amount_out: AssetBalance,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<Quote<AssetBalance>>>;
+
+	/// Returns the reserves of `asset_a` and `asset_b` in the pool with `fee_tier`, in that order.
+	#[method(name = "dex_getReserves")]
+	fn get_reserves(
+		&self,
+		asset_a: NativeOrAsset<AssetId>,
+		asset_b: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<(AssetBalance, AssetBalance)>>;
+
+	/// Returns the LP token id of the pool of `asset_a` and `asset_b` with `fee_tier`.
+	#[method(name = "dex_lpId")]
+	fn lp_id(
+		&self,
+		asset_a: NativeOrAsset<AssetId>,
+		asset_b: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<AssetId>>;
+
+	/// Returns a snapshot of the pool of `asset_a` and `asset_b` with `fee_tier`.
+	#[method(name = "dex_poolInfo")]
+	fn pool_info(
+		&self,
+		asset_a: NativeOrAsset<AssetId>,
+		asset_b: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<PoolInfo<AssetId, AssetBalance>>>;
+
+	/// Returns the fee tiers, in basis points, new pools can be created with.
+	#[method(name = "dex_feeTiers")]
+	fn fee_tiers(&self, at: Option<BlockHash>) -> RpcResult<Vec<u16>>;
+
+	/// Returns a snapshot of every pool.
+	#[method(name = "dex_pools")]
+	fn pools(&self, at: Option<BlockHash>) -> RpcResult<Vec<PoolInfo<AssetId, AssetBalance>>>;
+}
+
+/// Provides the DEX RPC methods by calling into the runtime.
+pub struct Dex<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Dex<C, B> {
+	/// Creates a new instance of the DEX RPC handler.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error code returned when the runtime API call fails.
+const RUNTIME_ERROR: i32 = 1;
+
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> jsonrpsee::core::Error {
+	CallError::Custom(ErrorObject::owned(
+		RUNTIME_ERROR,
+		"Runtime error",
+		Some(format!("{:?}", err)),
+	))
+	.into()
+}
+
+impl<C, Block, AssetId, AssetBalance> DexApiServer<<Block as BlockT>::Hash, AssetId, AssetBalance>
+	for Dex<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DexRuntimeApi<Block, AssetId, AssetBalance>,
+	AssetId: Codec + Send + Sync + 'static,
+	AssetBalance: Codec + Send + Sync + 'static,
+{
+	fn quote_exact_in(
+		&self,
+		asset_in: NativeOrAsset<AssetId>,
+		asset_out: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		amount_in: AssetBalance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<Quote<AssetBalance>>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.quote_exact_in(at, asset_in, asset_out, fee_tier, amount_in)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn quote_exact_out(
+		&self,
+		asset_in: NativeOrAsset<AssetId>,
+		asset_out: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		amount_out: AssetBalance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<Quote<AssetBalance>>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.quote_exact_out(at, asset_in, asset_out, fee_tier, amount_out)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn get_reserves(
+		&self,
+		asset_a: NativeOrAsset<AssetId>,
+		asset_b: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<(AssetBalance, AssetBalance)>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.get_reserves(at, asset_a, asset_b, fee_tier)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn lp_id(
+		&self,
+		asset_a: NativeOrAsset<AssetId>,
+		asset_b: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<AssetId>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.lp_id(at, asset_a, asset_b, fee_tier)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn pool_info(
+		&self,
+		asset_a: NativeOrAsset<AssetId>,
+		asset_b: NativeOrAsset<AssetId>,
+		fee_tier: u16,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<PoolInfo<AssetId, AssetBalance>>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client
+			.runtime_api()
+			.pool_info(at, asset_a, asset_b, fee_tier)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn fee_tiers(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<Vec<u16>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client.runtime_api().fee_tiers(at).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn pools(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<PoolInfo<AssetId, AssetBalance>>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client.runtime_api().pools(at).map_err(runtime_error_into_rpc_err)
+	}
+}
@@ -5,30 +5,894 @@ use super::*;
 #[allow(unused)]
 use crate::Pallet as Dex;
 use frame_benchmarking::v2::*;
+use frame_support::{
+	assert_ok,
+	sp_std::{boxed::Box, vec},
+	traits::fungible,
+};
 use frame_system::RawOrigin;
 
+// the balance of every asset the benchmark accounts start with
+const FUNDS: u32 = 1_000_000_000;
+// the amount of each asset deposited into a pool when it is created
+const LIQUIDITY: u32 = 1_000_000;
+// the amount traded, zapped or staked by the benchmarked calls
+const AMOUNT: u32 = 10_000;
+
+fn asset<T: Config>(id: u32) -> AssetKindOf<T> {
+	NativeOrAsset::Asset(T::BenchmarkHelper::asset_id(id))
+}
+
+fn fee_tier<T: Config>() -> u16 {
+	T::DefaultFeeTier::get()
+}
+
+// creates the assets 1 to `assets` if needed and gives `who` `FUNDS` of each of them and of the
+// native currency, which pays for pool deposits
+fn fund<T: Config>(who: &T::AccountId, assets: u32) {
+	let balances = (1..=assets)
+		.map(|id| (T::BenchmarkHelper::asset_id(id), FUNDS.into()))
+		.collect();
+	assert_ok!(Dex::<T>::setup_account(who.clone(), balances));
+	<T::NativeBalance as fungible::Mutate<T::AccountId>>::set_balance(who, FUNDS.into());
+}
+
+// creates the pool of assets `a` and `b` with the default fee tier and deposits `LIQUIDITY` of
+// both from `who`
+fn create_funded_pool<T: Config>(who: &T::AccountId, a: u32, b: u32) {
+	assert_ok!(Dex::<T>::add_liquidity(
+		RawOrigin::Signed(who.clone()).into(),
+		asset::<T>(a),
+		asset::<T>(b),
+		fee_tier::<T>(),
+		LIQUIDITY.into(),
+		LIQUIDITY.into(),
+		Zero::zero(),
+		Zero::zero(),
+	));
+}
+
+// the assets 1 to `n`
+fn stable_assets<T: Config>(n: u32) -> BoundedVec<AssetKindOf<T>, T::MaxStableAssets> {
+	(1..=n).map(asset::<T>).collect::<Vec<_>>().try_into().unwrap()
+}
+
+// `amount` of each of the assets 1 to `n`
+fn stable_amounts<T: Config>(
+	n: u32,
+	amount: u32,
+) -> BoundedVec<(AssetKindOf<T>, AssetBalanceOf<T>), T::MaxStableAssets> {
+	(1..=n)
+		.map(|id| (asset::<T>(id), amount.into()))
+		.collect::<Vec<_>>()
+		.try_into()
+		.unwrap()
+}
+
+// creates the StableSwap pool of assets 1 to `n` with the default fee tier and deposits
+// `LIQUIDITY` of each of them from `who`
+fn create_funded_stable_pool<T: Config>(who: &T::AccountId, n: u32) -> AssetIdOf<T> {
+	assert_ok!(Dex::<T>::create_stable_pool(
+		RawOrigin::Signed(who.clone()).into(),
+		stable_assets::<T>(n),
+		fee_tier::<T>(),
+		100,
+	));
+	assert_ok!(Dex::<T>::add_stable_liquidity(
+		RawOrigin::Signed(who.clone()).into(),
+		lp_id::<T>(1, 2),
+		stable_amounts::<T>(n, LIQUIDITY),
+		Zero::zero(),
+	));
+	lp_id::<T>(1, 2)
+}
+
+// the assets 1 to `n` with even weights, the first taking what doesn't divide evenly
+fn weighted_assets<T: Config>(
+	n: u32,
+) -> BoundedVec<(AssetKindOf<T>, Perbill), T::MaxWeightedAssets> {
+	let total = Perbill::from_percent(100).deconstruct();
+	let weight = total / n;
+	(1..=n)
+		.map(|id| {
+			let weight = if id == 1 { total - weight * (n - 1) } else { weight };
+			(asset::<T>(id), Perbill::from_parts(weight))
+		})
+		.collect::<Vec<_>>()
+		.try_into()
+		.unwrap()
+}
+
+// `amount` of each of the assets 1 to `n`
+fn weighted_amounts<T: Config>(
+	n: u32,
+	amount: u32,
+) -> BoundedVec<(AssetKindOf<T>, AssetBalanceOf<T>), T::MaxWeightedAssets> {
+	(1..=n)
+		.map(|id| (asset::<T>(id), amount.into()))
+		.collect::<Vec<_>>()
+		.try_into()
+		.unwrap()
+}
+
+// creates the weighted pool of assets 1 to `n` with the default fee tier and deposits
+// `LIQUIDITY` of each of them from `who`, for a supply of `LIQUIDITY`
+fn create_funded_weighted_pool<T: Config>(who: &T::AccountId, n: u32) -> AssetIdOf<T> {
+	assert_ok!(Dex::<T>::create_weighted_pool(
+		RawOrigin::Signed(who.clone()).into(),
+		weighted_assets::<T>(n),
+		fee_tier::<T>(),
+	));
+	assert_ok!(Dex::<T>::add_weighted_liquidity(
+		RawOrigin::Signed(who.clone()).into(),
+		lp_id::<T>(1, 2),
+		LIQUIDITY.into(),
+		weighted_amounts::<T>(n, LIQUIDITY),
+	));
+	lp_id::<T>(1, 2)
+}
+
+// creates the concentrated pool of assets 1 and 2 with the default fee tier at a price of one and
+// mints a position of `LIQUIDITY` over ten tick spacings either side of it from `who`, the
+// position 0
+fn create_funded_concentrated_pool<T: Config>(who: &T::AccountId) -> AssetIdOf<T> {
+	assert_ok!(Dex::<T>::create_concentrated_pool(
+		RawOrigin::Signed(who.clone()).into(),
+		asset::<T>(1),
+		asset::<T>(2),
+		fee_tier::<T>(),
+		FixedU128::one(),
+	));
+	let lp_id = lp_id::<T>(1, 2);
+	let tick = concentrated_math::tick_spacing(fee_tier::<T>()) * 10;
+	assert_ok!(Dex::<T>::mint_position(
+		RawOrigin::Signed(who.clone()).into(),
+		lp_id.clone(),
+		-tick,
+		tick,
+		LIQUIDITY.into(),
+		FUNDS.into(),
+		FUNDS.into(),
+	));
+	lp_id
+}
+
+fn lp_id<T: Config>(a: u32, b: u32) -> AssetIdOf<T> {
+	Dex::<T>::get_lp_id(&asset::<T>(a), &asset::<T>(b), fee_tier::<T>()).unwrap()
+}
+
+// turns the protocol fee on, so pools created afterwards track their fee growth
+fn enable_protocol_fee<T: Config>() -> Result<(), BenchmarkError> {
+	let origin =
+		T::PermissionOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+	Dex::<T>::set_protocol_fee(origin, Some(account("protocol", 0, 0)), Perbill::from_percent(50))?;
+	Ok(())
+}
+
+// trades through the pool of assets 1 and 2, so the next liquidity change has to mint the
+// protocol's share of the fees
+fn accrue_protocol_fee<T: Config>(trader: &T::AccountId) -> Result<(), BenchmarkError> {
+	Dex::<T>::swap_exact_in_for_out(
+		RawOrigin::Signed(trader.clone()).into(),
+		asset::<T>(1),
+		asset::<T>(2),
+		fee_tier::<T>(),
+		AMOUNT.into(),
+		Zero::zero(),
+	)?;
+	Ok(())
+}
+
+// starts a farm for the pool of assets 1 and 2 paying out asset 3 from a funded rewards account
+fn create_farm<T: Config>() -> Result<AssetIdOf<T>, BenchmarkError> {
+	let origin =
+		T::PermissionOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+	fund::<T>(&Dex::<T>::rewards_account(), 3);
+	let lp_id = lp_id::<T>(1, 2);
+	Dex::<T>::set_farm(origin, lp_id.clone(), asset::<T>(3), AMOUNT.into())?;
+	Ok(lp_id)
+}
+
+fn run_to_block<T: Config>(n: u32) {
+	frame_system::Pallet::<T>::set_block_number(n.into());
+}
+
 #[benchmarks]
 mod benchmarks {
 	use super::*;
 
 	#[benchmark]
-	fn do_something() {
-		let value = 100u32.into();
+	fn add_liquidity_new_pool() {
 		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+
 		#[extrinsic_call]
-		do_something(RawOrigin::Signed(caller), value);
+		add_liquidity(
+			RawOrigin::Signed(caller),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			LIQUIDITY.into(),
+			LIQUIDITY.into(),
+			Zero::zero(),
+			Zero::zero(),
+		);
 
-		assert_eq!(Something::<T>::get(), Some(value));
+		assert!(PoolMap::<T>::contains_key(lp_id::<T>(1, 2)));
 	}
 
 	#[benchmark]
-	fn cause_error() {
-		Something::<T>::put(100u32);
+	fn add_liquidity_existing_pool() -> Result<(), BenchmarkError> {
 		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		enable_protocol_fee::<T>()?;
+		create_funded_pool::<T>(&caller, 1, 2);
+		accrue_protocol_fee::<T>(&caller)?;
+
+		#[extrinsic_call]
+		add_liquidity(
+			RawOrigin::Signed(caller.clone()),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			AMOUNT.into(),
+			AMOUNT.into(),
+			Zero::zero(),
+			Zero::zero(),
+		);
+
+		assert!(!T::Fungibles::balance(lp_id::<T>(1, 2), &account("protocol", 0, 0)).is_zero());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn remove_liquidity() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		enable_protocol_fee::<T>()?;
+		create_funded_pool::<T>(&caller, 1, 2);
+		accrue_protocol_fee::<T>(&caller)?;
+		let lp_before = T::Fungibles::balance(lp_id::<T>(1, 2), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			AMOUNT.into(),
+			Zero::zero(),
+			Zero::zero(),
+		);
+
+		assert!(T::Fungibles::balance(lp_id::<T>(1, 2), &caller) < lp_before);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn swap_exact_in_for_out() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let balance_before = T::Fungibles::balance(T::BenchmarkHelper::asset_id(2), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			AMOUNT.into(),
+			Zero::zero(),
+		);
+
+		assert!(T::Fungibles::balance(T::BenchmarkHelper::asset_id(2), &caller) > balance_before);
+	}
+
+	#[benchmark]
+	fn swap_in_for_exact_out() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let balance_before = T::Fungibles::balance(T::BenchmarkHelper::asset_id(2), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			LIQUIDITY.into(),
+			AMOUNT.into(),
+		);
+
+		assert_eq!(
+			T::Fungibles::balance(T::BenchmarkHelper::asset_id(2), &caller),
+			balance_before + AMOUNT.into()
+		);
+	}
+
+	#[benchmark]
+	fn price_oracle() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee_tier::<T>());
+	}
+
+	#[benchmark]
+	fn set_fee_tiers(n: Linear<0, { T::MaxFeeTiers::get() }>) -> Result<(), BenchmarkError> {
+		let origin =
+			T::PermissionOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let fee_tiers: BoundedVec<u16, T::MaxFeeTiers> =
+			(0..n).map(|tier| tier as u16).collect::<Vec<_>>().try_into().unwrap();
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, fee_tiers.clone());
+
+		assert_eq!(FeeTiers::<T>::get(), fee_tiers);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn flash_loan(n: Linear<1, { T::MaxPools::get() }>) {
+		// every one of the `n` pools holds asset 1, so the loan looks at all of them, takes
+		// something from each and shares its fee between them
+		let provider: T::AccountId = account("provider", 0, 0);
+		fund::<T>(&provider, n + 1);
+		for other in 2..=n + 1 {
+			create_funded_pool::<T>(&provider, 1, other);
+		}
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 1);
+		let amount: AssetBalanceOf<T> = (LIQUIDITY * (n - 1) + AMOUNT).into();
+		let dex_account = Dex::<T>::account_id();
+
+		#[block]
+		{
+			// the borrower pays the loan back from its own funds, which is what the weight of the
+			// loan itself covers. Whatever the borrower does with it is charged separately
+			assert_ok!(Dex::<T>::do_flash_loan(&caller, &asset::<T>(1), amount, |fee| {
+				NativeOrAssetUnion::<T>::transfer(
+					&asset::<T>(1),
+					&caller,
+					&dex_account,
+					fee + amount,
+					Expendable,
+				)?;
+				Ok(Weight::zero())
+			}));
+		}
+	}
+
+	#[benchmark]
+	fn swap_exact_in_along_path(n: Linear<2, { T::MaxPathLength::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		for hop in 1..n {
+			create_funded_pool::<T>(&caller, hop, hop + 1);
+		}
+		let path: BoundedVec<_, T::MaxPathLength> =
+			(1..=n).map(asset::<T>).collect::<Vec<_>>().try_into().unwrap();
+		let fee_tiers: BoundedVec<_, T::MaxPathLength> =
+			vec![fee_tier::<T>(); n as usize - 1].try_into().unwrap();
+		let balance_before = T::Fungibles::balance(T::BenchmarkHelper::asset_id(n), &caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), path, fee_tiers, AMOUNT.into(), Zero::zero());
+
+		assert!(T::Fungibles::balance(T::BenchmarkHelper::asset_id(n), &caller) > balance_before);
+	}
+
+	#[benchmark]
+	fn swap_in_for_exact_out_along_path(n: Linear<2, { T::MaxPathLength::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		for hop in 1..n {
+			create_funded_pool::<T>(&caller, hop, hop + 1);
+		}
+		let path: BoundedVec<_, T::MaxPathLength> =
+			(1..=n).map(asset::<T>).collect::<Vec<_>>().try_into().unwrap();
+		let fee_tiers: BoundedVec<_, T::MaxPathLength> =
+			vec![fee_tier::<T>(); n as usize - 1].try_into().unwrap();
+		let balance_before = T::Fungibles::balance(T::BenchmarkHelper::asset_id(n), &caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), path, fee_tiers, LIQUIDITY.into(), AMOUNT.into());
+
+		assert_eq!(
+			T::Fungibles::balance(T::BenchmarkHelper::asset_id(n), &caller),
+			balance_before + AMOUNT.into()
+		);
+	}
+
+	#[benchmark]
+	fn twap_oracle() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee_tier::<T>(), 0);
+	}
+
+	#[benchmark]
+	fn set_protocol_fee() -> Result<(), BenchmarkError> {
+		let origin =
+			T::PermissionOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let recipient: T::AccountId = account("protocol", 0, 0);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, Some(recipient.clone()), Perbill::from_percent(50));
+
+		assert_eq!(ProtocolFeeRecipient::<T>::get(), Some(recipient));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn zap_in() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let lp_before = T::Fungibles::balance(lp_id::<T>(1, 2), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset::<T>(1),
+			AMOUNT.into(),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			Zero::zero(),
+		);
+
+		assert!(T::Fungibles::balance(lp_id::<T>(1, 2), &caller) > lp_before);
+	}
+
+	#[benchmark]
+	fn zap_out() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let lp_before = T::Fungibles::balance(lp_id::<T>(1, 2), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			AMOUNT.into(),
+			Zero::zero(),
+		);
+
+		assert!(T::Fungibles::balance(lp_id::<T>(1, 2), &caller) < lp_before);
+	}
+
+	#[benchmark]
+	fn set_farm() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		// changing an existing farm brings its rewards up to date first
+		let lp_id = create_farm::<T>()?;
+		run_to_block::<T>(10);
+		let origin =
+			T::PermissionOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, lp_id.clone(), asset::<T>(3), (AMOUNT * 2).into());
+
+		assert_eq!(Farms::<T>::get(&lp_id).unwrap().reward_per_block, (AMOUNT * 2).into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn stake_lp() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let lp_id = create_farm::<T>()?;
+		// adding to an existing stake accrues its rewards first
+		Dex::<T>::stake_lp(RawOrigin::Signed(caller.clone()).into(), lp_id.clone(), AMOUNT.into())?;
+		run_to_block::<T>(10);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), lp_id.clone(), AMOUNT.into());
+
+		assert_eq!(Dex::<T>::staked_balance(&lp_id, &caller), Some((AMOUNT * 2).into()));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn unstake_lp() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let lp_id = create_farm::<T>()?;
+		Dex::<T>::stake_lp(RawOrigin::Signed(caller.clone()).into(), lp_id.clone(), AMOUNT.into())?;
+		run_to_block::<T>(10);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), lp_id.clone(), AMOUNT.into());
+
+		assert!(!Dex::<T>::pending_rewards(&lp_id, &caller).is_zero());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn claim_rewards() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let lp_id = create_farm::<T>()?;
+		Dex::<T>::stake_lp(RawOrigin::Signed(caller.clone()).into(), lp_id.clone(), AMOUNT.into())?;
+		run_to_block::<T>(10);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), lp_id.clone());
+
+		assert!(Dex::<T>::pending_rewards(&lp_id, &caller).is_zero());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn flash_swap() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let balance_before = T::Fungibles::balance(T::BenchmarkHelper::asset_id(2), &caller);
+
+		#[block]
+		{
+			// takes out asset 2 and repays more than enough of asset 1. Whatever the call does
+			// is charged separately
+			assert_ok!(Dex::<T>::do_flash_swap(
+				&caller,
+				&asset::<T>(1),
+				&asset::<T>(2),
+				fee_tier::<T>(),
+				(Zero::zero(), AMOUNT.into()),
+				((AMOUNT * 2).into(), Zero::zero()),
+				|| Ok(Weight::zero()),
+			));
+		}
+
+		assert_eq!(
+			T::Fungibles::balance(T::BenchmarkHelper::asset_id(2), &caller),
+			balance_before + AMOUNT.into()
+		);
+	}
+
+	#[benchmark]
+	fn create_pool() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee_tier::<T>());
+
+		assert!(PoolMap::<T>::contains_key(lp_id::<T>(1, 2)));
+	}
+
+	#[benchmark]
+	fn destroy_pool() -> Result<(), BenchmarkError> {
+		// a pool that had liquidity, so its locked LP tokens are burned and the reserves they
+		// stood for are paid out
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		Dex::<T>::remove_liquidity(
+			RawOrigin::Signed(caller.clone()).into(),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			T::Fungibles::balance(lp_id::<T>(1, 2), &caller),
+			Zero::zero(),
+			Zero::zero(),
+		)?;
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), asset::<T>(1), asset::<T>(2), fee_tier::<T>());
+
+		assert!(!PoolMap::<T>::contains_key(lp_id::<T>(1, 2)));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_pool_status() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let origin =
+			T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+		#[extrinsic_call]
+		_(
+			origin as T::RuntimeOrigin,
+			asset::<T>(1),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			PoolStatus::SwapsPaused,
+		);
+
+		assert_eq!(PoolMap::<T>::get(lp_id::<T>(1, 2)).unwrap().status, PoolStatus::SwapsPaused);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn skim() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let pool_account = Dex::<T>::pool_account(&lp_id::<T>(1, 2));
+		for id in [1, 2] {
+			assert_ok!(NativeOrAssetUnion::<T>::transfer(
+				&asset::<T>(id),
+				&caller,
+				&pool_account,
+				AMOUNT.into(),
+				Expendable,
+			));
+		}
+		let to: T::AccountId = account("to", 0, 0);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), lp_id::<T>(1, 2), to.clone());
+
+		assert_eq!(NativeOrAssetUnion::<T>::balance(&asset::<T>(1), &to), AMOUNT.into());
+	}
+
+	#[benchmark]
+	fn sync() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_pool::<T>(&caller, 1, 2);
+		let pool_account = Dex::<T>::pool_account(&lp_id::<T>(1, 2));
+		assert_ok!(NativeOrAssetUnion::<T>::transfer(
+			&asset::<T>(1),
+			&caller,
+			&pool_account,
+			AMOUNT.into(),
+			Expendable,
+		));
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), lp_id::<T>(1, 2));
+
+		let reserves = Dex::<T>::get_reserves(asset::<T>(1), asset::<T>(2), fee_tier::<T>());
+		assert_eq!(reserves, Some(((LIQUIDITY + AMOUNT).into(), LIQUIDITY.into())));
+	}
+
+	#[benchmark]
+	fn create_stable_pool(n: Linear<2, { T::MaxStableAssets::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), stable_assets::<T>(n), fee_tier::<T>(), 100);
+
+		assert_eq!(
+			Dex::<T>::get_lp_id(&asset::<T>(1), &asset::<T>(n), fee_tier::<T>()),
+			Ok(lp_id::<T>(1, 2))
+		);
+	}
+
+	#[benchmark]
+	fn add_stable_liquidity(n: Linear<2, { T::MaxStableAssets::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let lp_id = create_funded_stable_pool::<T>(&caller, n);
+		let lp_before = T::Fungibles::balance(lp_id.clone(), &caller);
+
+		// a deposit off the pool's balance, so the imbalance fee is worked out as well
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			lp_id.clone(),
+			stable_amounts::<T>(n - 1, AMOUNT),
+			Zero::zero(),
+		);
+
+		assert!(T::Fungibles::balance(lp_id, &caller) > lp_before);
+	}
+
+	#[benchmark]
+	fn remove_stable_liquidity(n: Linear<2, { T::MaxStableAssets::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let lp_id = create_funded_stable_pool::<T>(&caller, n);
+		let lp_before = T::Fungibles::balance(lp_id.clone(), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			lp_id.clone(),
+			AMOUNT.into(),
+			stable_amounts::<T>(n, 1),
+		);
+
+		assert_eq!(T::Fungibles::balance(lp_id, &caller), lp_before - AMOUNT.into());
+	}
+
+	#[benchmark]
+	fn ramp_amplification() -> Result<(), BenchmarkError> {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let lp_id = create_funded_stable_pool::<T>(&caller, 2);
+		let origin = T::AmplificationOrigin::try_successful_origin()
+			.map_err(|_| BenchmarkError::Weightless)?;
+		let end = frame_system::Pallet::<T>::block_number()
+			.saturating_add(T::MinAmplificationRamp::get());
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, lp_id.clone(), 1_000, end);
+
+		let PoolKind::StableSwap(stable) = PoolMap::<T>::get(lp_id).unwrap().kind else {
+			panic!("the pool is a StableSwap pool")
+		};
+		assert_eq!(stable.amplification.target, 1_000);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn create_weighted_pool(n: Linear<2, { T::MaxWeightedAssets::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), weighted_assets::<T>(n), fee_tier::<T>());
+
+		assert_eq!(
+			Dex::<T>::get_lp_id(&asset::<T>(1), &asset::<T>(n), fee_tier::<T>()),
+			Ok(lp_id::<T>(1, 2))
+		);
+	}
+
+	#[benchmark]
+	fn add_weighted_liquidity(n: Linear<2, { T::MaxWeightedAssets::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let lp_id = create_funded_weighted_pool::<T>(&caller, n);
+		let lp_before = T::Fungibles::balance(lp_id.clone(), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			lp_id.clone(),
+			AMOUNT.into(),
+			weighted_amounts::<T>(n, LIQUIDITY),
+		);
+
+		assert_eq!(T::Fungibles::balance(lp_id, &caller), lp_before + AMOUNT.into());
+	}
+
+	#[benchmark]
+	fn remove_weighted_liquidity(n: Linear<2, { T::MaxWeightedAssets::get() }>) {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, n);
+		let lp_id = create_funded_weighted_pool::<T>(&caller, n);
+		let lp_before = T::Fungibles::balance(lp_id.clone(), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			lp_id.clone(),
+			AMOUNT.into(),
+			weighted_amounts::<T>(n, 1),
+		);
+
+		assert_eq!(T::Fungibles::balance(lp_id, &caller), lp_before - AMOUNT.into());
+	}
+
+	#[benchmark]
+	fn add_weighted_liquidity_single() {
+		let caller: T::AccountId = whitelisted_caller();
+		let n = T::MaxWeightedAssets::get();
+		fund::<T>(&caller, n);
+		let lp_id = create_funded_weighted_pool::<T>(&caller, n);
+		let lp_before = T::Fungibles::balance(lp_id.clone(), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			lp_id.clone(),
+			asset::<T>(1),
+			AMOUNT.into(),
+			Zero::zero(),
+		);
+
+		assert!(T::Fungibles::balance(lp_id, &caller) > lp_before);
+	}
+
+	#[benchmark]
+	fn remove_weighted_liquidity_single() {
+		let caller: T::AccountId = whitelisted_caller();
+		let n = T::MaxWeightedAssets::get();
+		fund::<T>(&caller, n);
+		let lp_id = create_funded_weighted_pool::<T>(&caller, n);
+		let lp_before = T::Fungibles::balance(lp_id.clone(), &caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			lp_id.clone(),
+			AMOUNT.into(),
+			asset::<T>(1),
+			Zero::zero(),
+		);
+
+		assert_eq!(T::Fungibles::balance(lp_id, &caller), lp_before - AMOUNT.into());
+	}
+
+	#[benchmark]
+	fn create_concentrated_pool() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			asset::<T>(1),
+			asset::<T>(2),
+			fee_tier::<T>(),
+			FixedU128::one(),
+		);
+
+		assert!(Dex::<T>::get_lp_id(&asset::<T>(1), &asset::<T>(2), fee_tier::<T>()).is_ok());
+	}
+
+	#[benchmark]
+	fn mint_position() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		let lp_id = create_funded_concentrated_pool::<T>(&caller);
+		// a range of its own, so both of its ticks are initialized
+		let tick = concentrated_math::tick_spacing(fee_tier::<T>()) * 20;
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			lp_id,
+			-tick,
+			tick,
+			LIQUIDITY.into(),
+			FUNDS.into(),
+			FUNDS.into(),
+		);
+
+		assert!(Positions::<T>::contains_key(&caller, 1));
+	}
+
+	#[benchmark]
+	fn burn_position() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_concentrated_pool::<T>(&caller);
+		// a trade through the pool earns the position fees to pay out
+		accrue_protocol_fee::<T>(&caller).unwrap();
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), 0, LIQUIDITY.into(), Zero::zero(), Zero::zero());
+
+		assert!(!Positions::<T>::contains_key(&caller, 0));
+	}
+
+	#[benchmark]
+	fn collect_fees() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller, 2);
+		create_funded_concentrated_pool::<T>(&caller);
+		// a trade through the pool earns the position fees to pay out
+		accrue_protocol_fee::<T>(&caller).unwrap();
+
 		#[extrinsic_call]
-		cause_error(RawOrigin::Signed(caller));
+		_(RawOrigin::Signed(caller.clone()), 0);
 
-		assert_eq!(Something::<T>::get(), Some(101u32));
+		let position = Positions::<T>::get(&caller, 0).unwrap();
+		assert!(position.fees_owed_1.is_zero() && position.fees_owed_2.is_zero());
 	}
 
 	impl_benchmark_test_suite!(Dex, crate::mock::new_test_ext(), crate::mock::Test);
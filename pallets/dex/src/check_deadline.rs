@@ -0,0 +1,91 @@
+use crate::{Call, Config};
+use codec::{Decode, Encode};
+use frame_support::{
+	sp_runtime::{
+		traits::{DispatchInfoOf, SignedExtension},
+		transaction_validity::{
+			InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction,
+		},
+		SaturatedConversion, Saturating,
+	},
+	sp_std::fmt,
+	traits::IsSubType,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::TypeInfo;
+
+/// Rejects DEX calls whose deadline has passed.
+///
+/// The deadline is the last block number in which the transaction may be included. Calls to other
+/// pallets, and transactions signed without a deadline, are not affected. Expired DEX calls are
+/// invalid during transaction validation, so they are dropped from the transaction pool instead of
+/// being included in a block and failing on slippage.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckDexDeadline<T: Config + Send + Sync>(pub Option<BlockNumberFor<T>>);
+
+impl<T: Config + Send + Sync> CheckDexDeadline<T> {
+	/// Create a new extension with the given deadline.
+	pub fn new(deadline: Option<BlockNumberFor<T>>) -> Self {
+		Self(deadline)
+	}
+}
+
+impl<T: Config + Send + Sync> fmt::Debug for CheckDexDeadline<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "CheckDexDeadline({:?})", self.0)
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for CheckDexDeadline<T>
+where
+	<T as frame_system::Config>::RuntimeCall: IsSubType<Call<T>>,
+{
+	const IDENTIFIER: &'static str = "CheckDexDeadline";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		let (Some(deadline), Some(_)) = (self.0, call.is_sub_type()) else {
+			return Ok(ValidTransaction::default())
+		};
+
+		// Block number the transaction would be included in
+		let now = frame_system::Pallet::<T>::block_number();
+		if now > deadline {
+			return Err(InvalidTransaction::Stale.into())
+		}
+
+		// Valid for every block up to and including the deadline
+		let longevity = deadline.saturating_sub(now).saturated_into::<u64>().saturating_add(1);
+		Ok(ValidTransaction { longevity, ..Default::default() })
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		self.validate(who, call, info, len).map(|_| ())
+	}
+}
@@ -0,0 +1,317 @@
+//! The maths of concentrated liquidity, as in Uniswap V3.
+//!
+//! Liquidity is provided over ranges of ticks, tick `i` being the price `1.0001^i` of a pool's
+//! first asset in its second. Within a range, liquidity `L` trades like a constant product pool
+//! with `L = sqrt(x * y)`, so only the square root of the price needs to be followed: paying in
+//! `Δy` of the second asset moves it by `Δy / L`, and `Δx` of the first asset moves its inverse
+//! by `Δx / L`. Square roots of prices are Q64.96 fixed-point numbers and fees per unit of
+//! liquidity Q128.128 ones, both in `U256`, as Uniswap has them. Amounts paid in are rounded up
+//! and amounts paid out down, in the pool's favour, and `None` means the maths is out of range
+//! or overflowed.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::sp_runtime::{FixedPointNumber, FixedU128, RuntimeDebug};
+use scale_info::TypeInfo;
+use sp_core::{U256, U512};
+
+/// The lowest tick, whose price is about `2^-128`.
+pub const MIN_TICK: i32 = -887_272;
+
+/// The highest tick, whose price is about `2^128`.
+pub const MAX_TICK: i32 = 887_272;
+
+/// The square root of the price of `MIN_TICK`.
+pub const MIN_SQRT_PRICE: U256 = U256([4_295_128_739, 0, 0, 0]);
+
+/// The square root of the price of `MAX_TICK`.
+pub const MAX_SQRT_PRICE: U256 =
+	U256([6_743_328_256_752_651_558, 17_280_870_778_742_802_505, 4_294_805_859, 0]);
+
+// 2^96, one in Q64.96
+const Q96: U256 = U256([0, 1 << 32, 0, 0]);
+
+// 2^128, one in Q128.128
+const Q128: U256 = U256([0, 0, 1, 0]);
+
+// `sqrt(1.0001)^-(2^i)` as a Q128.128 number, rounded up, for every bit `i` a tick can have
+const TICK_FACTORS: [u128; 20] = [
+	0xfffcb933bd6fad37aa2d162d1a594001,
+	0xfff97272373d413259a46990580e213a,
+	0xfff2e50f5f656932ef12357cf3c7fdcc,
+	0xffe5caca7e10e4e61c3624eaa0941cd0,
+	0xffcb9843d60f6159c9db58835c926644,
+	0xff973b41fa98c081472e6896dfb254c0,
+	0xff2ea16466c96a3843ec78b326b52861,
+	0xfe5dee046a99a2a811c461f1969c3053,
+	0xfcbe86c7900a88aedcffc83b479aa3a4,
+	0xf987a7253ac413176f2b074cf7815e54,
+	0xf3392b0822b70005940c7a398e4b70f3,
+	0xe7159475a2c29b7443b29c7fa6e889d9,
+	0xd097f3bdfd2022b8845ad8f792aa5825,
+	0xa9f746462d870fdf8a65dc1f90e061e5,
+	0x70d869a156d2a1b890bb3df62baf32f7,
+	0x31be135f97d08fd981231505542fcfa6,
+	0x9aa508b5b7a84e1c677de54f3e99bc9,
+	0x5d6af8dedb81196699c329225ee604,
+	0x2216e584f5fa1ea926041bedfe98,
+	0x48a170391f7dc42444e8fa2,
+];
+
+/// An initialized tick of a concentrated liquidity pool, a bound of at least one position.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct Tick {
+	/// The liquidity of the positions bounded by the tick. The tick is dropped when it is zero.
+	pub liquidity_gross: u128,
+	/// The liquidity that becomes active as the price crosses the tick upwards, and inactive
+	/// as it crosses downwards.
+	pub liquidity_net: i128,
+	/// The fees per unit of liquidity earned on the other side of the tick from the current
+	/// price, in each asset of the pool.
+	pub fee_growth_outside_1: U256,
+	pub fee_growth_outside_2: U256,
+}
+
+/// A step of a swap towards a target price.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct SwapStep {
+	/// The square root of the price after the step.
+	pub sqrt_price: U256,
+	/// Whether the step reached its target.
+	pub reached: bool,
+	/// The amount paid in, without the fee.
+	pub amount_in: U256,
+	/// The amount taken out.
+	pub amount_out: U256,
+	/// The fee paid on top of `amount_in`.
+	pub fee: U256,
+}
+
+// `numerator / denominator`, rounded down or up, if it fits
+fn div(numerator: U512, denominator: U512, round_up: bool) -> Option<U256> {
+	if denominator.is_zero() {
+		return None
+	}
+	let (quotient, remainder) = numerator.div_mod(denominator);
+	let quotient = if round_up && !remainder.is_zero() { quotient + U512::one() } else { quotient };
+	U256::try_from(quotient).ok()
+}
+
+// `a * b / denominator`, rounded down or up, without overflowing in between
+fn mul_div(a: U256, b: U256, denominator: U256, round_up: bool) -> Option<U256> {
+	div(a.full_mul(b), denominator.into(), round_up)
+}
+
+/// The distance between the ticks that can bound positions in a pool with `fee_tier`, as
+/// Uniswap has it for its tiers: 10 for 5 basis points, 60 for 30 and 200 for 100.
+pub fn tick_spacing(fee_tier: u16) -> i32 {
+	(i32::from(fee_tier) * 2).max(1)
+}
+
+/// The square root of the price at `tick`.
+pub fn sqrt_price_at_tick(tick: i32) -> Option<U256> {
+	if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+		return None
+	}
+	let bits = tick.unsigned_abs();
+	let mut ratio = if bits & 1 == 1 { U256::from(TICK_FACTORS[0]) } else { Q128 };
+	for (bit, factor) in TICK_FACTORS.iter().enumerate().skip(1) {
+		if bits & (1 << bit) != 0 {
+			ratio = ratio.checked_mul(U256::from(*factor))? >> 128;
+		}
+	}
+	if tick > 0 {
+		ratio = U256::MAX / ratio;
+	}
+	// from Q128.128 to Q64.96, rounded up
+	let sqrt_price = ratio >> 32;
+	Some(if (ratio & U256::from(u32::MAX)).is_zero() {
+		sqrt_price
+	} else {
+		sqrt_price + U256::one()
+	})
+}
+
+/// The highest tick whose price is at most the one of `sqrt_price`.
+pub fn tick_at_sqrt_price(sqrt_price: U256) -> Option<i32> {
+	if sqrt_price < MIN_SQRT_PRICE || sqrt_price >= MAX_SQRT_PRICE {
+		return None
+	}
+	let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+	while low < high {
+		let middle = low + (high - low + 1) / 2;
+		if sqrt_price_at_tick(middle)? <= sqrt_price {
+			low = middle;
+		} else {
+			high = middle - 1;
+		}
+	}
+	Some(low)
+}
+
+/// The square root of `price`, rounded down.
+pub fn sqrt_price_of(price: FixedU128) -> Option<U256> {
+	let scaled = (U512::from(price.into_inner()) << 192) / U512::from(FixedU128::DIV);
+	U256::try_from(scaled.integer_sqrt()).ok()
+}
+
+/// The price of the square root `sqrt_price`, rounded down.
+pub fn price_of(sqrt_price: U256) -> Option<FixedU128> {
+	let price = (sqrt_price.full_mul(sqrt_price) * U512::from(FixedU128::DIV)) >> 192;
+	u128::try_from(price).ok().map(FixedU128::from_inner)
+}
+
+/// The amount of the first asset `liquidity` holds between two prices:
+/// `L * (sqrt(Pb) - sqrt(Pa)) / (sqrt(Pa) * sqrt(Pb))`.
+pub fn amount_1_delta(
+	sqrt_price_a: U256,
+	sqrt_price_b: U256,
+	liquidity: u128,
+	round_up: bool,
+) -> Option<U256> {
+	let (lower, upper) = (sqrt_price_a.min(sqrt_price_b), sqrt_price_a.max(sqrt_price_b));
+	if lower.is_zero() {
+		return None
+	}
+	let per_upper = mul_div(U256::from(liquidity) << 96, upper - lower, upper, round_up)?;
+	div(per_upper.into(), lower.into(), round_up)
+}
+
+/// The amount of the second asset `liquidity` holds between two prices:
+/// `L * (sqrt(Pb) - sqrt(Pa))`.
+pub fn amount_2_delta(
+	sqrt_price_a: U256,
+	sqrt_price_b: U256,
+	liquidity: u128,
+	round_up: bool,
+) -> Option<U256> {
+	let (lower, upper) = (sqrt_price_a.min(sqrt_price_b), sqrt_price_a.max(sqrt_price_b));
+	mul_div(U256::from(liquidity), upper - lower, Q96, round_up)
+}
+
+/// The amounts of the first and second asset `liquidity` between `sqrt_price_lower` and
+/// `sqrt_price_upper` holds at `sqrt_price`. Below the range it is all in the first asset, and
+/// above it all in the second.
+pub fn amounts_for_liquidity(
+	sqrt_price: U256,
+	sqrt_price_lower: U256,
+	sqrt_price_upper: U256,
+	liquidity: u128,
+	round_up: bool,
+) -> Option<(U256, U256)> {
+	let sqrt_price = sqrt_price.max(sqrt_price_lower).min(sqrt_price_upper);
+	Some((
+		amount_1_delta(sqrt_price, sqrt_price_upper, liquidity, round_up)?,
+		amount_2_delta(sqrt_price_lower, sqrt_price, liquidity, round_up)?,
+	))
+}
+
+// the price after `amount` of the first asset is paid in, or taken out if not `add`, rounded up
+// so it moves less: `L * sqrt(P) / (L ± Δx * sqrt(P))`
+fn next_sqrt_price_from_amount_1(
+	sqrt_price: U256,
+	liquidity: u128,
+	amount: U256,
+	add: bool,
+) -> Option<U256> {
+	if amount.is_zero() {
+		return Some(sqrt_price)
+	}
+	let numerator = U512::from(U256::from(liquidity) << 96);
+	let product = amount.full_mul(sqrt_price);
+	let denominator =
+		if add { numerator.checked_add(product)? } else { numerator.checked_sub(product)? };
+	div(numerator.checked_mul(sqrt_price.into())?, denominator, true)
+}
+
+// the price after `amount` of the second asset is paid in, or taken out if not `add`, rounded
+// down so it moves less: `sqrt(P) ± Δy / L`
+fn next_sqrt_price_from_amount_2(
+	sqrt_price: U256,
+	liquidity: u128,
+	amount: U256,
+	add: bool,
+) -> Option<U256> {
+	if add {
+		sqrt_price.checked_add(mul_div(amount, Q96, U256::from(liquidity), false)?)
+	} else {
+		sqrt_price.checked_sub(mul_div(amount, Q96, U256::from(liquidity), true)?)
+	}
+}
+
+/// Works out a step of a swap, from `sqrt_price` towards `sqrt_price_target` along `liquidity`,
+/// that pays in, or takes out if not `exact_in`, at most `amount_remaining`. The first asset is
+/// paid in if the target is below the price. Paying in, `amount_remaining` includes the fee of
+/// `fee_tier`.
+pub fn swap_step(
+	sqrt_price: U256,
+	sqrt_price_target: U256,
+	liquidity: u128,
+	amount_remaining: U256,
+	fee_tier: u16,
+	exact_in: bool,
+) -> Option<SwapStep> {
+	let first_in = sqrt_price >= sqrt_price_target;
+	let fee_denominator = U256::from(10_000u32);
+	let fee_complement = fee_denominator.checked_sub(U256::from(fee_tier))?;
+	let amount_in_to = |to: U256, round_up: bool| {
+		if first_in {
+			amount_1_delta(to, sqrt_price, liquidity, round_up)
+		} else {
+			amount_2_delta(sqrt_price, to, liquidity, round_up)
+		}
+	};
+	let amount_out_to = |to: U256| {
+		if first_in {
+			amount_2_delta(to, sqrt_price, liquidity, false)
+		} else {
+			amount_1_delta(sqrt_price, to, liquidity, false)
+		}
+	};
+
+	let (next, reached) = if exact_in {
+		let remaining_less_fee = mul_div(amount_remaining, fee_complement, fee_denominator, false)?;
+		if remaining_less_fee >= amount_in_to(sqrt_price_target, true)? {
+			(sqrt_price_target, true)
+		} else if first_in {
+			(next_sqrt_price_from_amount_1(sqrt_price, liquidity, remaining_less_fee, true)?, false)
+		} else {
+			(next_sqrt_price_from_amount_2(sqrt_price, liquidity, remaining_less_fee, true)?, false)
+		}
+	} else if amount_remaining >= amount_out_to(sqrt_price_target)? {
+		(sqrt_price_target, true)
+	} else if first_in {
+		(next_sqrt_price_from_amount_2(sqrt_price, liquidity, amount_remaining, false)?, false)
+	} else {
+		(next_sqrt_price_from_amount_1(sqrt_price, liquidity, amount_remaining, false)?, false)
+	};
+
+	let amount_in = amount_in_to(next, true)?;
+	let amount_out = amount_out_to(next)?;
+	let amount_out = if exact_in { amount_out } else { amount_out.min(amount_remaining) };
+	// what is left of an input that doesn't reach the target is the fee, so it is all paid in
+	let fee = if exact_in && !reached {
+		amount_remaining.checked_sub(amount_in)?
+	} else {
+		mul_div(amount_in, U256::from(fee_tier), fee_complement, true)?
+	};
+	Some(SwapStep { sqrt_price: next, reached, amount_in, amount_out, fee })
+}
+
+/// The fees earned per unit of `liquidity` for a `fee`.
+pub fn fee_growth(fee: U256, liquidity: u128) -> Option<U256> {
+	mul_div(fee, Q128, U256::from(liquidity), false)
+}
+
+/// The fees `liquidity` earned while the fees per unit of liquidity grew by `growth`.
+pub fn fees_earned(growth: U256, liquidity: u128) -> Option<U256> {
+	mul_div(growth, U256::from(liquidity), Q128, false)
+}
+
+/// `liquidity` changed by `delta`, if it stays in range.
+pub fn add_liquidity_delta(liquidity: u128, delta: i128) -> Option<u128> {
+	if delta >= 0 {
+		liquidity.checked_add(delta.unsigned_abs())
+	} else {
+		liquidity.checked_sub(delta.unsigned_abs())
+	}
+}
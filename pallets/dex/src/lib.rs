@@ -5,14 +5,30 @@ use frame_support::{
 	pallet_prelude::*,
 	sp_runtime::{
 		traits::{
-			AccountIdConversion, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Hash,
-			IntegerSquareRoot, One, TrailingZeroInput,
+			AccountIdConversion, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, IntegerSquareRoot,
+			One, Saturating, Zero,
 		},
-		ArithmeticError, Percent,
+		ArithmeticError, FixedPointNumber, FixedU128, Perbill, Permill, RuntimeDebug,
+		SaturatedConversion,
+	},
+	traits::{
+		fungible,
+		fungibles::{self, Create, Inspect, Mutate},
+		tokens::{
+			DepositConsequence,
+			Fortitude::Polite,
+			Precision::Exact,
+			Preservation::{self, Expendable, Protect},
+			Provenance,
+		},
+		UnixTime,
 	},
-	traits::fungibles::{self, Create, Inspect, Mutate},
 };
 pub use pallet::*;
+use scale_info::prelude::{format, vec};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::U256;
 
 #[cfg(test)]
 mod mock;
@@ -22,23 +38,111 @@ mod tests;
 
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+pub mod weights;
+pub use weights::WeightInfo;
+
+pub mod migrations;
+
+mod check_deadline;
+pub use check_deadline::CheckDexDeadline;
+
+mod native_or_asset;
+pub use native_or_asset::{NativeOrAsset, NativeOrAssetUnion};
+
+mod stable_swap;
+pub use stable_swap::Amplification;
+
+mod weighted_math;
+
+mod concentrated_math;
+pub use concentrated_math::Tick;
+
+// the most bytes of each asset's symbol used in the name and symbol of the LP token of a pair,
+// which keeps them within the string limit of `pallet_assets`. The assets of a larger pool share
+// as many bytes as the two of a pair would take
+const MAX_LP_SYMBOL_PART: usize = 12;
+
+// the most a single ramp can multiply or divide the amplification coefficient of a StableSwap
+// pool by
+const MAX_AMPLIFICATION_CHANGE: u32 = 10;
+
+// the lightest an asset of a weighted pool can be, which bounds the exponents of its maths
+const MIN_WEIGHT: Perbill = Perbill::from_percent(1);
+
+/// Borrows flash loans taken without a call, so flash loan strategies can be written as pallets.
+/// The runtime picks the receiver through `Config::FlashLoanReceiver`.
+pub trait FlashLoanReceiver<AccountId, AssetKind, Balance> {
+	/// The most weight `on_flash_loan` can use, charged up front.
+	fn max_weight() -> Weight;
+
+	/// Called once `amount` of `asset` has been lent to `borrower`. Before it returns, `amount`
+	/// plus `fee` must be paid into the DEX's account. Returns the weight it actually used.
+	fn on_flash_loan(
+		borrower: &AccountId,
+		asset: &AssetKind,
+		amount: Balance,
+		fee: Balance,
+	) -> Result<Weight, DispatchError>;
+}
+
+// without a receiver, flash loans need a call
+impl<AccountId, AssetKind, Balance> FlashLoanReceiver<AccountId, AssetKind, Balance> for () {
+	fn max_weight() -> Weight {
+		Weight::zero()
+	}
+
+	fn on_flash_loan(
+		_borrower: &AccountId,
+		_asset: &AssetKind,
+		_amount: Balance,
+		_fee: Balance,
+	) -> Result<Weight, DispatchError> {
+		Err(DispatchError::Unavailable)
+	}
+}
+
+/// Creates the asset ids the benchmarks trade, so they don't depend on the runtime's asset id type.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AssetId> {
+	/// Returns the asset id for `id`.
+	fn asset_id(id: u32) -> AssetId;
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl<AssetId: From<u32>> BenchmarkHelper<AssetId> for () {
+	fn asset_id(id: u32) -> AssetId {
+		id.into()
+	}
+}
 
 #[frame_support::pallet]
 pub mod pallet {
-	use crate::ArithmeticError;
+	use crate::{
+		concentrated_math, Amplification, ArithmeticError, FlashLoanReceiver, NativeOrAsset,
+		NativeOrAssetUnion, Tick, WeightInfo, MAX_AMPLIFICATION_CHANGE, MIN_WEIGHT,
+	};
 	use frame_support::{
-		dispatch::Dispatchable,
+		dispatch::{extract_actual_weight, Dispatchable, GetDispatchInfo, PostDispatchInfo, Vec},
 		pallet_prelude::*,
-		sp_runtime::{traits::CheckedMul, Percent},
+		sp_runtime::{
+			traits::{CheckedAdd, CheckedSub, Convert, SaturatedConversion, Saturating, Zero},
+			FixedPointNumber, FixedU128, Perbill, Permill,
+		},
 		traits::{
 			fungible,
-			fungibles::{self, Create, Inspect, Mutate},
-			tokens::{Fortitude::Force, Precision::BestEffort, Preservation::*},
+			fungibles::{self, Inspect, Mutate},
+			tokens::{Fortitude::Polite, Precision::BestEffort, Preservation::*},
+			Contains, UnixTime,
 		},
 	};
 	use frame_system::pallet_prelude::*;
-	use scale_info::prelude::boxed::Box;
+	use scale_info::prelude::{boxed::Box, vec};
+	use sp_core::U256;
+	/// The current storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(3);
+
 	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
@@ -47,10 +151,20 @@ pub mod pallet {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
-		/// Type to access the Balances Pallet.
-		type NativeBalance: fungible::Inspect<Self::AccountId>
+		/// The runtime's call type, dispatched by flash loans.
+		type RuntimeCall: Parameter
+			+ Dispatchable<RuntimeOrigin = Self::RuntimeOrigin, PostInfo = PostDispatchInfo>
+			+ GetDispatchInfo
+			+ From<frame_system::Call<Self>>;
+
+		/// The overarching hold reason, the pool creation deposit is held with.
+		type RuntimeHoldReason: From<HoldReason>;
+
+		/// Type to access the Balances Pallet. Pools pair assets with the native currency through
+		/// it, so it shares the balance type of `Fungibles`.
+		type NativeBalance: fungible::Inspect<Self::AccountId, Balance = AssetBalanceOf<Self>>
 			+ fungible::Mutate<Self::AccountId>
-			+ fungible::hold::Inspect<Self::AccountId>
+			+ fungible::hold::Inspect<Self::AccountId, Reason = Self::RuntimeHoldReason>
 			+ fungible::hold::Mutate<Self::AccountId>
 			+ fungible::freeze::Inspect<Self::AccountId>
 			+ fungible::freeze::Mutate<Self::AccountId>;
@@ -58,7 +172,10 @@ pub mod pallet {
 		/// Type to access the Assets Pallet.
 		type Fungibles: fungibles::Inspect<Self::AccountId>
 			+ fungibles::Mutate<Self::AccountId>
-			+ fungibles::Create<Self::AccountId>;
+			+ fungibles::Create<Self::AccountId>
+			+ fungibles::Destroy<Self::AccountId>
+			+ fungibles::metadata::Mutate<Self::AccountId>
+			+ fungibles::metadata::MetadataDeposit<AssetBalanceOf<Self>>;
 
 		type PermissionOrigin: EnsureOrigin<Self::RuntimeOrigin>;
 
@@ -66,8 +183,122 @@ pub mod pallet {
 		#[pallet::constant]
 		type PalletId: Get<frame_support::PalletId>;
 
-		// type RuntimeCall: Parameter + Dispatchable<RuntimeOrigin = Self::RuntimeOrigin> +
-		// GetDispatchInfo;
+		/// The maximum number of assets in a multi-hop swap path, including both ends.
+		#[pallet::constant]
+		type MaxPathLength: Get<u32>;
+
+		/// Source of the current time for the TWAP oracle, usually `pallet_timestamp`.
+		type Timestamp: UnixTime;
+
+		/// The number of price observations kept per pool for TWAP queries.
+		#[pallet::constant]
+		type MaxObservations: Get<u32>;
+
+		/// The fee tier, in basis points, used for flash loans and by `DexInterface`. It is allowed
+		/// by default alongside 5, 30 and 100.
+		#[pallet::constant]
+		type DefaultFeeTier: Get<u16>;
+
+		/// The maximum number of fee tiers governance can allow at once.
+		#[pallet::constant]
+		type MaxFeeTiers: Get<u32>;
+
+		/// The LP tokens permanently locked when a pool is created, so its supply can never
+		/// return to zero. Should not be zero.
+		#[pallet::constant]
+		type MinimumLiquidity: Get<AssetBalanceOf<Self>>;
+
+		/// The number of blocks claimed farming rewards vest over linearly. Zero pays them out
+		/// at once.
+		#[pallet::constant]
+		type RewardVestingPeriod: Get<BlockNumberFor<Self>>;
+
+		/// The number of decimals of the native currency, used by the price oracles.
+		#[pallet::constant]
+		type NativeDecimals: Get<u8>;
+
+		/// The symbol of the native currency, used in the metadata of LP tokens.
+		#[pallet::constant]
+		type NativeSymbol: Get<Vec<u8>>;
+
+		/// The minimum balance of the LP token of a new pool. It should be small, as no account
+		/// can hold fewer LP tokens of the pool than this.
+		#[pallet::constant]
+		type LpMinBalance: Get<AssetBalanceOf<Self>>;
+
+		/// Turns the index of a new LP token, counting up from zero, into its asset id. The ids
+		/// should be reserved for LP tokens, so no one can create an asset with the id of a
+		/// future pool's LP token.
+		type LpAssetId: Convert<u32, AssetIdOf<Self>>;
+
+		/// The calls a borrower may dispatch with a flash loan. DEX calls that change its state
+		/// are refused while a loan is out regardless.
+		type FlashLoanCallFilter: Contains<<Self as Config>::RuntimeCall>;
+
+		/// The origin that can pause, freeze and reset pools.
+		type PauseOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The number of blocks the circuit breaker measures a pool's price move over.
+		#[pallet::constant]
+		type PriceMoveWindow: Get<BlockNumberFor<Self>>;
+
+		/// How far swaps may move a pool's price within a `PriceMoveWindow` before its circuit
+		/// breaker trips, pausing swaps with it after the swap that moved it that far. Zero
+		/// turns the check off.
+		#[pallet::constant]
+		type MaxPriceMove: Get<Permill>;
+
+		/// How far a single swap may move a pool's price. Swaps moving it further fail. Zero
+		/// turns the check off.
+		#[pallet::constant]
+		type MaxPriceImpact: Get<Permill>;
+
+		/// The native currency held from whoever creates a pool, until the pool is destroyed.
+		#[pallet::constant]
+		type PoolCreationDeposit: Get<AssetBalanceOf<Self>>;
+
+		/// The maximum number of pools that can exist at once.
+		#[pallet::constant]
+		type MaxPools: Get<u32>;
+
+		/// The maximum number of assets in a StableSwap pool.
+		#[pallet::constant]
+		type MaxStableAssets: Get<u32>;
+
+		/// The highest amplification coefficient a StableSwap pool can have.
+		#[pallet::constant]
+		type MaxAmplification: Get<u32>;
+
+		/// The fewest blocks the amplification coefficient of a StableSwap pool can be ramped
+		/// over, so it can't be moved faster than arbitrageurs can follow.
+		#[pallet::constant]
+		type MinAmplificationRamp: Get<BlockNumberFor<Self>>;
+
+		/// The origin that can ramp the amplification coefficient of StableSwap pools.
+		type AmplificationOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The maximum number of assets in a weighted pool.
+		#[pallet::constant]
+		type MaxWeightedAssets: Get<u32>;
+
+		/// The maximum number of initialized ticks in a concentrated liquidity pool, which
+		/// bounds the ticks a swap with it can cross. Every position initializes up to two.
+		#[pallet::constant]
+		type MaxTicks: Get<u32>;
+
+		/// Handles flash loans taken without a call.
+		type FlashLoanReceiver: FlashLoanReceiver<
+			Self::AccountId,
+			AssetKindOf<Self>,
+			AssetBalanceOf<Self>,
+		>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Creates the asset ids used by the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: crate::BenchmarkHelper<AssetIdOf<Self>>;
 	}
 
 	// gives us access to the asset id and balance types of the fungibles
@@ -77,21 +308,23 @@ pub mod pallet {
 	pub type AssetBalanceOf<T> = <<T as Config>::Fungibles as fungibles::Inspect<
 		<T as frame_system::Config>::AccountId,
 	>>::Balance;
+	// an asset pools can be made of, the native currency or one of the fungibles
+	pub type AssetKindOf<T> = NativeOrAsset<AssetIdOf<T>>;
 
 	// Stores Pool pairs in sorted order
 	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
 	#[scale_info(skip_type_params(T))]
 	pub struct PoolPair<T: Config> {
-		pub asset_1: AssetIdOf<T>,
+		pub asset_1: AssetKindOf<T>,
 		pub amount_1: AssetBalanceOf<T>,
-		pub asset_2: AssetIdOf<T>,
+		pub asset_2: AssetKindOf<T>,
 		pub amount_2: AssetBalanceOf<T>,
 	}
 	impl<T: Config> PoolPair<T> {
 		pub fn new(
-			asset_a: AssetIdOf<T>,
+			asset_a: AssetKindOf<T>,
 			amount_a: AssetBalanceOf<T>,
-			asset_b: AssetIdOf<T>,
+			asset_b: AssetKindOf<T>,
 			amount_b: AssetBalanceOf<T>,
 		) -> Result<Self, &'static str> {
 			if asset_a == asset_b {
@@ -116,18 +349,423 @@ pub mod pallet {
 	}
 
 	/// STORAGE DEFINED HERE
-	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
 	#[scale_info(skip_type_params(T))]
 	pub struct Pool<T: Config> {
 		// stores the asset ids and balances of the two assets in the pool in sorted order
 		pub pool_pair: PoolPair<T>,
 
+		// Swap fee in basis points, chosen when the pool was created
+		pub fee_tier: u16,
+
 		// Total supply of the LP tokens
 		pub lp_supply: AssetBalanceOf<T>,
+
+		// Running sums of the price of `asset_1` in `asset_2` and of `asset_2` in `asset_1`,
+		// weighted by the seconds each price was held. Stored as raw `FixedU128` values and
+		// allowed to wrap, so only differences between two readings are meaningful
+		pub price_cumulative_1: u128,
+		pub price_cumulative_2: u128,
+
+		// Timestamp in seconds of the block the accumulators were last updated in
+		pub block_timestamp_last: u64,
+
+		// Product of the reserves after the last liquidity change while the protocol fee was on,
+		// zero while it is off
+		pub k_last: AssetBalanceOf<T>,
+
+		// Whether the pool can be swapped with and its liquidity changed
+		pub status: PoolStatus,
+
+		// The invariant the pool trades along, and the state only pools of that kind have
+		pub kind: PoolKind<T>,
 	}
 	impl<T: Config> Pool<T> {
-		pub fn new(pool_pair: PoolPair<T>, lp_supply: AssetBalanceOf<T>) -> Self {
-			Self { pool_pair: pool_pair.clone(), lp_supply }
+		pub fn new(
+			pool_pair: PoolPair<T>,
+			fee_tier: u16,
+			lp_supply: AssetBalanceOf<T>,
+			block_timestamp: u64,
+		) -> Self {
+			Self {
+				pool_pair,
+				fee_tier,
+				lp_supply,
+				price_cumulative_1: 0,
+				price_cumulative_2: 0,
+				block_timestamp_last: block_timestamp,
+				k_last: Default::default(),
+				status: PoolStatus::Active,
+				kind: PoolKind::ConstantProduct,
+			}
+		}
+
+		// the assets of the pool and their reserves, those of `pool_pair` first
+		pub fn reserves(&self) -> Vec<(AssetKindOf<T>, AssetBalanceOf<T>)> {
+			let pair = &self.pool_pair;
+			let mut reserves =
+				vec![(pair.asset_1.clone(), pair.amount_1), (pair.asset_2.clone(), pair.amount_2)];
+			match &self.kind {
+				PoolKind::ConstantProduct | PoolKind::Concentrated(_) => {},
+				PoolKind::StableSwap(stable) =>
+					reserves.extend(stable.more_reserves.iter().cloned()),
+				PoolKind::Weighted(weighted) =>
+					reserves.extend(weighted.more_reserves.iter().cloned()),
+			}
+			reserves
+		}
+
+		// the reserve of `asset`, if the pool holds it
+		pub fn reserve(&self, asset: &AssetKindOf<T>) -> Option<AssetBalanceOf<T>> {
+			self.reserves()
+				.into_iter()
+				.find(|(reserve_asset, _)| reserve_asset == asset)
+				.map(|(_, amount)| amount)
+		}
+
+		pub fn contains(&self, asset: &AssetKindOf<T>) -> bool {
+			self.reserve(asset).is_some()
+		}
+
+		// sets the reserve of `asset`, failing if the pool doesn't hold it
+		pub fn set_reserve(
+			&mut self,
+			asset: &AssetKindOf<T>,
+			amount: AssetBalanceOf<T>,
+		) -> Result<(), Error<T>> {
+			let pair = &mut self.pool_pair;
+			if pair.asset_1 == *asset {
+				pair.amount_1 = amount;
+				return Ok(())
+			}
+			if pair.asset_2 == *asset {
+				pair.amount_2 = amount;
+				return Ok(())
+			}
+			let is_asset = |(reserve_asset, _): &&mut (AssetKindOf<T>, AssetBalanceOf<T>)| {
+				reserve_asset == asset
+			};
+			let (_, reserve) = match &mut self.kind {
+				PoolKind::ConstantProduct | PoolKind::Concentrated(_) => None,
+				PoolKind::StableSwap(stable) => stable.more_reserves.iter_mut().find(is_asset),
+				PoolKind::Weighted(weighted) => weighted.more_reserves.iter_mut().find(is_asset),
+			}
+			.ok_or(Error::<T>::AssetNotInPool)?;
+			*reserve = amount;
+			Ok(())
+		}
+
+		// the normalized weight of `asset` in the pool, if it is a weighted pool holding it. The
+		// two assets of a constant product pool weigh half each
+		pub fn weight(&self, asset: &AssetKindOf<T>) -> Option<Perbill> {
+			let index =
+				self.reserves().iter().position(|(reserve_asset, _)| reserve_asset == asset)?;
+			match &self.kind {
+				PoolKind::ConstantProduct => Some(Perbill::from_percent(50)),
+				PoolKind::StableSwap(_) | PoolKind::Concentrated(_) => None,
+				PoolKind::Weighted(weighted) => weighted.weights.get(index).copied(),
+			}
+		}
+	}
+
+	// The invariant a pool trades along
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+	#[scale_info(skip_type_params(T))]
+	pub enum PoolKind<T: Config> {
+		// Uniswap V2's `x * y = k` over `pool_pair`
+		ConstantProduct,
+		// Curve's StableSwap invariant, for assets that should trade close to 1:1
+		StableSwap(StablePool<T>),
+		// Balancer's weighted product invariant, for assets held at fixed shares of the pool's
+		// value. A constant product pool is the same curve with two assets at 50/50, kept with
+		// its own integer maths
+		Weighted(WeightedPool<T>),
+		// Uniswap V3's concentrated liquidity, provided over ranges of prices by positions
+		// instead of LP tokens
+		Concentrated(ConcentratedPool<T>),
+	}
+
+	// The state of a StableSwap pool beyond its first two assets, which are in `pool_pair`
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+	#[scale_info(skip_type_params(T))]
+	pub struct StablePool<T: Config> {
+		// The assets after the first two and their reserves, in sorted order
+		pub more_reserves: BoundedVec<(AssetKindOf<T>, AssetBalanceOf<T>), T::MaxStableAssets>,
+
+		// The amplification coefficient, ramped by the `AmplificationOrigin`
+		pub amplification: Amplification<BlockNumberFor<T>>,
+	}
+
+	// The state of a weighted pool beyond the reserves of its first two assets, which are in
+	// `pool_pair`
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+	#[scale_info(skip_type_params(T))]
+	pub struct WeightedPool<T: Config> {
+		// The assets after the first two and their reserves, in sorted order
+		pub more_reserves: BoundedVec<(AssetKindOf<T>, AssetBalanceOf<T>), T::MaxWeightedAssets>,
+
+		// The weight of every asset, in the order of `Pool::reserves`, adding up to 100%
+		pub weights: BoundedVec<Perbill, T::MaxWeightedAssets>,
+	}
+
+	// The state of a concentrated liquidity pool. Its reserves in `pool_pair` are what its
+	// positions hold and the fees they haven't collected yet
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+	#[scale_info(skip_type_params(T))]
+	pub struct ConcentratedPool<T: Config> {
+		// The square root of the price of `asset_1` in `asset_2`, as a Q64.96 number
+		pub sqrt_price: U256,
+
+		// The tick the price is in, the highest whose price is at most the current one. A price
+		// that crossed down onto a tick is in the tick below
+		pub tick: i32,
+
+		// The liquidity of the positions whose range holds the price
+		pub liquidity: u128,
+
+		// The fees earned per unit of liquidity since the pool was created, in `asset_1` and
+		// `asset_2`, as Q128.128 numbers allowed to wrap
+		pub fee_growth_global_1: U256,
+		pub fee_growth_global_2: U256,
+
+		// The ticks bounding positions, in ascending order. Keeping them in the pool lets a swap
+		// cross them without touching any other storage
+		pub ticks: BoundedVec<(i32, Tick), T::MaxTicks>,
+	}
+	impl<T: Config> ConcentratedPool<T> {
+		pub fn new(sqrt_price: U256, tick: i32) -> Self {
+			Self {
+				sqrt_price,
+				tick,
+				liquidity: 0,
+				fee_growth_global_1: U256::zero(),
+				fee_growth_global_2: U256::zero(),
+				ticks: BoundedVec::default(),
+			}
+		}
+
+		// the initialized tick `tick`
+		pub fn tick_info(&self, tick: i32) -> Option<&Tick> {
+			self.ticks.iter().find(|(index, _)| *index == tick).map(|(_, info)| info)
+		}
+
+		// the fees earned per unit of liquidity while the price was between `lower` and
+		// `upper`, in `asset_1` and `asset_2`. Only differences between two readings are
+		// meaningful
+		pub fn fee_growth_inside(&self, lower: i32, upper: i32) -> (U256, U256) {
+			let lower_info = self.tick_info(lower).cloned().unwrap_or_default();
+			let upper_info = self.tick_info(upper).cloned().unwrap_or_default();
+			let inside = |global: U256, lower_outside: U256, upper_outside: U256| {
+				let below = if self.tick >= lower {
+					lower_outside
+				} else {
+					global.overflowing_sub(lower_outside).0
+				};
+				let above = if self.tick < upper {
+					upper_outside
+				} else {
+					global.overflowing_sub(upper_outside).0
+				};
+				global.overflowing_sub(below).0.overflowing_sub(above).0
+			};
+			(
+				inside(
+					self.fee_growth_global_1,
+					lower_info.fee_growth_outside_1,
+					upper_info.fee_growth_outside_1,
+				),
+				inside(
+					self.fee_growth_global_2,
+					lower_info.fee_growth_outside_2,
+					upper_info.fee_growth_outside_2,
+				),
+			)
+		}
+
+		// adds `liquidity_delta` to the positions `tick` is the lower bound of, or the upper
+		// bound if `upper`, initializing the tick or dropping it when no position is left
+		pub fn update_tick(
+			&mut self,
+			tick: i32,
+			liquidity_delta: i128,
+			upper: bool,
+		) -> Result<(), DispatchError> {
+			let index = match self.ticks.binary_search_by_key(&tick, |(index, _)| *index) {
+				Ok(index) => index,
+				Err(index) => {
+					// the fees so far count as earned below a tick the price is above, which
+					// positions only ever take differences of
+					let (fee_growth_outside_1, fee_growth_outside_2) = if tick <= self.tick {
+						(self.fee_growth_global_1, self.fee_growth_global_2)
+					} else {
+						(U256::zero(), U256::zero())
+					};
+					let info =
+						Tick { fee_growth_outside_1, fee_growth_outside_2, ..Default::default() };
+					self.ticks
+						.try_insert(index, (tick, info))
+						.map_err(|_| Error::<T>::TooManyTicks)?;
+					index
+				},
+			};
+			let (_, info) = self.ticks.get_mut(index).ok_or(Error::<T>::NoneValue)?;
+			info.liquidity_gross =
+				concentrated_math::add_liquidity_delta(info.liquidity_gross, liquidity_delta)
+					.ok_or(ArithmeticError::Overflow)?;
+			info.liquidity_net = if upper {
+				info.liquidity_net.checked_sub(liquidity_delta)
+			} else {
+				info.liquidity_net.checked_add(liquidity_delta)
+			}
+			.ok_or(ArithmeticError::Overflow)?;
+			if info.liquidity_gross == 0 {
+				self.ticks.remove(index);
+			}
+			Ok(())
+		}
+
+		// crosses `tick` as the price moves over it, so the fees earned outside it become those
+		// earned on the side the price leaves, and returns the liquidity it adds going up
+		pub fn cross(&mut self, tick: i32) -> i128 {
+			let (global_1, global_2) = (self.fee_growth_global_1, self.fee_growth_global_2);
+			match self.ticks.iter_mut().find(|(index, _)| *index == tick) {
+				Some((_, info)) => {
+					info.fee_growth_outside_1 =
+						global_1.overflowing_sub(info.fee_growth_outside_1).0;
+					info.fee_growth_outside_2 =
+						global_2.overflowing_sub(info.fee_growth_outside_2).0;
+					info.liquidity_net
+				},
+				None => 0,
+			}
+		}
+	}
+
+	// A position of concentrated liquidity, held by the account it is stored under
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Position<T: Config> {
+		// The pool of the position
+		pub lp_id: AssetIdOf<T>,
+
+		// The range of ticks the position provides liquidity over, and earns fees in
+		pub tick_lower: i32,
+		pub tick_upper: i32,
+
+		// The liquidity the position provides
+		pub liquidity: u128,
+
+		// The fees earned per unit of liquidity in the range when the position was last updated
+		pub fee_growth_inside_1: U256,
+		pub fee_growth_inside_2: U256,
+
+		// The fees earned up to the last update and not collected yet
+		pub fees_owed_1: AssetBalanceOf<T>,
+		pub fees_owed_2: AssetBalanceOf<T>,
+	}
+
+	// What a pool is open for, set by the `PauseOrigin` or by the circuit breaker
+	#[derive(
+		Encode, Decode, MaxEncodedLen, TypeInfo, Clone, Copy, PartialEq, Eq, RuntimeDebug, Default,
+	)]
+	pub enum PoolStatus {
+		// Open for everything
+		#[default]
+		Active,
+		// Liquidity can be added and removed, but nothing can be swapped
+		SwapsPaused,
+		// Nothing can be done with the pool
+		Frozen,
+	}
+
+	// A snapshot of a pool's price accumulators, used to compute a TWAP against the present
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, RuntimeDebug)]
+	pub struct Observation {
+		pub timestamp: u64,
+		pub price_cumulative_1: u128,
+		pub price_cumulative_2: u128,
+	}
+
+	// Liquidity mining of the LP token of a pool, configured by governance
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Farm<T: Config> {
+		// Asset the rewards are paid in, out of `Pallet::rewards_account`
+		pub reward_asset: AssetKindOf<T>,
+
+		// Rewards shared between the stakers every block
+		pub reward_per_block: AssetBalanceOf<T>,
+
+		// LP tokens staked in the farm
+		pub total_staked: AssetBalanceOf<T>,
+
+		// Rewards earned by a single staked LP token since the farm was started
+		pub reward_per_share: FixedU128,
+
+		// Block `reward_per_share` was last brought up to date in
+		pub last_update: BlockNumberFor<T>,
+	}
+	impl<T: Config> Farm<T> {
+		pub fn new(
+			reward_asset: AssetKindOf<T>,
+			reward_per_block: AssetBalanceOf<T>,
+			block_number: BlockNumberFor<T>,
+		) -> Self {
+			Self {
+				reward_asset,
+				reward_per_block,
+				total_staked: Zero::zero(),
+				reward_per_share: FixedU128::zero(),
+				last_update: block_number,
+			}
+		}
+	}
+
+	// LP tokens an account has staked in a farm, which stay frozen in its account
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Stake<T: Config> {
+		pub amount: AssetBalanceOf<T>,
+
+		// `reward_per_share` of the farm when the rewards of the stake were last added to
+		// `pending`
+		pub reward_per_share_paid: FixedU128,
+
+		// Rewards earned and not claimed yet
+		pub pending: AssetBalanceOf<T>,
+	}
+	impl<T: Config> Stake<T> {
+		pub fn new(reward_per_share: FixedU128) -> Self {
+			Self {
+				amount: Zero::zero(),
+				reward_per_share_paid: reward_per_share,
+				pending: Zero::zero(),
+			}
+		}
+	}
+
+	// Claimed rewards released linearly over `RewardVestingPeriod` blocks from `start`
+	#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+	#[scale_info(skip_type_params(T))]
+	pub struct VestingSchedule<T: Config> {
+		pub total: AssetBalanceOf<T>,
+		pub released: AssetBalanceOf<T>,
+		pub start: BlockNumberFor<T>,
+	}
+	impl<T: Config> VestingSchedule<T> {
+		pub fn new(block_number: BlockNumberFor<T>) -> Self {
+			Self { total: Zero::zero(), released: Zero::zero(), start: block_number }
+		}
+
+		// the part of `total` vested by `now`
+		pub fn vested(&self, now: BlockNumberFor<T>) -> AssetBalanceOf<T> {
+			let period = T::RewardVestingPeriod::get();
+			let elapsed = now.saturating_sub(self.start);
+			if elapsed >= period {
+				return self.total
+			}
+			Perbill::from_rational(elapsed.saturated_into::<u64>(), period.saturated_into::<u64>())
+				.mul_floor(self.total)
 		}
 	}
 
@@ -137,16 +775,120 @@ pub mod pallet {
 	pub type PoolMap<T> = StorageMap<_, Blake2_128Concat, AssetIdOf<T>, Pool<T>>;
 
 	#[pallet::storage]
-	pub type Fee<T> = StorageValue<_, u16, ValueQuery, FeeDefault>;
-	pub struct FeeDefault(u16);
-	impl Default for FeeDefault {
-		fn default() -> Self {
-			Self(3) // The default value is 50.
-		}
+	// Ring buffer of the most recent observations of every pool, at most one per block
+	pub type Observations<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		BoundedVec<Observation, T::MaxObservations>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	// The slot in `Observations` to overwrite next once the buffer of a pool is full
+	pub type ObservationIndex<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, u32, ValueQuery>;
+
+	#[pallet::storage]
+	// Fee tiers in basis points new pools can be created with, a pair can have one pool per tier
+	pub type FeeTiers<T: Config> =
+		StorageValue<_, BoundedVec<u16, T::MaxFeeTiers>, ValueQuery, FeeTiersDefault<T>>;
+
+	#[pallet::storage]
+	// Account the protocol's share of the LP fee growth is minted to, the fee is off while unset
+	pub type ProtocolFeeRecipient<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+
+	#[pallet::storage]
+	// Fraction of the LP fee growth minted to `ProtocolFeeRecipient`
+	pub type ProtocolFeeShare<T> = StorageValue<_, Perbill, ValueQuery>;
+
+	#[pallet::storage]
+	// Farms by the LP token staked in them
+	pub type Farms<T: Config> = StorageMap<_, Blake2_128Concat, AssetIdOf<T>, Farm<T>>;
+
+	#[pallet::storage]
+	// Stakes by LP token and staker, kept while either the stake or its pending rewards are
+	// non-zero
+	pub type Stakes<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AssetIdOf<T>,
+		Blake2_128Concat,
+		T::AccountId,
+		Stake<T>,
+	>;
+
+	#[pallet::storage]
+	// Claimed rewards still vesting, by reward asset and account
+	pub type RewardVesting<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		AssetKindOf<T>,
+		Blake2_128Concat,
+		T::AccountId,
+		VestingSchedule<T>,
+	>;
+
+	#[pallet::storage]
+	// Set while a flash loan or a flash swap is out, the DEX's state-changing calls can't be
+	// entered until it is repaid
+	pub type FlashLoanLock<T> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::storage]
+	// The block the current `PriceMoveWindow` of a pool started in and the pool's price of
+	// `asset_1` in `asset_2` before its first swap in it
+	pub type PriceWindows<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, (BlockNumberFor<T>, FixedU128)>;
+
+	#[pallet::storage]
+	// The number of pools in `PoolMap`, bounded by `MaxPools`
+	pub type PoolCount<T> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::storage]
+	// The account that created a pool and the deposit held from it, by LP token
+	pub type PoolDeposits<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, (T::AccountId, AssetBalanceOf<T>)>;
+
+	#[pallet::storage]
+	// The index `LpAssetId` turns into the asset id of the next LP token
+	pub type NextLpIndex<T> = StorageValue<_, u32, ValueQuery>;
+
+	#[pallet::storage]
+	// The LP token id of the pool of two assets, in `PoolPair` order, and a fee tier
+	pub type PairToLp<T: Config> =
+		StorageMap<_, Blake2_128Concat, (AssetKindOf<T>, AssetKindOf<T>, u16), AssetIdOf<T>>;
+
+	#[pallet::storage]
+	// The assets, in `PoolPair` order, and the fee tier of the pool of an LP token
+	pub type LpToPair<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetIdOf<T>, (AssetKindOf<T>, AssetKindOf<T>, u16)>;
+
+	#[pallet::storage]
+	// The positions of concentrated liquidity pools, by owner and position id
+	pub type Positions<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, u64, Position<T>>;
+
+	#[pallet::storage]
+	// The id of the next position minted
+	pub type NextPositionId<T> = StorageValue<_, u64, ValueQuery>;
+
+	// Reasons the DEX holds native currency for
+	#[pallet::composite_enum]
+	pub enum HoldReason {
+		// The deposit of a pool's creator, released when the pool is destroyed
+		PoolCreation,
 	}
-	impl frame_support::traits::Get<u16> for FeeDefault {
-		fn get() -> u16 {
-			Self::default().0
+
+	pub struct FeeTiersDefault<T>(PhantomData<T>);
+	impl<T: Config> frame_support::traits::Get<BoundedVec<u16, T::MaxFeeTiers>> for FeeTiersDefault<T> {
+		fn get() -> BoundedVec<u16, T::MaxFeeTiers> {
+			let mut fee_tiers = BoundedVec::new();
+			for fee_tier in [5, 30, 100, T::DefaultFeeTier::get()] {
+				if !fee_tiers.contains(&fee_tier) {
+					let _ = fee_tiers.try_push(fee_tier);
+				}
+			}
+			fee_tiers
 		}
 	}
 
@@ -160,60 +902,313 @@ pub mod pallet {
 			who: T::AccountId,
 		},
 
-		// Liquidity added to the pool
-		LiquidityAdded {
-			asset_a: AssetIdOf<T>,
-			asset_b: AssetIdOf<T>,
-			amount_a: AssetBalanceOf<T>,
-			amount_b: AssetBalanceOf<T>,
-			amount_lp: AssetBalanceOf<T>,
+		// A pool without liquidity was created, holding `deposit` from `who`
+		PoolCreated {
+			who: T::AccountId,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			lp_id: AssetIdOf<T>,
+			deposit: AssetBalanceOf<T>,
 		},
 
-		// Liquidity removed from the pool
-		LiquidityRemoved {
-			asset_a: AssetIdOf<T>,
-			asset_b: AssetIdOf<T>,
-			amount_a: AssetBalanceOf<T>,
-			amount_b: AssetBalanceOf<T>,
+		// A pool without liquidity and its LP token were destroyed, and `deposit` and what was
+		// left in the pool's account were released to `depositor`
+		PoolDestroyed {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			lp_id: AssetIdOf<T>,
+			depositor: T::AccountId,
+			deposit: AssetBalanceOf<T>,
 		},
 
-		// exchange rate between represented as a percent `asset_out` / `asset_in`
-		PriceOracleEvent {
-			rate: Percent,
-			asset_in: AssetIdOf<T>,
-			asset_out: AssetIdOf<T>,
+		// The `PauseOrigin` set the status of the pool of `lp_id`
+		PoolStatusChanged {
+			lp_id: AssetIdOf<T>,
+			status: PoolStatus,
 		},
 
-		// Swap event
-		SwapEvent {
-			asset_in: AssetIdOf<T>,
-			asset_out: AssetIdOf<T>,
-			amount_in: AssetBalanceOf<T>,
-			amount_out: AssetBalanceOf<T>,
+		// Swaps moved the price of the pool of `lp_id` further than `MaxPriceMove` within a
+		// `PriceMoveWindow`, so swaps with it were paused after the last of them
+		CircuitBreakerTripped {
+			lp_id: AssetIdOf<T>,
 		},
 
-		// Fee update
-		FeeUpdated {
-			new_fee: u16,
+		// What the account of the pool of `lp_id` held beyond its reserves was paid to `to`, in
+		// the pool's order of its assets
+		Skimmed {
+			lp_id: AssetIdOf<T>,
+			to: T::AccountId,
+			amounts: Vec<AssetBalanceOf<T>>,
 		},
 
-		// Flash loan event
-		FlashLoanEvent {
-			asset_id: AssetIdOf<T>,
-			amount: AssetBalanceOf<T>,
+		// The reserves of the pool of `lp_id` were set to what its account holds, in the pool's
+		// order of its assets
+		Synced {
+			lp_id: AssetIdOf<T>,
+			reserves: Vec<AssetBalanceOf<T>>,
 		},
-	}
-
-	#[pallet::error]
-	pub enum Error<T> {
-		/// Error names should be descriptive.
-		NoneValue,
 
-		// slippage too high
-		SlippageTooHigh,
+		// A StableSwap pool of `assets` without liquidity was created, holding `deposit` from
+		// `who`
+		StablePoolCreated {
+			who: T::AccountId,
+			assets: BoundedVec<AssetKindOf<T>, T::MaxStableAssets>,
+			fee_tier: u16,
+			amplification: u32,
+			lp_id: AssetIdOf<T>,
+			deposit: AssetBalanceOf<T>,
+		},
 
-		// Cannot create pool with the same asset
-		SameAsset,
+		// `amounts` were deposited into the StableSwap pool of `lp_id`, minting `amount_lp`
+		StableLiquidityAdded {
+			who: T::AccountId,
+			lp_id: AssetIdOf<T>,
+			amounts: Vec<(AssetKindOf<T>, AssetBalanceOf<T>)>,
+			amount_lp: AssetBalanceOf<T>,
+		},
+
+		// `amount_lp` was burned and paid out as `amounts` from the StableSwap pool of `lp_id`
+		StableLiquidityRemoved {
+			who: T::AccountId,
+			lp_id: AssetIdOf<T>,
+			amount_lp: AssetBalanceOf<T>,
+			amounts: Vec<(AssetKindOf<T>, AssetBalanceOf<T>)>,
+		},
+
+		// The amplification coefficient of the StableSwap pool of `lp_id` is ramped from
+		// `initial` now to `target` at block `end`
+		AmplificationRamped {
+			lp_id: AssetIdOf<T>,
+			initial: u32,
+			target: u32,
+			end: BlockNumberFor<T>,
+		},
+
+		// A weighted pool of `assets` with their weights, without liquidity, was created, holding
+		// `deposit` from `who`
+		WeightedPoolCreated {
+			who: T::AccountId,
+			assets: BoundedVec<(AssetKindOf<T>, Perbill), T::MaxWeightedAssets>,
+			fee_tier: u16,
+			lp_id: AssetIdOf<T>,
+			deposit: AssetBalanceOf<T>,
+		},
+
+		// `amounts` were deposited into the weighted pool of `lp_id`, minting `amount_lp`
+		WeightedLiquidityAdded {
+			who: T::AccountId,
+			lp_id: AssetIdOf<T>,
+			amounts: Vec<(AssetKindOf<T>, AssetBalanceOf<T>)>,
+			amount_lp: AssetBalanceOf<T>,
+		},
+
+		// `amount_lp` was burned and paid out as `amounts` from the weighted pool of `lp_id`
+		WeightedLiquidityRemoved {
+			who: T::AccountId,
+			lp_id: AssetIdOf<T>,
+			amount_lp: AssetBalanceOf<T>,
+			amounts: Vec<(AssetKindOf<T>, AssetBalanceOf<T>)>,
+		},
+
+		// A concentrated liquidity pool without positions was created at `price`, the price of
+		// `asset_a` in `asset_b`, holding `deposit` from `who`
+		ConcentratedPoolCreated {
+			who: T::AccountId,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			price: FixedU128,
+			lp_id: AssetIdOf<T>,
+			deposit: AssetBalanceOf<T>,
+		},
+
+		// `who` minted the position `position_id` of `liquidity` between two ticks of the pool
+		// of `lp_id`, paying in `amount_1` of its first asset and `amount_2` of its second
+		PositionMinted {
+			who: T::AccountId,
+			lp_id: AssetIdOf<T>,
+			position_id: u64,
+			tick_lower: i32,
+			tick_upper: i32,
+			liquidity: u128,
+			amount_1: AssetBalanceOf<T>,
+			amount_2: AssetBalanceOf<T>,
+		},
+
+		// `liquidity` of the position `position_id` of `who` was burned for `amount_1` of its
+		// pool's first asset and `amount_2` of its second
+		PositionBurned {
+			who: T::AccountId,
+			position_id: u64,
+			liquidity: u128,
+			amount_1: AssetBalanceOf<T>,
+			amount_2: AssetBalanceOf<T>,
+		},
+
+		// `who` collected the fees the position `position_id` earned, `amount_1` of its pool's
+		// first asset and `amount_2` of its second
+		FeesCollected {
+			who: T::AccountId,
+			position_id: u64,
+			amount_1: AssetBalanceOf<T>,
+			amount_2: AssetBalanceOf<T>,
+		},
+
+		// Liquidity added to the pool
+		LiquidityAdded {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			amount_a: AssetBalanceOf<T>,
+			amount_b: AssetBalanceOf<T>,
+			amount_lp: AssetBalanceOf<T>,
+		},
+
+		// Liquidity removed from the pool
+		LiquidityRemoved {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			amount_a: AssetBalanceOf<T>,
+			amount_b: AssetBalanceOf<T>,
+		},
+
+		// spot exchange rate in whole units of both assets, `rate` is the amount of `asset_out`
+		// one `asset_in` buys and `inverse_rate` the amount of `asset_in` one `asset_out` buys
+		PriceOracleEvent {
+			rate: FixedU128,
+			inverse_rate: FixedU128,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			fee_tier: u16,
+		},
+
+		// Swap event
+		SwapEvent {
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			fee_tier: u16,
+			amount_in: AssetBalanceOf<T>,
+			amount_out: AssetBalanceOf<T>,
+		},
+
+		// The fee tiers new pools can be created with were updated
+		FeeTiersUpdated {
+			fee_tiers: BoundedVec<u16, T::MaxFeeTiers>,
+		},
+
+		// Flash loan event, `fee` was credited to the pools holding `asset_id`
+		FlashLoanEvent {
+			asset_id: AssetKindOf<T>,
+			amount: AssetBalanceOf<T>,
+			fee: AssetBalanceOf<T>,
+		},
+
+		// Protocol fee update, `recipient` is `None` while the fee is off
+		ProtocolFeeUpdated {
+			recipient: Option<T::AccountId>,
+			share: Perbill,
+		},
+
+		// LP tokens minted to the protocol fee recipient for the fee growth of a pool
+		ProtocolFeeMinted {
+			lp_id: AssetIdOf<T>,
+			recipient: T::AccountId,
+			amount: AssetBalanceOf<T>,
+		},
+
+		// time weighted average price of `asset_in` in `asset_out` over at least `window` seconds
+		TwapOracleEvent {
+			rate: FixedU128,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			fee_tier: u16,
+			window: u64,
+		},
+
+		// Multi-hop swap summary, emitted after one `SwapEvent` per hop
+		PathSwapEvent {
+			path: BoundedVec<AssetKindOf<T>, T::MaxPathLength>,
+			fee_tiers: BoundedVec<u16, T::MaxPathLength>,
+			amount_in: AssetBalanceOf<T>,
+			amount_out: AssetBalanceOf<T>,
+		},
+
+		// `amount_in` of `asset_in` was partly swapped and deposited into its pool with
+		// `other_asset`, minting `amount_lp`
+		ZappedIn {
+			asset_in: AssetKindOf<T>,
+			other_asset: AssetKindOf<T>,
+			fee_tier: u16,
+			amount_in: AssetBalanceOf<T>,
+			amount_lp: AssetBalanceOf<T>,
+		},
+
+		// `amount_lp` was burned and paid out as `amount_out` of `asset_out` alone
+		ZappedOut {
+			asset_out: AssetKindOf<T>,
+			other_asset: AssetKindOf<T>,
+			fee_tier: u16,
+			amount_lp: AssetBalanceOf<T>,
+			amount_out: AssetBalanceOf<T>,
+		},
+
+		// The farm of `lp_id` was started or changed
+		FarmUpdated {
+			lp_id: AssetIdOf<T>,
+			reward_asset: AssetKindOf<T>,
+			reward_per_block: AssetBalanceOf<T>,
+		},
+
+		// LP tokens staked in a farm
+		LpStaked {
+			who: T::AccountId,
+			lp_id: AssetIdOf<T>,
+			amount: AssetBalanceOf<T>,
+		},
+
+		// LP tokens unstaked from a farm
+		LpUnstaked {
+			who: T::AccountId,
+			lp_id: AssetIdOf<T>,
+			amount: AssetBalanceOf<T>,
+		},
+
+		// `amount` of rewards claimed from a farm, `released` is what was paid out including
+		// earlier claims that have vested since
+		RewardsClaimed {
+			who: T::AccountId,
+			lp_id: AssetIdOf<T>,
+			reward_asset: AssetKindOf<T>,
+			amount: AssetBalanceOf<T>,
+			released: AssetBalanceOf<T>,
+		},
+
+		// `who` took the out amounts from a pool before its call and paid the in amounts after
+		FlashSwapped {
+			who: T::AccountId,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			amount_a_out: AssetBalanceOf<T>,
+			amount_b_out: AssetBalanceOf<T>,
+			amount_a_in: AssetBalanceOf<T>,
+			amount_b_in: AssetBalanceOf<T>,
+		},
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Error names should be descriptive.
+		NoneValue,
+
+		// slippage too high
+		SlippageTooHigh,
+
+		// Cannot create pool with the same asset
+		SameAsset,
 
 		// Trying to access a pool that doesn't exist
 		NoPool,
@@ -224,186 +1219,219 @@ pub mod pallet {
 		// Not allowed to set fee
 		NotAllowedToSetFee,
 
-		// Insufficient liquidity for flash loan
+		// The pools don't hold enough of the asset for a flash loan, a flash swap takes a whole
+		// reserve, or the pool has no liquidity to swap with or sync
 		InsufficientLiquidity,
 
-		// Insufficient repayment for flash loan
+		// Insufficient repayment for flash loan, or a flash swap that decreases the pool's
+		// product
 		InsufficientRepayment,
 
 		// Call failed
 		CallFailed,
+
+		// Swap path is shorter than two assets or uses the same pool twice
+		InvalidPath,
+
+		// TWAP window must be at least one second
+		InvalidTwapWindow,
+
+		// No stored observation is old enough to cover the TWAP window
+		NoObservationForWindow,
+
+		// Fee tier is not allowed for new pools, or is not below 10_000 basis points
+		InvalidFeeTier,
+
+		// A new pool must mint more LP tokens than the `MinimumLiquidity` it locks, and a deposit
+		// into an existing pool must mint some
+		InsufficientLiquidityMinted,
+
+		// Less than `amount_a_min` of `asset_a` would be deposited or withdrawn
+		InsufficientAmountA,
+
+		// Less than `amount_b_min` of `asset_b` would be deposited or withdrawn
+		InsufficientAmountB,
+
+		// Only the permission origin can start or change farms
+		NotAllowedToSetFarm,
+
+		// The LP token has no farm
+		NoFarm,
+
+		// Unstaking more LP tokens than are staked
+		InsufficientStake,
+
+		// A flash swap must take some of either asset out of the pool
+		InsufficientOutputAmount,
+
+		// The DEX can't be traded with or changed from within a flash loan
+		FlashLoanInProgress,
+
+		// The runtime's `FlashLoanCallFilter` doesn't allow the call to be made with a flash loan
+		CallNotAllowed,
+
+		// A pool of the pair already exists with that fee tier
+		PoolExists,
+
+		// There are already `MaxPools` pools
+		TooManyPools,
+
+		// Only a pool whose only LP tokens are the locked ones, and without positions or stakes,
+		// can be destroyed
+		PoolNotEmpty,
+
+		// Only the account that created the pool, or the `PauseOrigin`, can destroy it
+		NotPoolCreator,
+
+		// Swaps with the pool are paused
+		SwapsPaused,
+
+		// The pool is frozen
+		PoolFrozen,
+
+		// The swap would move the pool's price further than `MaxPriceImpact` allows
+		CircuitBreakerTripped,
+
+		// The call doesn't work with pools of this kind
+		UnsupportedPoolKind,
+
+		// A StableSwap pool needs between two and `MaxStableAssets` different assets, and its
+		// first deposit needs some of every one of them
+		InvalidStablePool,
+
+		// The amplification coefficient must be between one and `MaxAmplification`, and a ramp
+		// may change it at most tenfold over at least `MinAmplificationRamp` blocks
+		InvalidAmplification,
+
+		// The pool doesn't hold the asset
+		AssetNotInPool,
+
+		// A weighted pool needs between two and `MaxWeightedAssets` different assets, weighing
+		// at least 1% each and 100% together, and its first deposit needs some of every one of
+		// them
+		InvalidWeightedPool,
+
+		// A trade with a weighted pool may pay in at most half a reserve and take out at most a
+		// third of one
+		TradeTooLarge,
+
+		// A position's ticks must be multiples of its pool's tick spacing between `MIN_TICK` and
+		// `MAX_TICK`, the lower one below the upper one
+		InvalidTickRange,
+
+		// A concentrated pool can have at most `MaxTicks` initialized ticks
+		TooManyTicks,
+
+		// A concentrated pool's price must be strictly between those of `MIN_TICK` and
+		// `MAX_TICK`
+		InvalidPrice,
+
+		// The account has no position with that id
+		NoPosition,
+
+		// The position has less liquidity than was to be burned
+		InsufficientPositionLiquidity,
 	}
 
 	/// DISPATCHABLE FUNCTIONS DEFINED HERE
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		/// Initiates a request to add liquidity to a specific pool pair.
-		/// If the pool does not exist, it is created and the initial liquidity provided is minted.
-		/// If the pool does exist, the function calculates the additional liquidity to be minted
+		/// If the pool does not exist, it is created with `fee_tier`, which must be one of the
+		/// allowed `FeeTiers`, and the desired amounts are deposited. The initial liquidity is
+		/// minted less the `MinimumLiquidity`, which is locked forever.
+		/// If the pool does exist, only as much of the desired amounts as matches the pool's
+		/// current ratio is deposited, failing if that is less than `amount_a_min` or
+		/// `amount_b_min`. The function then calculates the additional liquidity to be minted
 		/// and adds it to the pool.
 		#[pallet::call_index(0)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(
+			T::WeightInfo::add_liquidity_new_pool()
+				.max(T::WeightInfo::add_liquidity_existing_pool())
+		)]
 		pub fn add_liquidity(
 			origin: OriginFor<T>,
-			asset_a: AssetIdOf<T>,
-			asset_b: AssetIdOf<T>,
-			amount_a: AssetBalanceOf<T>,
-			amount_b: AssetBalanceOf<T>,
-		) -> DispatchResult {
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			amount_a_desired: AssetBalanceOf<T>,
+			amount_b_desired: AssetBalanceOf<T>,
+			amount_a_min: AssetBalanceOf<T>,
+			amount_b_min: AssetBalanceOf<T>,
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
-			let cur_lp_id = Self::get_lp_id(&asset_a, &asset_b)?;
-			let add_amounts =
-				PoolPair::<T>::new(asset_a.clone(), amount_a, asset_b.clone(), amount_b)?;
-			let lp_amount;
-			match <PoolMap<T>>::get(&cur_lp_id) {
-				None => {
-					// New Pool
-					lp_amount = Self::calculate_lp(&add_amounts, None)?;
-					let _ = T::Fungibles::create(
-						cur_lp_id.clone(),
-						Self::account_id(),
-						true,
-						lp_amount,
-					);
-					T::Fungibles::mint_into(cur_lp_id.clone(), &who, lp_amount)?;
-					let new_pool = Pool::<T>::new(add_amounts, lp_amount);
-					<PoolMap<T>>::insert(&cur_lp_id, new_pool);
-				},
-				Some(existing_pool) => {
-					lp_amount = Self::calculate_lp(&add_amounts, Some(&existing_pool))?;
-					T::Fungibles::mint_into(cur_lp_id.clone(), &who, lp_amount)?;
-					Self::increase_pool(&add_amounts, &lp_amount, &cur_lp_id)?;
-				},
-			}
-
-			T::Fungibles::transfer(
-				asset_a.clone(),
-				&who,
-				&Self::account_id(),
-				amount_a,
-				Expendable,
-			)?;
-			T::Fungibles::transfer(
-				asset_b.clone(),
+			Self::ensure_unlocked()?;
+			// creating a pool costs more than depositing into one, so the difference is refunded
+			// when the pool already exists
+			let pool_exists = Self::get_lp_id(&asset_a, &asset_b, fee_tier).is_ok();
+			Self::do_add_liquidity(
 				&who,
-				&Self::account_id(),
-				amount_b,
-				Expendable,
-			)?;
-
-			Self::deposit_event(Event::LiquidityAdded {
 				asset_a,
 				asset_b,
-				amount_a,
-				amount_b,
-				amount_lp: lp_amount,
-			});
-			Ok(())
+				fee_tier,
+				amount_a_desired,
+				amount_b_desired,
+				amount_a_min,
+				amount_b_min,
+			)?;
+
+			let actual_weight = if pool_exists {
+				T::WeightInfo::add_liquidity_existing_pool()
+			} else {
+				T::WeightInfo::add_liquidity_new_pool()
+			};
+			Ok(Some(actual_weight).into())
 		}
 
 		/// Removes liquidity from a given pool pair by burning LP tokens.
 		/// The function checks the balance of the LP tokens, calculates the amount of each asset to
-		/// return, burns the LP tokens, updates the pool, and then returns assets to the user.
-		/// If LP tokens falls to default, the pool is removed from storage
+		/// return, checks them against `amount_a_min` and `amount_b_min`, burns the LP tokens,
+		/// updates the pool, and then returns assets to the user.
+		/// The `MinimumLiquidity` locked at creation is never burned, so the pool always remains.
 		#[pallet::call_index(1)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::remove_liquidity())]
 		pub fn remove_liquidity(
 			origin: OriginFor<T>,
-			asset_a: AssetIdOf<T>,
-			asset_b: AssetIdOf<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
 			token_amount: AssetBalanceOf<T>,
+			amount_a_min: AssetBalanceOf<T>,
+			amount_b_min: AssetBalanceOf<T>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			let cur_lp_id = Self::get_lp_id(&asset_a, &asset_b)?;
-			ensure!(
-				T::Fungibles::balance(cur_lp_id.clone(), &who) >= token_amount,
-				Error::<T>::InsufficientLPBalance
-			);
-
-			let pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
-			let amount_1 = pool
-				.pool_pair
-				.amount_1
-				.checked_mul(&token_amount)
-				.ok_or(ArithmeticError::Overflow)? /
-				pool.lp_supply;
-			let amount_2 = pool
-				.pool_pair
-				.amount_2
-				.checked_mul(&token_amount)
-				.ok_or(ArithmeticError::Overflow)? /
-				pool.lp_supply;
-
-			T::Fungibles::burn_from(cur_lp_id.clone(), &who, token_amount, BestEffort, Force)?;
-			Self::decrease_pool(&amount_1, &amount_2, &token_amount, &cur_lp_id)?;
-			T::Fungibles::transfer(
-				pool.pool_pair.asset_1,
-				&Self::account_id(),
-				&who,
-				amount_1,
-				Expendable,
-			)?;
-			T::Fungibles::transfer(
-				pool.pool_pair.asset_2,
-				&Self::account_id(),
+			Self::ensure_unlocked()?;
+			Self::do_remove_liquidity(
 				&who,
-				amount_2,
-				Expendable,
-			)?;
-
-			Self::deposit_event(Event::LiquidityRemoved {
 				asset_a,
 				asset_b,
-				amount_a: amount_1,
-				amount_b: amount_2,
-			});
+				fee_tier,
+				token_amount,
+				amount_a_min,
+				amount_b_min,
+			)?;
 			Ok(())
 		}
 
 		/// Performs an asset swap, providing an exact quantity of one asset to receive another.
 		/// The function retrieves the liquidity pool, calculates the output amount, and performs a
 		/// slippage check. If the trade is viable, it transfers the assets and updates the pool.
+		/// Like every swap, it fails if it moves the price further than `MaxPriceImpact`, and
+		/// pauses swaps with the pool after it if it trips the pool's circuit breaker.
 		#[pallet::call_index(2)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::swap_exact_in_for_out())]
 		pub fn swap_exact_in_for_out(
 			origin: OriginFor<T>,
-			asset_in: AssetIdOf<T>,
-			asset_out: AssetIdOf<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			fee_tier: u16,
 			exact_in: AssetBalanceOf<T>,
 			min_out: AssetBalanceOf<T>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			let cur_lp_id = Self::get_lp_id(&asset_in, &asset_out)?;
-			let pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoneValue)?;
-
-			let amount_out = Self::calculate_out(&exact_in, &asset_in, &pool)?;
-			if amount_out.0 < min_out {
-				return Err(Error::<T>::SlippageTooHigh.into())
-			}
-
-			T::Fungibles::transfer(
-				asset_in.clone(),
-				&who,
-				&Self::account_id(),
-				exact_in,
-				Expendable,
-			)?;
-			T::Fungibles::transfer(
-				asset_out.clone(),
-				&Self::account_id(),
-				&who,
-				amount_out.0,
-				Protect,
-			)?;
-			<PoolMap<T>>::insert(&cur_lp_id, amount_out.1);
-
-			Self::deposit_event(Event::SwapEvent {
-				asset_in,
-				asset_out,
-				amount_in: exact_in,
-				amount_out: amount_out.0,
-			});
-			Ok(())
+			Self::ensure_unlocked()?;
+			Self::do_swap_exact_in_for_out(&who, asset_in, asset_out, fee_tier, exact_in, min_out)
+				.map(|_| ())
 		}
 
 		/// Performs an asset swap aiming for an exact output amount, while allowing for a maximum
@@ -411,193 +1439,2350 @@ pub mod pallet {
 		/// on required output, checks for slippage, performs asset transfers if the trade is
 		/// viable, and updates the pool.
 		#[pallet::call_index(3)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::swap_in_for_exact_out())]
 		pub fn swap_in_for_exact_out(
 			origin: OriginFor<T>,
-			asset_in: AssetIdOf<T>,
-			asset_out: AssetIdOf<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			fee_tier: u16,
 			max_in: AssetBalanceOf<T>,
 			exact_out: AssetBalanceOf<T>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			let cur_lp_id = Self::get_lp_id(&asset_in, &asset_out)?;
-			let pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+			Self::ensure_unlocked()?;
+			let cur_lp_id = Self::get_lp_id(&asset_in, &asset_out, fee_tier)?;
+			let mut pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+			Self::update_oracle(&cur_lp_id, &mut pool);
 
-			let amount_in = Self::calculate_in(&exact_out, &asset_out, &pool)?;
+			let amount_in = Self::calculate_in(&exact_out, &asset_in, &asset_out, &pool)?;
 			if amount_in.0 > max_in {
 				return Err(Error::<T>::SlippageTooHigh.into())
 			}
+			Self::put_swapped_pool(&cur_lp_id, &pool, amount_in.1)?;
 
 			// trade is good transfer assets accordingly
-			T::Fungibles::transfer(
-				asset_in.clone(),
+			let pool_account = Self::pool_account(&cur_lp_id);
+			NativeOrAssetUnion::<T>::transfer(
+				&asset_in,
 				&who,
-				&Self::account_id(),
+				&pool_account,
 				amount_in.0,
 				Protect,
 			)?;
-			T::Fungibles::transfer(
-				asset_out.clone(),
-				&Self::account_id(),
+			NativeOrAssetUnion::<T>::transfer(
+				&asset_out,
+				&pool_account,
 				&who,
 				exact_out,
 				Expendable,
 			)?;
-			<PoolMap<T>>::insert(&cur_lp_id, amount_in.1);
 
 			Self::deposit_event(Event::SwapEvent {
 				asset_in,
 				asset_out,
+				fee_tier,
 				amount_in: amount_in.0,
 				amount_out: exact_out,
 			});
 			Ok(())
 		}
 
-		/// This function computes the price ratio between `asset_in` and `asset_out` using the
-		/// available liquidity pool.
+		/// This function computes the price ratio between `asset_in` and `asset_out` in both
+		/// directions using the pool with `fee_tier`. Prices are in whole units of each asset,
+		/// according to the decimals in its metadata.
 		#[pallet::call_index(4)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight(T::WeightInfo::price_oracle())]
 		pub fn price_oracle(
 			_origin: OriginFor<T>,
-			asset_in: AssetIdOf<T>,
-			asset_out: AssetIdOf<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			fee_tier: u16,
 		) -> DispatchResult {
-			let cur_lp_id = Self::get_lp_id(&asset_in, &asset_out)?;
-			let pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
-
-			let oracle_price;
-			if asset_in == pool.pool_pair.asset_1 {
-				oracle_price =
-					Percent::from_rational(pool.pool_pair.amount_2, pool.pool_pair.amount_1);
-			} else {
-				oracle_price =
-					Percent::from_rational(pool.pool_pair.amount_1, pool.pool_pair.amount_2);
-			};
+			let (rate, inverse_rate) = Self::spot_price(&asset_in, &asset_out, fee_tier)?;
 			Self::deposit_event(Event::PriceOracleEvent {
-				rate: oracle_price,
+				rate,
+				inverse_rate,
 				asset_in,
 				asset_out,
+				fee_tier,
 			});
 			Ok(())
 		}
 
-		/// This function allows the permission origin to set the fee
+		/// This function allows the permission origin to set the fee tiers, in basis points, new
+		/// pools can be created with. Existing pools keep their tier.
 		#[pallet::call_index(5)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
-		pub fn set_fee(origin: OriginFor<T>, new_fee: u16) -> DispatchResult {
+		#[pallet::weight(T::WeightInfo::set_fee_tiers(fee_tiers.len() as u32))]
+		pub fn set_fee_tiers(
+			origin: OriginFor<T>,
+			fee_tiers: BoundedVec<u16, T::MaxFeeTiers>,
+		) -> DispatchResult {
 			ensure!(
 				T::PermissionOrigin::try_origin(origin).is_ok(),
 				Error::<T>::NotAllowedToSetFee
 			);
-			<Fee<T>>::put(new_fee);
-			Self::deposit_event(Event::FeeUpdated { new_fee });
+			ensure!(
+				fee_tiers.iter().all(|fee_tier| *fee_tier < 10_000),
+				Error::<T>::InvalidFeeTier
+			);
+			<FeeTiers<T>>::put(fee_tiers.clone());
+			Self::deposit_event(Event::FeeTiersUpdated { fee_tiers });
 			Ok(())
 		}
 
-		/// This function allows a user to borrow a specified amount of an asset
-		/// temporarily for execution of a predefined function (`call`), provided
-		/// that the asset has sufficient liquidity. The borrowed assets are
-		/// automatically transferred to the user.
+		/// Lends `amount` of `asset_id` from the reserves of the active pools holding it to the
+		/// caller for the duration of `call`, which is dispatched with the caller's origin, or of
+		/// the runtime's `FlashLoanReceiver` if there is no `call`. By the end of it, the loan
+		/// plus a fee must be paid into the DEX's account. The fee is always charged at the
+		/// `DefaultFeeTier`, whatever the fee tiers of the pools lending. The loan goes back to
+		/// the pools it came from and the fee is credited to the pools holding `asset_id` in
+		/// proportion to their reserves. The weight of `call` is charged on top of the loan's,
+		/// and whatever `call` reports it did not use is refunded. `call` must pass the runtime's
+		/// `FlashLoanCallFilter`, and can't use the DEX's state-changing calls while the loan is
+		/// out.
 		#[pallet::call_index(6)]
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+		#[pallet::weight({
+			let borrow_weight = match call {
+				Some(call) => call.get_dispatch_info().weight,
+				None => T::FlashLoanReceiver::max_weight(),
+			};
+			T::WeightInfo::flash_loan(<PoolCount<T>>::get()).saturating_add(borrow_weight)
+		})]
 		pub fn flash_loan(
 			origin: OriginFor<T>,
-			asset_id: AssetIdOf<T>,
+			asset_id: AssetKindOf<T>,
 			amount: AssetBalanceOf<T>,
-			call: Box<<T as frame_system::Config>::RuntimeCall>,
-		) -> DispatchResult {
-			// ensuring sufficient liquidity and permission
+			call: Option<Box<<T as Config>::RuntimeCall>>,
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin.clone())?;
-			let total_liquidity = T::Fungibles::total_issuance(asset_id.clone());
-			ensure!(total_liquidity >= amount, Error::<T>::InsufficientLiquidity);
-			T::Fungibles::transfer(
-				asset_id.clone(),
-				&Self::account_id(),
-				&who,
-				amount,
-				Expendable,
-			)?;
+			if let Some(call) = &call {
+				ensure!(T::FlashLoanCallFilter::contains(call), Error::<T>::CallNotAllowed);
+			}
+			let borrow_weight = Self::do_flash_loan(&who, &asset_id, amount, |fee| match call {
+				// execute the borrowers contract
+				Some(call) => {
+					let call_info = call.get_dispatch_info();
+					let call_result = call.dispatch(origin);
+					let call_weight = extract_actual_weight(&call_result, &call_info);
+					call_result.map_err(|_| Error::<T>::CallFailed)?;
+					Ok(call_weight)
+				},
+				None => T::FlashLoanReceiver::on_flash_loan(&who, &asset_id, amount, fee),
+			})?;
+			let weight = T::WeightInfo::flash_loan(<PoolCount<T>>::get());
+			Ok(Some(weight.saturating_add(borrow_weight)).into())
+		}
 
-			// execute the borrowers contract
-			call.dispatch(origin).map_err(|_| Error::<T>::CallFailed)?;
-			let fee = Self::calculate_fees(&amount)?;
-			ensure!(
-				T::Fungibles::balance(asset_id.clone(), &Self::account_id()) >=
-					total_liquidity + fee,
-				Error::<T>::InsufficientRepayment
-			);
+		/// Swaps an exact amount of the first asset in `path` for the last asset, routing through
+		/// the pool of every consecutive pair of assets. `fee_tiers` picks the pool of each hop, so
+		/// it must be one shorter than `path`. All hops are executed atomically and slippage is
+		/// only checked against the final output.
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::swap_exact_in_along_path(path.len() as u32))]
+		pub fn swap_exact_in_along_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<AssetKindOf<T>, T::MaxPathLength>,
+			fee_tiers: BoundedVec<u16, T::MaxPathLength>,
+			exact_in: AssetBalanceOf<T>,
+			min_out: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::ensure_valid_path(&path, &fee_tiers)?;
+
+			let (amount_out, hops) = Self::swap_along_path_exact_in(&path, &fee_tiers, exact_in)?;
+			if amount_out < min_out {
+				return Err(Error::<T>::SlippageTooHigh.into())
+			}
+
+			Self::transfer_along_path(&who, &path, &hops, amount_out, (Expendable, Protect))?;
 
-			Self::deposit_event(Event::FlashLoanEvent { asset_id, amount });
+			Self::deposit_event(Event::PathSwapEvent {
+				path,
+				fee_tiers,
+				amount_in: exact_in,
+				amount_out,
+			});
 			Ok(())
 		}
-	}
-}
 
-impl<T: Config> Pallet<T> {
-	/// Calculates the amount of LP tokens.
-	///
-	/// For existing pools, the formula is `lp = sqrt((A+a)*(B+b)) - sqrt(A*B)`. `A` and `B` are the
-	/// current pool amounts and `a` and `b` are the amounts to add. For new pools, `lp =
-	/// sqrt(a*b)`.
-	fn calculate_lp(
-		new_pair: &PoolPair<T>,
-		pool: Option<&Pool<T>>,
-	) -> Result<AssetBalanceOf<T>, DispatchError> {
-		if let Some(pool) = pool {
-			// Calculate LP for existing pool
-			let total_1 = pool
-				.pool_pair
-				.amount_1
-				.checked_add(&new_pair.amount_1)
-				.ok_or(ArithmeticError::Overflow)?;
-			let total_2 = pool
-				.pool_pair
-				.amount_2
+		/// Swaps at most `max_in` of the first asset in `path` for an exact amount of the last
+		/// asset, routing through the pool of every consecutive pair of assets and the fee tier
+		/// given for it in `fee_tiers`. The required input is worked out backwards from
+		/// `exact_out`, and slippage is only checked against the total input.
+		#[pallet::call_index(8)]
+		#[pallet::weight(T::WeightInfo::swap_in_for_exact_out_along_path(path.len() as u32))]
+		pub fn swap_in_for_exact_out_along_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<AssetKindOf<T>, T::MaxPathLength>,
+			fee_tiers: BoundedVec<u16, T::MaxPathLength>,
+			max_in: AssetBalanceOf<T>,
+			exact_out: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::ensure_valid_path(&path, &fee_tiers)?;
+
+			let (amount_in, hops) = Self::swap_along_path_exact_out(&path, &fee_tiers, exact_out)?;
+			if amount_in > max_in {
+				return Err(Error::<T>::SlippageTooHigh.into())
+			}
+
+			Self::transfer_along_path(&who, &path, &hops, exact_out, (Protect, Expendable))?;
+
+			Self::deposit_event(Event::PathSwapEvent {
+				path,
+				fee_tiers,
+				amount_in,
+				amount_out: exact_out,
+			});
+			Ok(())
+		}
+
+		/// This function computes the time weighted average price of `asset_in` in `asset_out`
+		/// over at least the last `window` seconds, using the price accumulators of the pool with
+		/// `fee_tier`. Unlike `price_oracle`, the result can't be moved by trading within the
+		/// current block.
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::WeightInfo::twap_oracle())]
+		pub fn twap_oracle(
+			_origin: OriginFor<T>,
+			asset_in: AssetKindOf<T>,
+			asset_out: AssetKindOf<T>,
+			fee_tier: u16,
+			window: u64,
+		) -> DispatchResult {
+			let rate = Self::consult_twap(&asset_in, &asset_out, fee_tier, window)?;
+			Self::deposit_event(Event::TwapOracleEvent {
+				rate,
+				asset_in,
+				asset_out,
+				fee_tier,
+				window,
+			});
+			Ok(())
+		}
+
+		/// This function allows the permission origin to turn the protocol fee on or off. While
+		/// `recipient` is set, `share` of the fee growth of every pool is minted to it as LP
+		/// tokens whenever liquidity is added or removed.
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::WeightInfo::set_protocol_fee())]
+		pub fn set_protocol_fee(
+			origin: OriginFor<T>,
+			recipient: Option<T::AccountId>,
+			share: Perbill,
+		) -> DispatchResult {
+			ensure!(
+				T::PermissionOrigin::try_origin(origin).is_ok(),
+				Error::<T>::NotAllowedToSetFee
+			);
+			<ProtocolFeeRecipient<T>>::set(recipient.clone());
+			<ProtocolFeeShare<T>>::put(share);
+			Self::deposit_event(Event::ProtocolFeeUpdated { recipient, share });
+			Ok(())
+		}
+
+		/// Adds liquidity to the pool of `asset_in` and `other_asset` from `asset_in` alone.
+		/// The share of `amount_in` given by `zap_swap_amount` is first swapped for `other_asset`
+		/// through the same pool, then the rest is deposited with the swap output as
+		/// `add_liquidity` does, all in one call. Fails if fewer than `min_lp` LP tokens are
+		/// minted. Any dust left by rounding stays with the caller.
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::WeightInfo::zap_in())]
+		pub fn zap_in(
+			origin: OriginFor<T>,
+			asset_in: AssetKindOf<T>,
+			amount_in: AssetBalanceOf<T>,
+			other_asset: AssetKindOf<T>,
+			fee_tier: u16,
+			min_lp: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			let cur_lp_id = Self::get_lp_id(&asset_in, &other_asset, fee_tier)?;
+			let pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+			// the swap amount is only worked out for the constant product
+			ensure!(
+				matches!(pool.kind, PoolKind::ConstantProduct),
+				Error::<T>::UnsupportedPoolKind
+			);
+			let (reserve_in, _) = Self::reserves_of(&asset_in, &other_asset, &pool);
+
+			let swap_amount = Self::zap_swap_amount(amount_in, reserve_in, fee_tier)?;
+			let amount_other = Self::do_swap_exact_in_for_out(
+				&who,
+				asset_in.clone(),
+				other_asset.clone(),
+				fee_tier,
+				swap_amount,
+				Zero::zero(),
+			)?;
+			let amount_lp = Self::do_add_liquidity(
+				&who,
+				asset_in.clone(),
+				other_asset.clone(),
+				fee_tier,
+				amount_in.checked_sub(&swap_amount).ok_or(ArithmeticError::Underflow)?,
+				amount_other,
+				Zero::zero(),
+				Zero::zero(),
+			)?;
+			ensure!(amount_lp >= min_lp, Error::<T>::SlippageTooHigh);
+
+			Self::deposit_event(Event::ZappedIn {
+				asset_in,
+				other_asset,
+				fee_tier,
+				amount_in,
+				amount_lp,
+			});
+			Ok(())
+		}
+
+		/// Removes liquidity from the pool of `asset_out` and `other_asset` into `asset_out`
+		/// alone. The LP tokens are burned as `remove_liquidity` does, then the `other_asset`
+		/// paid out is swapped for `asset_out` through the same pool. Fails if less than
+		/// `min_out` of `asset_out` is received in total.
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::zap_out())]
+		pub fn zap_out(
+			origin: OriginFor<T>,
+			asset_out: AssetKindOf<T>,
+			other_asset: AssetKindOf<T>,
+			fee_tier: u16,
+			token_amount: AssetBalanceOf<T>,
+			min_out: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			// withdrawing from a StableSwap pool pays out all of its assets
+			let cur_lp_id = Self::get_lp_id(&asset_out, &other_asset, fee_tier)?;
+			ensure!(
+				<PoolMap<T>>::get(&cur_lp_id)
+					.map_or(false, |pool| matches!(pool.kind, PoolKind::ConstantProduct)),
+				Error::<T>::UnsupportedPoolKind
+			);
+			let (amount_removed, amount_other) = Self::do_remove_liquidity(
+				&who,
+				asset_out.clone(),
+				other_asset.clone(),
+				fee_tier,
+				token_amount,
+				Zero::zero(),
+				Zero::zero(),
+			)?;
+			let amount_swapped = Self::do_swap_exact_in_for_out(
+				&who,
+				other_asset.clone(),
+				asset_out.clone(),
+				fee_tier,
+				amount_other,
+				Zero::zero(),
+			)?;
+			let amount_out =
+				amount_removed.checked_add(&amount_swapped).ok_or(ArithmeticError::Overflow)?;
+			ensure!(amount_out >= min_out, Error::<T>::SlippageTooHigh);
+
+			Self::deposit_event(Event::ZappedOut {
+				asset_out,
+				other_asset,
+				fee_tier,
+				amount_lp: token_amount,
+				amount_out,
+			});
+			Ok(())
+		}
+
+		/// Lets the permission origin start a farm for the LP token `lp_id` of an existing pool,
+		/// or change the reward asset and emission of its farm. Every block, `reward_per_block` of
+		/// `reward_asset` is shared between the stakers in proportion to their stake. Rewards are
+		/// paid out of `rewards_account`, which has to be funded separately. Changing the reward
+		/// asset also changes the asset unclaimed rewards are paid in.
+		#[pallet::call_index(13)]
+		#[pallet::weight(T::WeightInfo::set_farm())]
+		pub fn set_farm(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			reward_asset: AssetKindOf<T>,
+			reward_per_block: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			ensure!(
+				T::PermissionOrigin::try_origin(origin).is_ok(),
+				Error::<T>::NotAllowedToSetFarm
+			);
+			ensure!(<PoolMap<T>>::contains_key(&lp_id), Error::<T>::NoPool);
+
+			let farm = match <Farms<T>>::get(&lp_id) {
+				Some(mut farm) => {
+					// rewards up to now are earned at the old rate
+					Self::update_farm(&mut farm);
+					farm.reward_asset = reward_asset.clone();
+					farm.reward_per_block = reward_per_block;
+					farm
+				},
+				None => Farm::<T>::new(
+					reward_asset.clone(),
+					reward_per_block,
+					frame_system::Pallet::<T>::block_number(),
+				),
+			};
+			<Farms<T>>::insert(&lp_id, farm);
+
+			Self::deposit_event(Event::FarmUpdated { lp_id, reward_asset, reward_per_block });
+			Ok(())
+		}
+
+		/// Stakes `amount` of the caller's LP tokens in the farm of `lp_id`. The LP tokens stay in
+		/// the caller's account, but are frozen until they are unstaked.
+		#[pallet::call_index(14)]
+		#[pallet::weight(T::WeightInfo::stake_lp())]
+		pub fn stake_lp(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			amount: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			let mut farm = <Farms<T>>::get(&lp_id).ok_or(Error::<T>::NoFarm)?;
+			ensure!(
+				T::Fungibles::reducible_balance(lp_id.clone(), &who, Expendable, Polite) >= amount,
+				Error::<T>::InsufficientLPBalance
+			);
+
+			Self::update_farm(&mut farm);
+			let mut stake = <Stakes<T>>::get(&lp_id, &who)
+				.unwrap_or_else(|| Stake::<T>::new(farm.reward_per_share));
+			Self::accrue_rewards(&farm, &mut stake)?;
+			stake.amount = stake.amount.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+			farm.total_staked =
+				farm.total_staked.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+			<Farms<T>>::insert(&lp_id, farm);
+			Self::put_stake(&lp_id, &who, stake);
+
+			Self::deposit_event(Event::LpStaked { who, lp_id, amount });
+			Ok(())
+		}
+
+		/// Unstakes `amount` of the caller's LP tokens from the farm of `lp_id`, unfreezing them.
+		/// Rewards earned so far can still be claimed afterwards.
+		#[pallet::call_index(15)]
+		#[pallet::weight(T::WeightInfo::unstake_lp())]
+		pub fn unstake_lp(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			amount: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			let mut farm = <Farms<T>>::get(&lp_id).ok_or(Error::<T>::NoFarm)?;
+			let mut stake = <Stakes<T>>::get(&lp_id, &who).ok_or(Error::<T>::InsufficientStake)?;
+			ensure!(stake.amount >= amount, Error::<T>::InsufficientStake);
+
+			Self::update_farm(&mut farm);
+			Self::accrue_rewards(&farm, &mut stake)?;
+			stake.amount = stake.amount.checked_sub(&amount).ok_or(ArithmeticError::Underflow)?;
+			farm.total_staked =
+				farm.total_staked.checked_sub(&amount).ok_or(ArithmeticError::Underflow)?;
+			<Farms<T>>::insert(&lp_id, farm);
+			Self::put_stake(&lp_id, &who, stake);
+
+			Self::deposit_event(Event::LpUnstaked { who, lp_id, amount });
+			Ok(())
+		}
+
+		/// Claims the caller's rewards from the farm of `lp_id`. Without a
+		/// `RewardVestingPeriod` they are paid out at once. Otherwise they are added to the
+		/// caller's vesting schedule for the reward asset and released linearly, and whatever has
+		/// vested so far is paid out.
+		#[pallet::call_index(16)]
+		#[pallet::weight(T::WeightInfo::claim_rewards())]
+		pub fn claim_rewards(origin: OriginFor<T>, lp_id: AssetIdOf<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			let mut farm = <Farms<T>>::get(&lp_id).ok_or(Error::<T>::NoFarm)?;
+			Self::update_farm(&mut farm);
+
+			let mut amount = Zero::zero();
+			if let Some(mut stake) = <Stakes<T>>::get(&lp_id, &who) {
+				Self::accrue_rewards(&farm, &mut stake)?;
+				amount = stake.pending;
+				stake.pending = Zero::zero();
+				Self::put_stake(&lp_id, &who, stake);
+			}
+			let released = Self::vest_rewards(&who, &farm.reward_asset, amount)?;
+			let reward_asset = farm.reward_asset.clone();
+			<Farms<T>>::insert(&lp_id, farm);
+
+			Self::deposit_event(Event::RewardsClaimed {
+				who,
+				lp_id,
+				reward_asset,
+				amount,
+				released,
+			});
+			Ok(())
+		}
+
+		/// Sends `amount_a_out` of `asset_a` and `amount_b_out` of `asset_b` from the pool with
+		/// `fee_tier` to the caller before anything is paid, like a Uniswap V2 flash swap, and
+		/// then dispatches `call` with the caller's origin. Afterwards `amount_a_in` and
+		/// `amount_b_in` are taken from the caller, so the swap can be repaid in either asset or
+		/// both. The reserves less the fee on what was paid in must multiply to at least the
+		/// product the pool started with. `call` must pass the runtime's `FlashLoanCallFilter`,
+		/// and can't use the DEX's state-changing calls while it runs.
+		#[pallet::call_index(17)]
+		#[pallet::weight({
+			let dispatch_info = call.get_dispatch_info();
+			T::WeightInfo::flash_swap().saturating_add(dispatch_info.weight)
+		})]
+		pub fn flash_swap(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			amount_a_out: AssetBalanceOf<T>,
+			amount_b_out: AssetBalanceOf<T>,
+			amount_a_in: AssetBalanceOf<T>,
+			amount_b_in: AssetBalanceOf<T>,
+			call: Box<<T as Config>::RuntimeCall>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin.clone())?;
+			ensure!(T::FlashLoanCallFilter::contains(&call), Error::<T>::CallNotAllowed);
+			let call_weight = Self::do_flash_swap(
+				&who,
+				&asset_a,
+				&asset_b,
+				fee_tier,
+				(amount_a_out, amount_b_out),
+				(amount_a_in, amount_b_in),
+				|| {
+					// execute the borrowers contract
+					let call_info = call.get_dispatch_info();
+					let call_result = call.dispatch(origin);
+					let call_weight = extract_actual_weight(&call_result, &call_info);
+					call_result.map_err(|_| Error::<T>::CallFailed)?;
+					Ok(call_weight)
+				},
+			)?;
+			Ok(Some(T::WeightInfo::flash_swap().saturating_add(call_weight)).into())
+		}
+
+		/// Creates the pool of `asset_a` and `asset_b` with `fee_tier`, which must be one of the
+		/// allowed `FeeTiers`, and its LP token. The pool starts without liquidity, the first
+		/// `add_liquidity` sets its price. `PoolCreationDeposit` is held from the caller until
+		/// the pool is destroyed, and at most `MaxPools` pools can exist.
+		#[pallet::call_index(18)]
+		#[pallet::weight(T::WeightInfo::create_pool())]
+		pub fn create_pool(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::do_create_pool(&who, asset_a, asset_b, fee_tier)?;
+			Ok(())
+		}
+
+		/// Destroys a pool that has no LP tokens but the `MinimumLiquidity` locked by its first
+		/// deposit, along with its LP token and farm, and releases the deposit to the pool's
+		/// creator. The locked LP tokens are burned, and what the pool's account still holds of
+		/// its assets is paid to the creator as well, or burned if it is too little for the
+		/// creator to hold. A concentrated pool can be destroyed once it has no positions left.
+		/// Fails while stakers have rewards to claim from the pool's farm, or if the pool's
+		/// account holds other assets. Only the pool's creator and the `PauseOrigin` can call it.
+		#[pallet::call_index(19)]
+		#[pallet::weight(T::WeightInfo::destroy_pool())]
+		pub fn destroy_pool(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+		) -> DispatchResult {
+			Self::ensure_unlocked()?;
+			let cur_lp_id = Self::get_lp_id(&asset_a, &asset_b, fee_tier)?;
+			let pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+			// pools created before deposits were taken have none to release, and only the
+			// `PauseOrigin` can destroy them
+			let (depositor, deposit) = <PoolDeposits<T>>::get(&cur_lp_id)
+				.unwrap_or_else(|| (Self::account_id(), Zero::zero()));
+			if let Err(origin) = T::PauseOrigin::try_origin(origin) {
+				ensure!(ensure_signed(origin)? == depositor, Error::<T>::NotPoolCreator);
+			}
+			// the only LP tokens left may be the locked ones, which no one can withdraw
+			let locked_account = Self::locked_liquidity_account();
+			let locked = T::Fungibles::balance(cur_lp_id.clone(), &locked_account);
+			ensure!(pool.lp_supply == locked, Error::<T>::PoolNotEmpty);
+			// nor positions, every one of which bounds a tick
+			if let PoolKind::Concentrated(concentrated) = &pool.kind {
+				ensure!(concentrated.ticks.is_empty(), Error::<T>::PoolNotEmpty);
+			}
+			// nor stakes, which are only kept while they have rewards to claim once the LP
+			// tokens are unstaked
+			ensure!(
+				<Stakes<T>>::iter_prefix(&cur_lp_id).next().is_none(),
+				Error::<T>::PoolNotEmpty
+			);
+
+			<PoolDeposits<T>>::remove(&cur_lp_id);
+			if !locked.is_zero() {
+				T::Fungibles::burn_from(cur_lp_id.clone(), &locked_account, locked, Exact, Polite)?;
+			}
+			Self::destroy_lp_asset(&cur_lp_id, &depositor)?;
+
+			// the reserves the locked LP tokens stood for, rounding dust and donations
+			let pool_account = Self::pool_account(&cur_lp_id);
+			let assets: Vec<_> = pool.reserves().into_iter().map(|(asset, _)| asset).collect();
+			for asset in &assets {
+				let dust = NativeOrAssetUnion::<T>::balance(asset, &pool_account);
+				if dust.is_zero() {
+					continue
+				}
+				if NativeOrAssetUnion::<T>::can_deposit(asset, &depositor, dust) ==
+					DepositConsequence::Success
+				{
+					NativeOrAssetUnion::<T>::transfer(
+						asset,
+						&pool_account,
+						&depositor,
+						dust,
+						Expendable,
+					)?;
+				} else {
+					NativeOrAssetUnion::<T>::burn_from(asset, &pool_account, dust)?;
+				}
+			}
+			// with the pool's assets gone, this only fails if other assets sent to its account
+			// still need the provider it was given
+			frame_system::Pallet::<T>::dec_providers(&pool_account)?;
+
+			<PoolMap<T>>::remove(&cur_lp_id);
+			<LpToPair<T>>::remove(&cur_lp_id);
+			for pair_key in Self::pair_keys(&assets, pool.fee_tier) {
+				<PairToLp<T>>::remove(pair_key);
+			}
+			<Observations<T>>::remove(&cur_lp_id);
+			<ObservationIndex<T>>::remove(&cur_lp_id);
+			<Farms<T>>::remove(&cur_lp_id);
+			<PriceWindows<T>>::remove(&cur_lp_id);
+			<PoolCount<T>>::mutate(|count| *count = count.saturating_sub(1));
+
+			if !deposit.is_zero() {
+				<T::NativeBalance as fungible::hold::Mutate<T::AccountId>>::release(
+					&HoldReason::PoolCreation.into(),
+					&depositor,
+					deposit,
+					BestEffort,
+				)?;
+			}
+
+			Self::deposit_event(Event::PoolDestroyed {
+				asset_a,
+				asset_b,
+				fee_tier,
+				lp_id: cur_lp_id,
+				depositor,
+				deposit,
+			});
+			Ok(())
+		}
+
+		/// Lets the `PauseOrigin` pause swaps with a pool, freeze it entirely, or make it active
+		/// again, which also resets a tripped circuit breaker and starts a new price window.
+		#[pallet::call_index(20)]
+		#[pallet::weight(T::WeightInfo::set_pool_status())]
+		pub fn set_pool_status(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			status: PoolStatus,
+		) -> DispatchResult {
+			T::PauseOrigin::ensure_origin(origin)?;
+			let cur_lp_id = Self::get_lp_id(&asset_a, &asset_b, fee_tier)?;
+			<PoolMap<T>>::try_mutate(&cur_lp_id, |pool| -> DispatchResult {
+				pool.as_mut().ok_or(Error::<T>::NoPool)?.status = status;
+				Ok(())
+			})?;
+			<PriceWindows<T>>::remove(&cur_lp_id);
+
+			Self::deposit_event(Event::PoolStatusChanged { lp_id: cur_lp_id, status });
+			Ok(())
+		}
+
+		/// Pays whatever the account of the pool of `lp_id` holds of its assets beyond its
+		/// reserves to `to`, like Uniswap V2's `skim`. The reserves themselves aren't touched.
+		#[pallet::call_index(21)]
+		#[pallet::weight(T::WeightInfo::skim())]
+		pub fn skim(origin: OriginFor<T>, lp_id: AssetIdOf<T>, to: T::AccountId) -> DispatchResult {
+			ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			let pool = <PoolMap<T>>::get(&lp_id).ok_or(Error::<T>::NoPool)?;
+			ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+
+			let pool_account = Self::pool_account(&lp_id);
+			let balances = Self::pool_balances(&lp_id, &pool);
+			let mut amounts = Vec::with_capacity(balances.len());
+			for ((asset, reserve), balance) in pool.reserves().into_iter().zip(balances) {
+				let amount = balance.saturating_sub(reserve);
+				Self::transfer_nonzero(&asset, &pool_account, &to, amount)?;
+				amounts.push(amount);
+			}
+
+			Self::deposit_event(Event::Skimmed { lp_id, to, amounts });
+			Ok(())
+		}
+
+		/// Sets the reserves of the pool of `lp_id` to what its account actually holds of its
+		/// assets, like Uniswap V2's `sync`. The pool can't be frozen. As a sync repairs the
+		/// reserves, however far they drifted, it isn't held to the price limits of a swap and
+		/// starts a new price window instead.
+		#[pallet::call_index(22)]
+		#[pallet::weight(T::WeightInfo::sync())]
+		pub fn sync(origin: OriginFor<T>, lp_id: AssetIdOf<T>) -> DispatchResult {
+			ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			let mut pool = <PoolMap<T>>::get(&lp_id).ok_or(Error::<T>::NoPool)?;
+			ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+			// the price of a concentrated pool doesn't follow from its reserves
+			ensure!(
+				!matches!(pool.kind, PoolKind::Concentrated(_)),
+				Error::<T>::UnsupportedPoolKind
+			);
+			// the first deposit sets the reserves of a pool without liquidity anyway
+			ensure!(!pool.lp_supply.is_zero(), Error::<T>::InsufficientLiquidity);
+			Self::update_oracle(&lp_id, &mut pool);
+
+			let reserves = Self::pool_balances(&lp_id, &pool);
+			for ((asset, _), reserve) in pool.reserves().iter().zip(&reserves) {
+				pool.set_reserve(asset, *reserve)?;
+			}
+			<PoolMap<T>>::insert(&lp_id, pool);
+			// the price moved outside of any swap, so the next window starts from the new one
+			<PriceWindows<T>>::remove(&lp_id);
+
+			Self::deposit_event(Event::Synced { lp_id: lp_id.clone(), reserves });
+			Ok(())
+		}
+
+		/// Creates a StableSwap pool of two to `MaxStableAssets` different `assets` with
+		/// `fee_tier`, which must be one of the allowed `FeeTiers`, and its LP token. It trades
+		/// along Curve's invariant with the given `amplification` coefficient, for assets that
+		/// should trade close to 1:1. Every pair of its assets can be swapped through it as
+		/// through the pool of that pair with `fee_tier`, so none of them may have one yet. The
+		/// deposit is held as `create_pool` does.
+		#[pallet::call_index(23)]
+		#[pallet::weight(T::WeightInfo::create_stable_pool(assets.len() as u32))]
+		pub fn create_stable_pool(
+			origin: OriginFor<T>,
+			assets: BoundedVec<AssetKindOf<T>, T::MaxStableAssets>,
+			fee_tier: u16,
+			amplification: u32,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			ensure!(
+				(1..=T::MaxAmplification::get()).contains(&amplification),
+				Error::<T>::InvalidAmplification
+			);
+			ensure!(assets.len() >= 2, Error::<T>::InvalidStablePool);
+			let mut sorted = assets.clone().into_inner();
+			sorted.sort_by_key(|asset| asset.encode());
+			sorted.dedup();
+			ensure!(sorted.len() == assets.len(), Error::<T>::InvalidStablePool);
+
+			let more_reserves = sorted[2..]
+				.iter()
+				.map(|asset| (asset.clone(), Zero::zero()))
+				.collect::<Vec<_>>()
+				.try_into()
+				.map_err(|_| Error::<T>::InvalidStablePool)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			let kind = PoolKind::StableSwap(StablePool {
+				more_reserves,
+				amplification: Amplification::new(amplification, now),
+			});
+			let (lp_id, deposit) = Self::insert_new_pool(&who, &sorted, fee_tier, kind)?;
+
+			Self::deposit_event(Event::StablePoolCreated {
+				who,
+				assets,
+				fee_tier,
+				amplification,
+				lp_id,
+				deposit,
+			});
+			Ok(())
+		}
+
+		/// Deposits `amounts` of any of the assets of the StableSwap pool of `lp_id`, in any
+		/// ratio, failing if fewer than `min_lp` LP tokens are minted. The first deposit needs
+		/// some of every asset and sets the pool's starting balance, minting its invariant `D`
+		/// less the `MinimumLiquidity`, which is locked forever. Later deposits mint their share
+		/// of the growth of `D`, less the swap fee on the part of them that is off the pool's
+		/// current ratio.
+		#[pallet::call_index(24)]
+		#[pallet::weight(T::WeightInfo::add_stable_liquidity(T::MaxStableAssets::get()))]
+		pub fn add_stable_liquidity(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			amounts: BoundedVec<(AssetKindOf<T>, AssetBalanceOf<T>), T::MaxStableAssets>,
+			min_lp: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::do_add_stable_liquidity(&who, &lp_id, &amounts, min_lp)?;
+			Ok(())
+		}
+
+		/// Burns `lp_amount` of the caller's LP tokens of the StableSwap pool of `lp_id` and pays
+		/// out the same share of each of its reserves. `min_amounts` can give the least of any
+		/// of its assets the caller accepts.
+		#[pallet::call_index(25)]
+		#[pallet::weight(T::WeightInfo::remove_stable_liquidity(T::MaxStableAssets::get()))]
+		pub fn remove_stable_liquidity(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			lp_amount: AssetBalanceOf<T>,
+			min_amounts: BoundedVec<(AssetKindOf<T>, AssetBalanceOf<T>), T::MaxStableAssets>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			let pool = <PoolMap<T>>::get(&lp_id).ok_or(Error::<T>::NoPool)?;
+			ensure!(matches!(pool.kind, PoolKind::StableSwap(_)), Error::<T>::UnsupportedPoolKind);
+			Self::do_remove_liquidity_proportionally(&who, &lp_id, lp_amount, &min_amounts)?;
+			Ok(())
+		}
+
+		/// Lets the `AmplificationOrigin` move the amplification coefficient of the StableSwap
+		/// pool of `lp_id` linearly from its current value to `target` by block `end`, like
+		/// Curve's `ramp_A`. `end` must be at least `MinAmplificationRamp` blocks away and the
+		/// coefficient may change at most tenfold, so the pool's prices only drift slowly.
+		/// Starting a new ramp replaces the current one from wherever it has got to.
+		#[pallet::call_index(26)]
+		#[pallet::weight(T::WeightInfo::ramp_amplification())]
+		pub fn ramp_amplification(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			target: u32,
+			end: BlockNumberFor<T>,
+		) -> DispatchResult {
+			T::AmplificationOrigin::ensure_origin(origin)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(
+				end >= now.saturating_add(T::MinAmplificationRamp::get()),
+				Error::<T>::InvalidAmplification
+			);
+			ensure!(
+				(1..=T::MaxAmplification::get()).contains(&target),
+				Error::<T>::InvalidAmplification
+			);
+
+			let initial = <PoolMap<T>>::try_mutate(&lp_id, |pool| -> Result<u32, DispatchError> {
+				let pool = pool.as_mut().ok_or(Error::<T>::NoPool)?;
+				let PoolKind::StableSwap(stable) = &mut pool.kind else {
+					return Err(Error::<T>::UnsupportedPoolKind.into())
+				};
+				let initial = stable.amplification.at(now);
+				ensure!(
+					target <= initial.saturating_mul(MAX_AMPLIFICATION_CHANGE) &&
+						initial <= target.saturating_mul(MAX_AMPLIFICATION_CHANGE),
+					Error::<T>::InvalidAmplification
+				);
+				stable.amplification = Amplification { initial, target, start: now, end };
+				Ok(initial)
+			})?;
+
+			Self::deposit_event(Event::AmplificationRamped { lp_id, initial, target, end });
+			Ok(())
+		}
+
+		/// Creates a weighted pool of two to `MaxWeightedAssets` different `assets` with
+		/// `fee_tier`, which must be one of the allowed `FeeTiers`, and its LP token. Every asset
+		/// is given a weight of at least 1%, and the weights must add up to 100%. It trades along
+		/// Balancer's weighted product invariant, which keeps each asset at its weight's share of
+		/// the pool's value, so two assets at 50/50 trade like a constant product pool. Every
+		/// pair of its assets can be swapped through it as through the pool of that pair with
+		/// `fee_tier`, so none of them may have one yet. The deposit is held as `create_pool`
+		/// does.
+		#[pallet::call_index(27)]
+		#[pallet::weight(T::WeightInfo::create_weighted_pool(assets.len() as u32))]
+		pub fn create_weighted_pool(
+			origin: OriginFor<T>,
+			assets: BoundedVec<(AssetKindOf<T>, Perbill), T::MaxWeightedAssets>,
+			fee_tier: u16,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			ensure!(assets.len() >= 2, Error::<T>::InvalidWeightedPool);
+			let total_weight: u64 =
+				assets.iter().map(|(_, weight)| u64::from(weight.deconstruct())).sum();
+			ensure!(
+				total_weight == u64::from(Perbill::from_percent(100).deconstruct()) &&
+					assets.iter().all(|(_, weight)| *weight >= MIN_WEIGHT),
+				Error::<T>::InvalidWeightedPool
+			);
+			let mut sorted = assets.clone().into_inner();
+			sorted.sort_by_key(|(asset, _)| asset.encode());
+			sorted.dedup_by(|(asset_a, _), (asset_b, _)| asset_a == asset_b);
+			ensure!(sorted.len() == assets.len(), Error::<T>::InvalidWeightedPool);
+
+			let (sorted_assets, weights): (Vec<_>, Vec<_>) = sorted.into_iter().unzip();
+			let more_reserves = sorted_assets[2..]
+				.iter()
+				.map(|asset| (asset.clone(), Zero::zero()))
+				.collect::<Vec<_>>()
+				.try_into()
+				.map_err(|_| Error::<T>::InvalidWeightedPool)?;
+			let weights = weights.try_into().map_err(|_| Error::<T>::InvalidWeightedPool)?;
+			let kind = PoolKind::Weighted(WeightedPool { more_reserves, weights });
+			let (lp_id, deposit) = Self::insert_new_pool(&who, &sorted_assets, fee_tier, kind)?;
+
+			Self::deposit_event(Event::WeightedPoolCreated {
+				who,
+				assets,
+				fee_tier,
+				lp_id,
+				deposit,
+			});
+			Ok(())
+		}
+
+		/// Mints exactly `lp_amount` LP tokens of the weighted pool of `lp_id` for depositing the
+		/// same share of each of its reserves, rounded up. `max_amounts` can give the most of any
+		/// of its assets the caller will pay. The first deposit sets the pool's starting balances
+		/// and so its prices: it pays `max_amounts` as they are, which must fund every asset, and
+		/// `lp_amount` becomes the pool's supply, of which the `MinimumLiquidity` is locked
+		/// forever.
+		#[pallet::call_index(28)]
+		#[pallet::weight(T::WeightInfo::add_weighted_liquidity(T::MaxWeightedAssets::get()))]
+		pub fn add_weighted_liquidity(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			lp_amount: AssetBalanceOf<T>,
+			max_amounts: BoundedVec<(AssetKindOf<T>, AssetBalanceOf<T>), T::MaxWeightedAssets>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::do_add_weighted_liquidity(&who, &lp_id, lp_amount, &max_amounts)?;
+			Ok(())
+		}
+
+		/// Burns `lp_amount` of the caller's LP tokens of the weighted pool of `lp_id` and pays
+		/// out the same share of each of its reserves. `min_amounts` can give the least of any
+		/// of its assets the caller accepts.
+		#[pallet::call_index(29)]
+		#[pallet::weight(T::WeightInfo::remove_weighted_liquidity(T::MaxWeightedAssets::get()))]
+		pub fn remove_weighted_liquidity(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			lp_amount: AssetBalanceOf<T>,
+			min_amounts: BoundedVec<(AssetKindOf<T>, AssetBalanceOf<T>), T::MaxWeightedAssets>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			let pool = <PoolMap<T>>::get(&lp_id).ok_or(Error::<T>::NoPool)?;
+			ensure!(matches!(pool.kind, PoolKind::Weighted(_)), Error::<T>::UnsupportedPoolKind);
+			Self::do_remove_liquidity_proportionally(&who, &lp_id, lp_amount, &min_amounts)?;
+			Ok(())
+		}
+
+		/// Deposits `amount_in` of a single `asset` of the weighted pool of `lp_id`, failing if
+		/// fewer than `min_lp` LP tokens are minted, like Balancer's `joinswapExternAmountIn`.
+		/// The deposit is worth as much as swapping the part of it beyond `asset`'s weight for
+		/// the other assets and depositing them all, so that part pays the swap fee. At most half
+		/// the reserve of `asset` can be deposited at once.
+		#[pallet::call_index(30)]
+		#[pallet::weight(T::WeightInfo::add_weighted_liquidity_single())]
+		pub fn add_weighted_liquidity_single(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			asset: AssetKindOf<T>,
+			amount_in: AssetBalanceOf<T>,
+			min_lp: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::do_add_weighted_liquidity_single(&who, &lp_id, &asset, amount_in, min_lp)?;
+			Ok(())
+		}
+
+		/// Burns `lp_amount` of the caller's LP tokens of the weighted pool of `lp_id` for a
+		/// single `asset` of it, failing if less than `min_amount` is paid out, like Balancer's
+		/// `exitswapPoolAmountIn`. The part of the payout beyond `asset`'s weight pays the swap
+		/// fee, and at most a third of the reserve of `asset` can be paid out at once.
+		#[pallet::call_index(31)]
+		#[pallet::weight(T::WeightInfo::remove_weighted_liquidity_single())]
+		pub fn remove_weighted_liquidity_single(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			lp_amount: AssetBalanceOf<T>,
+			asset: AssetKindOf<T>,
+			min_amount: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::do_remove_weighted_liquidity_single(&who, &lp_id, lp_amount, &asset, min_amount)?;
+			Ok(())
+		}
+
+		/// Creates a concentrated liquidity pool of `asset_a` and `asset_b` with `fee_tier`,
+		/// which must be one of the allowed `FeeTiers`, at `price`, the price of `asset_a` in
+		/// `asset_b`. Liquidity is provided to it by positions over ranges of prices, as in
+		/// Uniswap V3, so it has no LP token. The pool is swapped with as the pool of its pair
+		/// with `fee_tier`, so that pair may not have one yet. The deposit is held as
+		/// `create_pool` does.
+		#[pallet::call_index(32)]
+		#[pallet::weight(T::WeightInfo::create_concentrated_pool())]
+		pub fn create_concentrated_pool(
+			origin: OriginFor<T>,
+			asset_a: AssetKindOf<T>,
+			asset_b: AssetKindOf<T>,
+			fee_tier: u16,
+			price: FixedU128,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			ensure!(asset_a != asset_b, Error::<T>::SameAsset);
+			let (asset_1, asset_2, _) = Self::pair_key(&asset_a, &asset_b, fee_tier);
+			// the pool follows the price of its first asset in its second
+			let pool_price = if asset_a == asset_1 { Some(price) } else { price.reciprocal() };
+			let sqrt_price = pool_price
+				.and_then(concentrated_math::sqrt_price_of)
+				.ok_or(Error::<T>::InvalidPrice)?;
+			// swaps stop short of the extreme prices, so the pool can't start at them
+			let tick = concentrated_math::tick_at_sqrt_price(sqrt_price)
+				.filter(|tick| {
+					(concentrated_math::MIN_TICK + 1..concentrated_math::MAX_TICK - 1)
+						.contains(tick)
+				})
+				.ok_or(Error::<T>::InvalidPrice)?;
+			let kind = PoolKind::Concentrated(ConcentratedPool::new(sqrt_price, tick));
+			let (lp_id, deposit) =
+				Self::insert_new_pool(&who, &[asset_1, asset_2], fee_tier, kind)?;
+
+			Self::deposit_event(Event::ConcentratedPoolCreated {
+				who,
+				asset_a,
+				asset_b,
+				fee_tier,
+				price,
+				lp_id,
+				deposit,
+			});
+			Ok(())
+		}
+
+		/// Mints a position of `liquidity` between `tick_lower` and `tick_upper` in the
+		/// concentrated pool of `lp_id`, paying in what that liquidity holds of the pool's first
+		/// and second asset at the current price: only the first below the range, and only the
+		/// second above it. Fails if that is more than `amount_1_max` or `amount_2_max`. The
+		/// ticks must be multiples of the pool's tick spacing, twice its fee tier. The position
+		/// earns the swap fees paid while the price is in its range.
+		#[pallet::call_index(33)]
+		#[pallet::weight(T::WeightInfo::mint_position())]
+		pub fn mint_position(
+			origin: OriginFor<T>,
+			lp_id: AssetIdOf<T>,
+			tick_lower: i32,
+			tick_upper: i32,
+			liquidity: u128,
+			amount_1_max: AssetBalanceOf<T>,
+			amount_2_max: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::do_mint_position(
+				&who,
+				&lp_id,
+				tick_lower,
+				tick_upper,
+				liquidity,
+				amount_1_max,
+				amount_2_max,
+			)?;
+			Ok(())
+		}
+
+		/// Burns `liquidity` of the caller's position `position_id` and pays out what it holds
+		/// of its pool's assets at the current price, failing if that is less than
+		/// `amount_1_min` of the first or `amount_2_min` of the second. Burning all of it also
+		/// pays out the position's fees and removes it.
+		#[pallet::call_index(34)]
+		#[pallet::weight(T::WeightInfo::burn_position())]
+		pub fn burn_position(
+			origin: OriginFor<T>,
+			position_id: u64,
+			liquidity: u128,
+			amount_1_min: AssetBalanceOf<T>,
+			amount_2_min: AssetBalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::do_burn_position(&who, position_id, liquidity, amount_1_min, amount_2_min)?;
+			Ok(())
+		}
+
+		/// Pays out the swap fees the caller's position `position_id` has earned so far.
+		#[pallet::call_index(35)]
+		#[pallet::weight(T::WeightInfo::collect_fees())]
+		pub fn collect_fees(origin: OriginFor<T>, position_id: u64) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_unlocked()?;
+			Self::do_collect_fees(&who, position_id)?;
+			Ok(())
+		}
+	}
+}
+
+/// A quote for a swap against a single pool, as returned by the `DexApi` runtime API.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Quote<Balance> {
+	/// The amount the caller pays in, including the fee.
+	pub amount_in: Balance,
+	/// The amount the caller receives.
+	pub amount_out: Balance,
+	/// The part of `amount_in` that is left in the pool for the LPs.
+	pub fee: Balance,
+	/// How much worse the execution price is than the current spot price, ignoring the fee.
+	pub price_impact: Permill,
+}
+
+/// A snapshot of a pool, as returned by the `DexApi` runtime API.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct PoolInfo<AssetId, Balance> {
+	/// The asset id of the pool's LP token.
+	pub lp_id: AssetId,
+	/// The first asset of the pair, in sorted order.
+	pub asset_1: NativeOrAsset<AssetId>,
+	/// The reserve of `asset_1`.
+	pub amount_1: Balance,
+	/// The second asset of the pair, in sorted order.
+	pub asset_2: NativeOrAsset<AssetId>,
+	/// The reserve of `asset_2`.
+	pub amount_2: Balance,
+	/// The swap fee of the pool in basis points.
+	pub fee_tier: u16,
+	/// The total supply of the LP token.
+	pub lp_supply: Balance,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Calculates the amount of LP tokens.
+	///
+	/// For existing pools, the formula is `lp = sqrt((A+a)*(B+b)) - sqrt(A*B)`. `A` and `B` are the
+	/// current pool amounts and `a` and `b` are the amounts to add. For new pools, `lp =
+	/// sqrt(a*b)`.
+	fn calculate_lp(
+		new_pair: &PoolPair<T>,
+		pool: Option<&Pool<T>>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		if let Some(pool) = pool {
+			// Calculate LP for existing pool
+			let total_1 = pool
+				.pool_pair
+				.amount_1
+				.checked_add(&new_pair.amount_1)
+				.ok_or(ArithmeticError::Overflow)?;
+			let total_2 = pool
+				.pool_pair
+				.amount_2
 				.checked_add(&new_pair.amount_2)
 				.ok_or(ArithmeticError::Overflow)?;
 			let total_product = total_1.checked_mul(&total_2).ok_or(ArithmeticError::Overflow)?;
 			let sqrt_total = IntegerSquareRoot::integer_sqrt(&total_product);
 
-			let current_product = pool
-				.pool_pair
-				.amount_1
-				.checked_mul(&pool.pool_pair.amount_2)
-				.ok_or(ArithmeticError::Overflow)?;
-			let sqrt_current = IntegerSquareRoot::integer_sqrt(&current_product);
+			let current_product = pool
+				.pool_pair
+				.amount_1
+				.checked_mul(&pool.pool_pair.amount_2)
+				.ok_or(ArithmeticError::Overflow)?;
+			let sqrt_current = IntegerSquareRoot::integer_sqrt(&current_product);
+
+			let lp = sqrt_total.checked_sub(&sqrt_current).ok_or(ArithmeticError::Underflow)?;
+			Ok(lp)
+		} else {
+			// Calculate LP for new pool: sqrt(a * b)
+			let product = new_pair
+				.amount_1
+				.checked_mul(&new_pair.amount_2)
+				.ok_or(ArithmeticError::Overflow)?;
+			Ok(IntegerSquareRoot::integer_sqrt(&product))
+		}
+	}
+
+	/// Creates a pool without liquidity for `who` as `create_pool` does and returns its LP token
+	/// id.
+	pub fn do_create_pool(
+		who: &T::AccountId,
+		asset_a: AssetKindOf<T>,
+		asset_b: AssetKindOf<T>,
+		fee_tier: u16,
+	) -> Result<AssetIdOf<T>, DispatchError> {
+		ensure!(asset_a != asset_b, Error::<T>::SameAsset);
+		let (asset_1, asset_2, _) = Self::pair_key(&asset_a, &asset_b, fee_tier);
+		let (cur_lp_id, deposit) =
+			Self::insert_new_pool(who, &[asset_1, asset_2], fee_tier, PoolKind::ConstantProduct)?;
+
+		Self::deposit_event(Event::PoolCreated {
+			who: who.clone(),
+			asset_a,
+			asset_b,
+			fee_tier,
+			lp_id: cur_lp_id.clone(),
+			deposit,
+		});
+		Ok(cur_lp_id)
+	}
+
+	// creates a pool of `kind` without liquidity for `who` from distinct `assets` in sorted order,
+	// indexing it under every pair of them, and returns its LP token id and the deposit held
+	fn insert_new_pool(
+		who: &T::AccountId,
+		assets: &[AssetKindOf<T>],
+		fee_tier: u16,
+		kind: PoolKind<T>,
+	) -> Result<(AssetIdOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let pair_keys = Self::pair_keys(assets, fee_tier);
+		ensure!(
+			pair_keys.iter().all(|pair_key| !<PairToLp<T>>::contains_key(pair_key)),
+			Error::<T>::PoolExists
+		);
+		ensure!(<FeeTiers<T>>::get().contains(&fee_tier), Error::<T>::InvalidFeeTier);
+		let pool_count = <PoolCount<T>>::get();
+		ensure!(pool_count < T::MaxPools::get(), Error::<T>::TooManyPools);
+
+		let deposit = T::PoolCreationDeposit::get();
+		if !deposit.is_zero() {
+			<T::NativeBalance as fungible::hold::Mutate<T::AccountId>>::hold(
+				&HoldReason::PoolCreation.into(),
+				who,
+				deposit,
+			)?;
+		}
+		let cur_lp_id = Self::allocate_lp_id()?;
+		for pair_key in &pair_keys {
+			<PairToLp<T>>::insert(pair_key, &cur_lp_id);
+		}
+		<LpToPair<T>>::insert(&cur_lp_id, &pair_keys[0]);
+		<PoolDeposits<T>>::insert(&cur_lp_id, (who.clone(), deposit));
+
+		let pool_pair =
+			PoolPair::<T>::new(assets[0].clone(), Zero::zero(), assets[1].clone(), Zero::zero())?;
+		// positions of concentrated pools aren't fungible, so they have no LP token
+		if !matches!(kind, PoolKind::Concentrated(_)) {
+			T::Fungibles::create(
+				cur_lp_id.clone(),
+				Self::account_id(),
+				true,
+				T::LpMinBalance::get(),
+			)?;
+			Self::set_lp_metadata(who, &cur_lp_id, assets, fee_tier)?;
+		}
+		// keeps the pool's account alive, so it can hold assets that aren't sufficient
+		frame_system::Pallet::<T>::inc_providers(&Self::pool_account(&cur_lp_id));
+		<PoolMap<T>>::insert(
+			&cur_lp_id,
+			Pool::<T> { kind, ..Pool::<T>::new(pool_pair, fee_tier, Zero::zero(), Self::now()) },
+		);
+		<PoolCount<T>>::put(pool_count + 1);
+		Ok((cur_lp_id, deposit))
+	}
+
+	// names the LP token of a new pool after its assets, as `A/B 30bps LP` and `A-B-LP`, and
+	// gives it the mean of their decimals, as LP tokens are minted as the geometric mean of the
+	// amounts deposited, or as their sum in StableSwap pools. The DEX's account owns the LP token
+	// and reserves its metadata deposit, so `who` pays the deposit into it, along with the
+	// existential deposit if the account doesn't exist yet
+	fn set_lp_metadata(
+		who: &T::AccountId,
+		lp_id: &AssetIdOf<T>,
+		assets: &[AssetKindOf<T>],
+		fee_tier: u16,
+	) -> DispatchResult {
+		let part_length = MAX_LP_SYMBOL_PART * 2 / assets.len();
+		let symbols: Vec<Vec<u8>> = assets
+			.iter()
+			.map(|asset| {
+				let mut symbol = NativeOrAssetUnion::<T>::symbol(asset);
+				symbol.truncate(part_length);
+				symbol
+			})
+			.collect();
+		let name = [&symbols.join(&b'/')[..], format!(" {}bps LP", fee_tier).as_bytes()].concat();
+		let symbol = [&symbols.join(&b'-')[..], b"-LP"].concat();
+		let decimals = assets
+			.iter()
+			.map(|asset| u16::from(NativeOrAssetUnion::<T>::decimals(asset)))
+			.sum::<u16>() /
+			assets.len() as u16;
+
+		let dex_account = Self::account_id();
+		let deposit = <T::Fungibles as fungibles::metadata::MetadataDeposit<
+			AssetBalanceOf<T>,
+		>>::calc_metadata_deposit(&name, &symbol);
+		if !deposit.is_zero() {
+			let missing = <T::NativeBalance as fungible::Inspect<T::AccountId>>::minimum_balance()
+				.saturating_sub(
+					<T::NativeBalance as fungible::Inspect<T::AccountId>>::total_balance(
+						&dex_account,
+					),
+				);
+			NativeOrAssetUnion::<T>::transfer(
+				&NativeOrAsset::Native,
+				who,
+				&dex_account,
+				deposit.saturating_add(missing),
+				Protect,
+			)?;
+		}
+		<T::Fungibles as fungibles::metadata::Mutate<T::AccountId>>::set(
+			lp_id.clone(),
+			&dex_account,
+			name,
+			symbol,
+			decimals.saturated_into(),
+		)
+	}
+
+	// destroys the LP token of a pool without liquidity, if it was created by the DEX, and
+	// refunds the metadata deposit this unreserves to `depositor`. With no supply left there are
+	// no accounts of it to remove first
+	fn destroy_lp_asset(lp_id: &AssetIdOf<T>, depositor: &T::AccountId) -> DispatchResult {
+		if !T::Fungibles::asset_exists(lp_id.clone()) {
+			return Ok(())
+		}
+		let dex_account = Self::account_id();
+		let free_balance =
+			|| <T::NativeBalance as fungible::Inspect<T::AccountId>>::balance(&dex_account);
+		let free_before = free_balance();
+		<T::Fungibles as fungibles::Destroy<T::AccountId>>::start_destroy(
+			lp_id.clone(),
+			Some(dex_account.clone()),
+		)?;
+		<T::Fungibles as fungibles::Destroy<T::AccountId>>::finish_destroy(lp_id.clone())?;
+
+		let refund = free_balance().saturating_sub(free_before);
+		if !refund.is_zero() && *depositor != dex_account {
+			NativeOrAssetUnion::<T>::transfer(
+				&NativeOrAsset::Native,
+				&dex_account,
+				depositor,
+				refund,
+				Protect,
+			)?;
+		}
+		Ok(())
+	}
+
+	/// Deposits liquidity for `who` as `add_liquidity` does and returns the LP tokens minted.
+	fn do_add_liquidity(
+		who: &T::AccountId,
+		asset_a: AssetKindOf<T>,
+		asset_b: AssetKindOf<T>,
+		fee_tier: u16,
+		amount_a_desired: AssetBalanceOf<T>,
+		amount_b_desired: AssetBalanceOf<T>,
+		amount_a_min: AssetBalanceOf<T>,
+		amount_b_min: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		// depositing into a pool that doesn't exist yet creates it, taking the deposit
+		let cur_lp_id = match Self::get_lp_id(&asset_a, &asset_b, fee_tier) {
+			Ok(lp_id) => lp_id,
+			Err(_) => Self::do_create_pool(who, asset_a.clone(), asset_b.clone(), fee_tier)?,
+		};
+		Self::mint_protocol_fee(&cur_lp_id)?;
+		let pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		if let PoolKind::StableSwap(_) = pool.kind {
+			// a StableSwap pool takes deposits in any ratio, so the desired amounts go in as they
+			// are
+			return Self::do_add_stable_liquidity(
+				who,
+				&cur_lp_id,
+				&[(asset_a, amount_a_desired), (asset_b, amount_b_desired)],
+				Zero::zero(),
+			)
+		}
+		// a weighted pool is joined through its own calls, as the amounts of a pair can't keep
+		// its other assets at their weights, and a concentrated pool through positions
+		ensure!(
+			!matches!(pool.kind, PoolKind::Weighted(_) | PoolKind::Concentrated(_)),
+			Error::<T>::UnsupportedPoolKind
+		);
+		// a pool without liquidity takes the desired amounts as they are
+		let funded_pool = Some(&pool).filter(|pool| !pool.lp_supply.is_zero());
+		let (amount_a, amount_b) = Self::liquidity_amounts(
+			&asset_a,
+			&asset_b,
+			funded_pool,
+			amount_a_desired,
+			amount_b_desired,
+			amount_a_min,
+			amount_b_min,
+		)?;
+		let add_amounts = PoolPair::<T>::new(asset_a.clone(), amount_a, asset_b.clone(), amount_b)?;
+		let lp_amount;
+		match funded_pool {
+			None => {
+				// First deposit, which sets the price
+				let lp_supply = Self::calculate_lp(&add_amounts, None)?;
+				let minimum_liquidity = T::MinimumLiquidity::get();
+				ensure!(lp_supply > minimum_liquidity, Error::<T>::InsufficientLiquidityMinted);
+				lp_amount = lp_supply - minimum_liquidity;
+				// locking the minimum liquidity stops the first depositor from pricing the LP
+				// token so high that later deposits round down to nothing
+				T::Fungibles::mint_into(
+					cur_lp_id.clone(),
+					&Self::locked_liquidity_account(),
+					minimum_liquidity,
+				)?;
+				T::Fungibles::mint_into(cur_lp_id.clone(), who, lp_amount)?;
+				let mut new_pool = pool.clone();
+				new_pool.pool_pair = add_amounts;
+				new_pool.lp_supply = lp_supply;
+				new_pool.block_timestamp_last = Self::now();
+				Self::update_k_last(&mut new_pool)?;
+				Self::record_observation(&cur_lp_id, &new_pool);
+				<PoolMap<T>>::insert(&cur_lp_id, new_pool);
+			},
+			Some(existing_pool) => {
+				lp_amount = Self::calculate_lp(&add_amounts, Some(existing_pool))?;
+				ensure!(!lp_amount.is_zero(), Error::<T>::InsufficientLiquidityMinted);
+				T::Fungibles::mint_into(cur_lp_id.clone(), who, lp_amount)?;
+				Self::increase_pool(&add_amounts, &lp_amount, &cur_lp_id)?;
+			},
+		}
+
+		// only the amounts actually deposited leave the caller's account
+		let pool_account = Self::pool_account(&cur_lp_id);
+		NativeOrAssetUnion::<T>::transfer(&asset_a, who, &pool_account, amount_a, Expendable)?;
+		NativeOrAssetUnion::<T>::transfer(&asset_b, who, &pool_account, amount_b, Expendable)?;
+
+		Self::deposit_event(Event::LiquidityAdded {
+			asset_a,
+			asset_b,
+			fee_tier,
+			amount_a,
+			amount_b,
+			amount_lp: lp_amount,
+		});
+		Ok(lp_amount)
+	}
+
+	/// Withdraws liquidity for `who` as `remove_liquidity` does and returns the amounts of
+	/// `asset_a` and `asset_b` paid out.
+	fn do_remove_liquidity(
+		who: &T::AccountId,
+		asset_a: AssetKindOf<T>,
+		asset_b: AssetKindOf<T>,
+		fee_tier: u16,
+		token_amount: AssetBalanceOf<T>,
+		amount_a_min: AssetBalanceOf<T>,
+		amount_b_min: AssetBalanceOf<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let cur_lp_id = Self::get_lp_id(&asset_a, &asset_b, fee_tier)?;
+		// LP tokens staked in a farm are frozen and can't be withdrawn
+		ensure!(
+			T::Fungibles::reducible_balance(cur_lp_id.clone(), who, Expendable, Polite) >=
+				token_amount,
+			Error::<T>::InsufficientLPBalance
+		);
+
+		Self::mint_protocol_fee(&cur_lp_id)?;
+		let pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+		// unlike swaps, withdrawals stay open while swaps are paused
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		if !matches!(pool.kind, PoolKind::ConstantProduct) {
+			// the pool's other assets are paid out as well
+			let amounts = Self::do_remove_liquidity_proportionally(
+				who,
+				&cur_lp_id,
+				token_amount,
+				&[(asset_a.clone(), amount_a_min), (asset_b.clone(), amount_b_min)],
+			)?;
+			let amount_of = |asset: &AssetKindOf<T>| {
+				amounts
+					.iter()
+					.find(|(paid_asset, _)| paid_asset == asset)
+					.map(|(_, amount)| *amount)
+					.unwrap_or_default()
+			};
+			return Ok((amount_of(&asset_a), amount_of(&asset_b)))
+		}
+		let amount_1 = pool
+			.pool_pair
+			.amount_1
+			.checked_mul(&token_amount)
+			.ok_or(ArithmeticError::Overflow)? /
+			pool.lp_supply;
+		let amount_2 = pool
+			.pool_pair
+			.amount_2
+			.checked_mul(&token_amount)
+			.ok_or(ArithmeticError::Overflow)? /
+			pool.lp_supply;
+		let (amount_a, amount_b) = if asset_a == pool.pool_pair.asset_1 {
+			(amount_1, amount_2)
+		} else {
+			(amount_2, amount_1)
+		};
+		ensure!(amount_a >= amount_a_min, Error::<T>::InsufficientAmountA);
+		ensure!(amount_b >= amount_b_min, Error::<T>::InsufficientAmountB);
+
+		T::Fungibles::burn_from(cur_lp_id.clone(), who, token_amount, Exact, Polite)?;
+		Self::decrease_pool(&amount_1, &amount_2, &token_amount, &cur_lp_id)?;
+		let pool_account = Self::pool_account(&cur_lp_id);
+		NativeOrAssetUnion::<T>::transfer(&asset_a, &pool_account, who, amount_a, Expendable)?;
+		NativeOrAssetUnion::<T>::transfer(&asset_b, &pool_account, who, amount_b, Expendable)?;
+
+		Self::deposit_event(Event::LiquidityRemoved {
+			asset_a,
+			asset_b,
+			fee_tier,
+			amount_a,
+			amount_b,
+		});
+		Ok((amount_a, amount_b))
+	}
+
+	/// Deposits `amounts` into the StableSwap pool of `lp_id` for `who` as `add_stable_liquidity`
+	/// does and returns the LP tokens minted.
+	fn do_add_stable_liquidity(
+		who: &T::AccountId,
+		lp_id: &AssetIdOf<T>,
+		amounts: &[(AssetKindOf<T>, AssetBalanceOf<T>)],
+		min_lp: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut pool = <PoolMap<T>>::get(lp_id).ok_or(Error::<T>::NoPool)?;
+		let amplification = Self::amplification(&pool)?;
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		let first_deposit = pool.lp_supply.is_zero();
+		if !first_deposit {
+			Self::update_oracle(lp_id, &mut pool);
+		}
+
+		let mut new_pool = pool.clone();
+		for (asset, amount) in amounts {
+			let reserve = new_pool.reserve(asset).ok_or(Error::<T>::AssetNotInPool)?;
+			new_pool.set_reserve(
+				asset,
+				reserve.checked_add(amount).ok_or(ArithmeticError::Overflow)?,
+			)?;
+		}
+		// the invariant is only defined once every reserve is funded
+		ensure!(
+			!first_deposit || new_pool.reserves().iter().all(|(_, reserve)| !reserve.is_zero()),
+			Error::<T>::InvalidStablePool
+		);
+		let minted = stable_swap::mint_amount(
+			&Self::stable_reserves(&pool),
+			&Self::stable_reserves(&new_pool),
+			Self::to_u256(pool.lp_supply),
+			amplification,
+			pool.fee_tier,
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		let minted = Self::from_u256(minted)?;
+
+		let lp_amount = if first_deposit {
+			// locked for the same reason as in a constant product pool
+			let minimum_liquidity = T::MinimumLiquidity::get();
+			ensure!(minted > minimum_liquidity, Error::<T>::InsufficientLiquidityMinted);
+			T::Fungibles::mint_into(
+				lp_id.clone(),
+				&Self::locked_liquidity_account(),
+				minimum_liquidity,
+			)?;
+			new_pool.block_timestamp_last = Self::now();
+			minted - minimum_liquidity
+		} else {
+			minted
+		};
+		ensure!(!lp_amount.is_zero(), Error::<T>::InsufficientLiquidityMinted);
+		ensure!(lp_amount >= min_lp, Error::<T>::SlippageTooHigh);
+		T::Fungibles::mint_into(lp_id.clone(), who, lp_amount)?;
+		new_pool.lp_supply =
+			pool.lp_supply.checked_add(&minted).ok_or(ArithmeticError::Overflow)?;
+		if first_deposit {
+			Self::record_observation(lp_id, &new_pool);
+		}
+		<PoolMap<T>>::insert(lp_id, new_pool);
+
+		let pool_account = Self::pool_account(lp_id);
+		for (asset, amount) in amounts {
+			Self::transfer_nonzero(asset, who, &pool_account, *amount)?;
+		}
 
-			let lp = sqrt_total.checked_sub(&sqrt_current).ok_or(ArithmeticError::Underflow)?;
-			Ok(lp)
+		Self::deposit_event(Event::StableLiquidityAdded {
+			who: who.clone(),
+			lp_id: lp_id.clone(),
+			amounts: amounts.to_vec(),
+			amount_lp: lp_amount,
+		});
+		Ok(lp_amount)
+	}
+
+	/// Withdraws `lp_amount` worth of every reserve of the StableSwap or weighted pool of `lp_id`
+	/// for `who` as `remove_stable_liquidity` and `remove_weighted_liquidity` do and returns the
+	/// amounts paid out, in the pool's order of its assets.
+	fn do_remove_liquidity_proportionally(
+		who: &T::AccountId,
+		lp_id: &AssetIdOf<T>,
+		lp_amount: AssetBalanceOf<T>,
+		min_amounts: &[(AssetKindOf<T>, AssetBalanceOf<T>)],
+	) -> Result<Vec<(AssetKindOf<T>, AssetBalanceOf<T>)>, DispatchError> {
+		// LP tokens staked in a farm are frozen and can't be withdrawn
+		ensure!(
+			T::Fungibles::reducible_balance(lp_id.clone(), who, Expendable, Polite) >= lp_amount,
+			Error::<T>::InsufficientLPBalance
+		);
+		let mut pool = <PoolMap<T>>::get(lp_id).ok_or(Error::<T>::NoPool)?;
+		ensure!(
+			matches!(pool.kind, PoolKind::StableSwap(_) | PoolKind::Weighted(_)),
+			Error::<T>::UnsupportedPoolKind
+		);
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		Self::update_oracle(lp_id, &mut pool);
+
+		let supply = Self::to_u256(pool.lp_supply);
+		let mut amounts = Vec::new();
+		for (asset, reserve) in pool.reserves() {
+			let amount = Self::to_u256(reserve)
+				.saturating_mul(Self::to_u256(lp_amount))
+				.checked_div(supply)
+				.ok_or(ArithmeticError::DivisionByZero)?;
+			let amount = Self::from_u256(amount)?;
+			pool.set_reserve(
+				&asset,
+				reserve.checked_sub(&amount).ok_or(ArithmeticError::Underflow)?,
+			)?;
+			amounts.push((asset, amount));
+		}
+		for (asset, amount_min) in min_amounts {
+			let (_, amount) = amounts
+				.iter()
+				.find(|(paid_asset, _)| paid_asset == asset)
+				.ok_or(Error::<T>::AssetNotInPool)?;
+			ensure!(amount >= amount_min, Error::<T>::SlippageTooHigh);
+		}
+
+		T::Fungibles::burn_from(lp_id.clone(), who, lp_amount, Exact, Polite)?;
+		pool.lp_supply =
+			pool.lp_supply.checked_sub(&lp_amount).ok_or(ArithmeticError::Underflow)?;
+		let weighted = matches!(pool.kind, PoolKind::Weighted(_));
+		// the locked minimum liquidity keeps the supply above zero, so the pool is always kept
+		<PoolMap<T>>::insert(lp_id, pool);
+		let pool_account = Self::pool_account(lp_id);
+		for (asset, amount) in &amounts {
+			Self::transfer_nonzero(asset, &pool_account, who, *amount)?;
+		}
+
+		let (who, lp_id, amount_lp) = (who.clone(), lp_id.clone(), lp_amount);
+		Self::deposit_event(if weighted {
+			Event::WeightedLiquidityRemoved { who, lp_id, amount_lp, amounts: amounts.clone() }
 		} else {
-			// Calculate LP for new pool: sqrt(a * b)
-			let product = new_pair
-				.amount_1
-				.checked_mul(&new_pair.amount_2)
-				.ok_or(ArithmeticError::Overflow)?;
-			Ok(IntegerSquareRoot::integer_sqrt(&product))
+			Event::StableLiquidityRemoved { who, lp_id, amount_lp, amounts: amounts.clone() }
+		});
+		Ok(amounts)
+	}
+
+	/// Deposits into the weighted pool of `lp_id` for `who` as `add_weighted_liquidity` does and
+	/// returns the amounts paid in, in the pool's order of its assets.
+	fn do_add_weighted_liquidity(
+		who: &T::AccountId,
+		lp_id: &AssetIdOf<T>,
+		lp_amount: AssetBalanceOf<T>,
+		max_amounts: &[(AssetKindOf<T>, AssetBalanceOf<T>)],
+	) -> Result<Vec<(AssetKindOf<T>, AssetBalanceOf<T>)>, DispatchError> {
+		let mut pool = <PoolMap<T>>::get(lp_id).ok_or(Error::<T>::NoPool)?;
+		ensure!(matches!(pool.kind, PoolKind::Weighted(_)), Error::<T>::UnsupportedPoolKind);
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		for (asset, _) in max_amounts {
+			ensure!(pool.contains(asset), Error::<T>::AssetNotInPool);
+		}
+		let max_amount_of = |asset: &AssetKindOf<T>| {
+			max_amounts
+				.iter()
+				.find(|(max_asset, _)| max_asset == asset)
+				.map(|(_, amount)| *amount)
+		};
+		let first_deposit = pool.lp_supply.is_zero();
+		if !first_deposit {
+			Self::update_oracle(lp_id, &mut pool);
+		}
+
+		let supply = Self::to_u256(pool.lp_supply);
+		let mut amounts = Vec::new();
+		for (asset, reserve) in pool.reserves() {
+			let amount = if first_deposit {
+				// the first deposit sets the balances, and so the prices, the pool starts from
+				max_amount_of(&asset)
+					.filter(|amount| !amount.is_zero())
+					.ok_or(Error::<T>::InvalidWeightedPool)?
+			} else {
+				// rounded up, so joining never takes value from the other LPs
+				let amount = Self::to_u256(reserve)
+					.saturating_mul(Self::to_u256(lp_amount))
+					.saturating_add(supply.saturating_sub(U256::one()))
+					.checked_div(supply)
+					.ok_or(ArithmeticError::DivisionByZero)?;
+				let amount = Self::from_u256(amount)?;
+				ensure!(
+					max_amount_of(&asset).map_or(true, |max_amount| amount <= max_amount),
+					Error::<T>::SlippageTooHigh
+				);
+				amount
+			};
+			pool.set_reserve(
+				&asset,
+				reserve.checked_add(&amount).ok_or(ArithmeticError::Overflow)?,
+			)?;
+			amounts.push((asset, amount));
+		}
+
+		let amount_lp = if first_deposit {
+			// locked for the same reason as in a constant product pool
+			let minimum_liquidity = T::MinimumLiquidity::get();
+			ensure!(lp_amount > minimum_liquidity, Error::<T>::InsufficientLiquidityMinted);
+			T::Fungibles::mint_into(
+				lp_id.clone(),
+				&Self::locked_liquidity_account(),
+				minimum_liquidity,
+			)?;
+			pool.block_timestamp_last = Self::now();
+			lp_amount - minimum_liquidity
+		} else {
+			lp_amount
+		};
+		ensure!(!amount_lp.is_zero(), Error::<T>::InsufficientLiquidityMinted);
+		T::Fungibles::mint_into(lp_id.clone(), who, amount_lp)?;
+		pool.lp_supply = pool.lp_supply.checked_add(&lp_amount).ok_or(ArithmeticError::Overflow)?;
+		if first_deposit {
+			Self::record_observation(lp_id, &pool);
+		}
+		<PoolMap<T>>::insert(lp_id, pool);
+
+		let pool_account = Self::pool_account(lp_id);
+		for (asset, amount) in &amounts {
+			Self::transfer_nonzero(asset, who, &pool_account, *amount)?;
+		}
+
+		Self::deposit_event(Event::WeightedLiquidityAdded {
+			who: who.clone(),
+			lp_id: lp_id.clone(),
+			amounts: amounts.clone(),
+			amount_lp,
+		});
+		Ok(amounts)
+	}
+
+	/// Deposits `amount_in` of `asset` into the weighted pool of `lp_id` for `who` as
+	/// `add_weighted_liquidity_single` does and returns the LP tokens minted.
+	fn do_add_weighted_liquidity_single(
+		who: &T::AccountId,
+		lp_id: &AssetIdOf<T>,
+		asset: &AssetKindOf<T>,
+		amount_in: AssetBalanceOf<T>,
+		min_lp: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let mut pool = <PoolMap<T>>::get(lp_id).ok_or(Error::<T>::NoPool)?;
+		ensure!(matches!(pool.kind, PoolKind::Weighted(_)), Error::<T>::UnsupportedPoolKind);
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		// the first deposit must fund every asset
+		ensure!(!pool.lp_supply.is_zero(), Error::<T>::InsufficientLiquidity);
+		Self::update_oracle(lp_id, &mut pool);
+
+		let reserve = pool.reserve(asset).ok_or(Error::<T>::AssetNotInPool)?;
+		let weight = pool.weight(asset).ok_or(Error::<T>::AssetNotInPool)?;
+		ensure!(
+			amount_in <= weighted_math::MAX_IN_RATIO.mul_floor(reserve),
+			Error::<T>::TradeTooLarge
+		);
+		// only the part of the deposit beyond the asset's own weight is in effect swapped, so
+		// only that part pays the fee
+		let fee = Self::calculate_fees(&weight.left_from_one().mul_ceil(amount_in), pool.fee_tier)?;
+		let amount_after_fee = amount_in.checked_sub(&fee).ok_or(ArithmeticError::Underflow)?;
+		let minted = weighted_math::lp_out_given_in(
+			reserve.saturated_into(),
+			weight,
+			pool.lp_supply.saturated_into(),
+			amount_after_fee.saturated_into(),
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		let minted = Self::from_u128(minted)?;
+		ensure!(!minted.is_zero(), Error::<T>::InsufficientLiquidityMinted);
+		ensure!(minted >= min_lp, Error::<T>::SlippageTooHigh);
+
+		T::Fungibles::mint_into(lp_id.clone(), who, minted)?;
+		pool.set_reserve(asset, reserve.checked_add(&amount_in).ok_or(ArithmeticError::Overflow)?)?;
+		pool.lp_supply = pool.lp_supply.checked_add(&minted).ok_or(ArithmeticError::Overflow)?;
+		<PoolMap<T>>::insert(lp_id, pool);
+		NativeOrAssetUnion::<T>::transfer(
+			asset,
+			who,
+			&Self::pool_account(lp_id),
+			amount_in,
+			Expendable,
+		)?;
+
+		Self::deposit_event(Event::WeightedLiquidityAdded {
+			who: who.clone(),
+			lp_id: lp_id.clone(),
+			amounts: vec![(asset.clone(), amount_in)],
+			amount_lp: minted,
+		});
+		Ok(minted)
+	}
+
+	/// Burns `lp_amount` of the LP tokens of the weighted pool of `lp_id` for `asset` for `who`
+	/// as `remove_weighted_liquidity_single` does and returns the amount paid out.
+	fn do_remove_weighted_liquidity_single(
+		who: &T::AccountId,
+		lp_id: &AssetIdOf<T>,
+		lp_amount: AssetBalanceOf<T>,
+		asset: &AssetKindOf<T>,
+		min_amount: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		// LP tokens staked in a farm are frozen and can't be withdrawn
+		ensure!(
+			T::Fungibles::reducible_balance(lp_id.clone(), who, Expendable, Polite) >= lp_amount,
+			Error::<T>::InsufficientLPBalance
+		);
+		let mut pool = <PoolMap<T>>::get(lp_id).ok_or(Error::<T>::NoPool)?;
+		ensure!(matches!(pool.kind, PoolKind::Weighted(_)), Error::<T>::UnsupportedPoolKind);
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		Self::update_oracle(lp_id, &mut pool);
+
+		let reserve = pool.reserve(asset).ok_or(Error::<T>::AssetNotInPool)?;
+		let weight = pool.weight(asset).ok_or(Error::<T>::AssetNotInPool)?;
+		let amount_before_fee = weighted_math::out_given_lp_in(
+			reserve.saturated_into(),
+			weight,
+			pool.lp_supply.saturated_into(),
+			lp_amount.saturated_into(),
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		let amount_before_fee = Self::from_u128(amount_before_fee)?;
+		ensure!(
+			amount_before_fee <= weighted_math::MAX_OUT_RATIO.mul_floor(reserve),
+			Error::<T>::TradeTooLarge
+		);
+		// as for a single asset deposit, the part beyond the asset's own weight pays the fee
+		let fee = Self::calculate_fees(
+			&weight.left_from_one().mul_ceil(amount_before_fee),
+			pool.fee_tier,
+		)?;
+		let amount_out = amount_before_fee.checked_sub(&fee).ok_or(ArithmeticError::Underflow)?;
+		ensure!(amount_out >= min_amount, Error::<T>::SlippageTooHigh);
+
+		T::Fungibles::burn_from(lp_id.clone(), who, lp_amount, Exact, Polite)?;
+		pool.set_reserve(
+			asset,
+			reserve.checked_sub(&amount_out).ok_or(ArithmeticError::Underflow)?,
+		)?;
+		pool.lp_supply =
+			pool.lp_supply.checked_sub(&lp_amount).ok_or(ArithmeticError::Underflow)?;
+		<PoolMap<T>>::insert(lp_id, pool);
+		Self::transfer_nonzero(asset, &Self::pool_account(lp_id), who, amount_out)?;
+
+		Self::deposit_event(Event::WeightedLiquidityRemoved {
+			who: who.clone(),
+			lp_id: lp_id.clone(),
+			amount_lp: lp_amount,
+			amounts: vec![(asset.clone(), amount_out)],
+		});
+		Ok(amount_out)
+	}
+
+	/// Mints a position for `who` in the concentrated pool of `lp_id` as `mint_position` does and
+	/// returns its id.
+	fn do_mint_position(
+		who: &T::AccountId,
+		lp_id: &AssetIdOf<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+		liquidity: u128,
+		amount_1_max: AssetBalanceOf<T>,
+		amount_2_max: AssetBalanceOf<T>,
+	) -> Result<u64, DispatchError> {
+		let mut pool = <PoolMap<T>>::get(lp_id).ok_or(Error::<T>::NoPool)?;
+		ensure!(matches!(pool.kind, PoolKind::Concentrated(_)), Error::<T>::UnsupportedPoolKind);
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		let tick_spacing = concentrated_math::tick_spacing(pool.fee_tier);
+		ensure!(
+			tick_lower < tick_upper &&
+				tick_lower >= concentrated_math::MIN_TICK &&
+				tick_upper <= concentrated_math::MAX_TICK &&
+				tick_lower % tick_spacing == 0 &&
+				tick_upper % tick_spacing == 0,
+			Error::<T>::InvalidTickRange
+		);
+		ensure!(liquidity > 0, Error::<T>::InsufficientLiquidityMinted);
+		let liquidity_delta = i128::try_from(liquidity).map_err(|_| ArithmeticError::Overflow)?;
+		Self::update_oracle(lp_id, &mut pool);
+
+		let PoolKind::Concentrated(concentrated) = &mut pool.kind else {
+			return Err(Error::<T>::UnsupportedPoolKind.into())
+		};
+		concentrated.update_tick(tick_lower, liquidity_delta, false)?;
+		concentrated.update_tick(tick_upper, liquidity_delta, true)?;
+		let (fee_growth_inside_1, fee_growth_inside_2) =
+			concentrated.fee_growth_inside(tick_lower, tick_upper);
+		if (tick_lower..tick_upper).contains(&concentrated.tick) {
+			concentrated.liquidity =
+				concentrated.liquidity.checked_add(liquidity).ok_or(ArithmeticError::Overflow)?;
+		}
+		let (amount_1, amount_2) =
+			Self::position_amounts(concentrated, tick_lower, tick_upper, liquidity, true)?;
+		ensure!(amount_1 <= amount_1_max && amount_2 <= amount_2_max, Error::<T>::SlippageTooHigh);
+
+		let pair = &mut pool.pool_pair;
+		pair.amount_1 = pair.amount_1.checked_add(&amount_1).ok_or(ArithmeticError::Overflow)?;
+		pair.amount_2 = pair.amount_2.checked_add(&amount_2).ok_or(ArithmeticError::Overflow)?;
+		let (asset_1, asset_2) = (pair.asset_1.clone(), pair.asset_2.clone());
+		<PoolMap<T>>::insert(lp_id, pool);
+		let pool_account = Self::pool_account(lp_id);
+		Self::transfer_nonzero(&asset_1, who, &pool_account, amount_1)?;
+		Self::transfer_nonzero(&asset_2, who, &pool_account, amount_2)?;
+
+		let position_id = <NextPositionId<T>>::get();
+		<NextPositionId<T>>::put(position_id.checked_add(1).ok_or(ArithmeticError::Overflow)?);
+		<Positions<T>>::insert(
+			who,
+			position_id,
+			Position::<T> {
+				lp_id: lp_id.clone(),
+				tick_lower,
+				tick_upper,
+				liquidity,
+				fee_growth_inside_1,
+				fee_growth_inside_2,
+				fees_owed_1: Zero::zero(),
+				fees_owed_2: Zero::zero(),
+			},
+		);
+
+		Self::deposit_event(Event::PositionMinted {
+			who: who.clone(),
+			lp_id: lp_id.clone(),
+			position_id,
+			tick_lower,
+			tick_upper,
+			liquidity,
+			amount_1,
+			amount_2,
+		});
+		Ok(position_id)
+	}
+
+	/// Burns `liquidity` of the position `position_id` of `who` as `burn_position` does and
+	/// returns the amounts paid out, including the fees if the whole position was burned.
+	fn do_burn_position(
+		who: &T::AccountId,
+		position_id: u64,
+		liquidity: u128,
+		amount_1_min: AssetBalanceOf<T>,
+		amount_2_min: AssetBalanceOf<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let mut position = <Positions<T>>::get(who, position_id).ok_or(Error::<T>::NoPosition)?;
+		ensure!(
+			liquidity > 0 && liquidity <= position.liquidity,
+			Error::<T>::InsufficientPositionLiquidity
+		);
+		let liquidity_delta = i128::try_from(liquidity).map_err(|_| ArithmeticError::Overflow)?;
+		let lp_id = position.lp_id.clone();
+		let mut pool = <PoolMap<T>>::get(&lp_id).ok_or(Error::<T>::NoPool)?;
+		// unlike swaps, withdrawals stay open while swaps are paused
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		Self::update_oracle(&lp_id, &mut pool);
+
+		let PoolKind::Concentrated(concentrated) = &mut pool.kind else {
+			return Err(Error::<T>::UnsupportedPoolKind.into())
+		};
+		// the fees are accrued first, as dropping a tick forgets what was earned outside it
+		Self::accrue_fees(concentrated, &mut position)?;
+		concentrated.update_tick(position.tick_lower, -liquidity_delta, false)?;
+		concentrated.update_tick(position.tick_upper, -liquidity_delta, true)?;
+		if (position.tick_lower..position.tick_upper).contains(&concentrated.tick) {
+			concentrated.liquidity = concentrated
+				.liquidity
+				.checked_sub(liquidity)
+				.ok_or(ArithmeticError::Underflow)?;
+		}
+		let (amount_1, amount_2) = Self::position_amounts(
+			concentrated,
+			position.tick_lower,
+			position.tick_upper,
+			liquidity,
+			false,
+		)?;
+		ensure!(amount_1 >= amount_1_min && amount_2 >= amount_2_min, Error::<T>::SlippageTooHigh);
+
+		position.liquidity -= liquidity;
+		// a position burned entirely is paid its fees and forgotten
+		let (fees_1, fees_2) = if position.liquidity == 0 {
+			<Positions<T>>::remove(who, position_id);
+			(position.fees_owed_1, position.fees_owed_2)
+		} else {
+			<Positions<T>>::insert(who, position_id, position);
+			(Zero::zero(), Zero::zero())
+		};
+		let total_1 = amount_1.checked_add(&fees_1).ok_or(ArithmeticError::Overflow)?;
+		let total_2 = amount_2.checked_add(&fees_2).ok_or(ArithmeticError::Overflow)?;
+		Self::pay_out_of_pool(&lp_id, pool, who, total_1, total_2)?;
+
+		Self::deposit_event(Event::PositionBurned {
+			who: who.clone(),
+			position_id,
+			liquidity,
+			amount_1,
+			amount_2,
+		});
+		if !fees_1.is_zero() || !fees_2.is_zero() {
+			Self::deposit_event(Event::FeesCollected {
+				who: who.clone(),
+				position_id,
+				amount_1: fees_1,
+				amount_2: fees_2,
+			});
+		}
+		Ok((total_1, total_2))
+	}
+
+	/// Pays out the fees of the position `position_id` of `who` as `collect_fees` does and
+	/// returns them.
+	fn do_collect_fees(
+		who: &T::AccountId,
+		position_id: u64,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let mut position = <Positions<T>>::get(who, position_id).ok_or(Error::<T>::NoPosition)?;
+		let lp_id = position.lp_id.clone();
+		let pool = <PoolMap<T>>::get(&lp_id).ok_or(Error::<T>::NoPool)?;
+		ensure!(pool.status != PoolStatus::Frozen, Error::<T>::PoolFrozen);
+		let PoolKind::Concentrated(concentrated) = &pool.kind else {
+			return Err(Error::<T>::UnsupportedPoolKind.into())
+		};
+
+		Self::accrue_fees(concentrated, &mut position)?;
+		let amount_1 = core::mem::take(&mut position.fees_owed_1);
+		let amount_2 = core::mem::take(&mut position.fees_owed_2);
+		<Positions<T>>::insert(who, position_id, position);
+		Self::pay_out_of_pool(&lp_id, pool, who, amount_1, amount_2)?;
+
+		Self::deposit_event(Event::FeesCollected {
+			who: who.clone(),
+			position_id,
+			amount_1,
+			amount_2,
+		});
+		Ok((amount_1, amount_2))
+	}
+
+	// adds the fees a position earned since it was last updated to what it is owed
+	fn accrue_fees(
+		concentrated: &ConcentratedPool<T>,
+		position: &mut Position<T>,
+	) -> DispatchResult {
+		let (inside_1, inside_2) =
+			concentrated.fee_growth_inside(position.tick_lower, position.tick_upper);
+		let earned = |inside: U256, last: U256| {
+			concentrated_math::fees_earned(inside.overflowing_sub(last).0, position.liquidity)
+				.ok_or(ArithmeticError::Overflow)
+				.and_then(|fees| Self::from_u256(fees).map_err(|_| ArithmeticError::Overflow))
+		};
+		let earned_1 = earned(inside_1, position.fee_growth_inside_1)?;
+		let earned_2 = earned(inside_2, position.fee_growth_inside_2)?;
+		position.fees_owed_1 =
+			position.fees_owed_1.checked_add(&earned_1).ok_or(ArithmeticError::Overflow)?;
+		position.fees_owed_2 =
+			position.fees_owed_2.checked_add(&earned_2).ok_or(ArithmeticError::Overflow)?;
+		position.fee_growth_inside_1 = inside_1;
+		position.fee_growth_inside_2 = inside_2;
+		Ok(())
+	}
+
+	// what `liquidity` between `tick_lower` and `tick_upper` holds of a concentrated pool's first
+	// and second asset at its current price, rounded up to be paid in or down to be paid out
+	fn position_amounts(
+		concentrated: &ConcentratedPool<T>,
+		tick_lower: i32,
+		tick_upper: i32,
+		liquidity: u128,
+		round_up: bool,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let sqrt_price_at = |tick: i32| {
+			concentrated_math::sqrt_price_at_tick(tick).ok_or(Error::<T>::InvalidTickRange)
+		};
+		let (amount_1, amount_2) = concentrated_math::amounts_for_liquidity(
+			concentrated.sqrt_price,
+			sqrt_price_at(tick_lower)?,
+			sqrt_price_at(tick_upper)?,
+			liquidity,
+			round_up,
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		Ok((Self::from_u256(amount_1)?, Self::from_u256(amount_2)?))
+	}
+
+	// takes `amount_1` and `amount_2` out of the reserves of a two asset pool, stores it and pays
+	// them to `who`
+	fn pay_out_of_pool(
+		lp_id: &AssetIdOf<T>,
+		mut pool: Pool<T>,
+		who: &T::AccountId,
+		amount_1: AssetBalanceOf<T>,
+		amount_2: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		let pair = &mut pool.pool_pair;
+		pair.amount_1 = pair.amount_1.checked_sub(&amount_1).ok_or(ArithmeticError::Underflow)?;
+		pair.amount_2 = pair.amount_2.checked_sub(&amount_2).ok_or(ArithmeticError::Underflow)?;
+		let (asset_1, asset_2) = (pair.asset_1.clone(), pair.asset_2.clone());
+		<PoolMap<T>>::insert(lp_id, pool);
+		let pool_account = Self::pool_account(lp_id);
+		Self::transfer_nonzero(&asset_1, &pool_account, who, amount_1)?;
+		Self::transfer_nonzero(&asset_2, &pool_account, who, amount_2)
+	}
+
+	/// Swaps for `who` as `swap_exact_in_for_out` does and returns the amount of `asset_out`
+	/// received.
+	fn do_swap_exact_in_for_out(
+		who: &T::AccountId,
+		asset_in: AssetKindOf<T>,
+		asset_out: AssetKindOf<T>,
+		fee_tier: u16,
+		exact_in: AssetBalanceOf<T>,
+		min_out: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let cur_lp_id = Self::get_lp_id(&asset_in, &asset_out, fee_tier)?;
+		let mut pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoneValue)?;
+		Self::update_oracle(&cur_lp_id, &mut pool);
+
+		let amount_out = Self::calculate_out(&exact_in, &asset_in, &asset_out, &pool)?;
+		if amount_out.0 < min_out {
+			return Err(Error::<T>::SlippageTooHigh.into())
 		}
+		Self::put_swapped_pool(&cur_lp_id, &pool, amount_out.1)?;
+
+		let pool_account = Self::pool_account(&cur_lp_id);
+		NativeOrAssetUnion::<T>::transfer(&asset_in, who, &pool_account, exact_in, Expendable)?;
+		NativeOrAssetUnion::<T>::transfer(&asset_out, &pool_account, who, amount_out.0, Protect)?;
+
+		Self::deposit_event(Event::SwapEvent {
+			asset_in,
+			asset_out,
+			fee_tier,
+			amount_in: exact_in,
+			amount_out: amount_out.0,
+		});
+		Ok(amount_out.0)
 	}
 
-	/// The account ID of the dex pallet. This account stores all of the assets in the dex.
+	/// The account ID of the dex pallet. It owns the LP tokens and holds their metadata deposits,
+	/// and flash loans are repaid to it. Pool reserves are held in `pool_account`s instead.
 	pub fn account_id() -> T::AccountId {
 		T::PalletId::get().into_account_truncating()
 	}
 
-	/// Generates a liquidity pool ID from the given asset IDs, ensuring the assets are distinct.
-	/// The pool ID is based on a hash of the sorted asset IDs.
+	/// The sovereign account of the pool with LP token `lp_id`, which holds that pool's
+	/// reserves and nothing else. Its balances of the pair can be audited against the pool.
+	pub fn pool_account(lp_id: &AssetIdOf<T>) -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating(lp_id)
+	}
+
+	/// The account the `MinimumLiquidity` of every pool is minted to. Nothing ever moves funds
+	/// out of it, so those LP tokens are locked until `destroy_pool` burns them.
+	pub fn locked_liquidity_account() -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating(b"minimum-liquidity")
+	}
+
+	/// The account farming rewards are paid out of. Governance funds it with the reward assets.
+	pub fn rewards_account() -> T::AccountId {
+		T::PalletId::get().into_sub_account_truncating(b"rewards")
+	}
+
+	/// What the account of the pool of `lp_id` holds of each of the pool's assets, in the pool's
+	/// order. These match the pool's reserves unless something was sent to the account directly.
+	pub fn pool_balances(lp_id: &AssetIdOf<T>, pool: &Pool<T>) -> Vec<AssetBalanceOf<T>> {
+		let pool_account = Self::pool_account(lp_id);
+		pool.reserves()
+			.iter()
+			.map(|(asset, _)| NativeOrAssetUnion::<T>::balance(asset, &pool_account))
+			.collect()
+	}
+
+	/// Looks up the LP token id of the pool of the given assets, in either order, and fee tier,
+	/// failing with `NoPool` if there is no such pool. A pair has a different pool for every tier.
 	pub fn get_lp_id(
-		asset_a: &AssetIdOf<T>,
-		asset_b: &AssetIdOf<T>,
+		asset_a: &AssetKindOf<T>,
+		asset_b: &AssetKindOf<T>,
+		fee_tier: u16,
 	) -> Result<AssetIdOf<T>, DispatchError> {
 		ensure!(asset_a != asset_b, Error::<T>::SameAsset);
-		let bytes = if asset_a.encode() > asset_b.encode() {
-			T::Hashing::hash(&(asset_b, asset_a).encode());
+		<PairToLp<T>>::get(Self::pair_key(asset_a, asset_b, fee_tier))
+			.ok_or_else(|| Error::<T>::NoPool.into())
+	}
+
+	/// The asset id the LP token of the next pool created will get.
+	pub fn next_lp_id() -> AssetIdOf<T> {
+		T::LpAssetId::convert(<NextLpIndex<T>>::get())
+	}
+
+	// the key of a pool in `PairToLp`, with its assets in `PoolPair` order
+	fn pair_key(
+		asset_a: &AssetKindOf<T>,
+		asset_b: &AssetKindOf<T>,
+		fee_tier: u16,
+	) -> (AssetKindOf<T>, AssetKindOf<T>, u16) {
+		if asset_a.encode() > asset_b.encode() {
+			(asset_b.clone(), asset_a.clone(), fee_tier)
+		} else {
+			(asset_a.clone(), asset_b.clone(), fee_tier)
+		}
+	}
+
+	// the keys of a pool of `assets` in `PairToLp`, one for every pair of them
+	fn pair_keys(
+		assets: &[AssetKindOf<T>],
+		fee_tier: u16,
+	) -> Vec<(AssetKindOf<T>, AssetKindOf<T>, u16)> {
+		let mut pair_keys = Vec::new();
+		for (index, asset_a) in assets.iter().enumerate() {
+			for asset_b in &assets[index + 1..] {
+				pair_keys.push(Self::pair_key(asset_a, asset_b, fee_tier));
+			}
+		}
+		pair_keys
+	}
+
+	// hands out the asset id of a new LP token. An id that already belongs to an asset, which
+	// only happens if the runtime doesn't reserve them or a pool kept its id from before they
+	// were allocated, is skipped
+	fn allocate_lp_id() -> Result<AssetIdOf<T>, DispatchError> {
+		let mut index = <NextLpIndex<T>>::get();
+		let mut lp_id = T::LpAssetId::convert(index);
+		while T::Fungibles::asset_exists(lp_id.clone()) {
+			index = index.checked_add(1).ok_or(ArithmeticError::Overflow)?;
+			lp_id = T::LpAssetId::convert(index);
+		}
+		<NextLpIndex<T>>::put(index.checked_add(1).ok_or(ArithmeticError::Overflow)?);
+		Ok(lp_id)
+	}
+
+	// the protocol fee recipient and share, if the fee is on
+	fn protocol_fee() -> Option<(T::AccountId, Perbill)> {
+		let share = <ProtocolFeeShare<T>>::get();
+		if share.is_zero() {
+			return None
+		}
+		<ProtocolFeeRecipient<T>>::get().map(|recipient| (recipient, share))
+	}
+
+	// records `k` for the next protocol fee calculation, or clears it while the fee is off
+	fn update_k_last(pool: &mut Pool<T>) -> Result<(), DispatchError> {
+		pool.k_last = if Self::protocol_fee().is_some() {
+			pool.pool_pair
+				.amount_1
+				.checked_mul(&pool.pool_pair.amount_2)
+				.ok_or(ArithmeticError::Overflow)?
 		} else {
-			T::Hashing::hash(&(asset_a, asset_b).encode());
+			Default::default()
 		};
-		let generated_lp_id = AssetIdOf::<T>::decode(&mut TrailingZeroInput::new(&bytes.encode()))
-			.expect("in our PBA exam, we assume all bytes can be ID");
-		Ok(generated_lp_id)
+		Ok(())
+	}
+
+	/// Mints the protocol's share of the growth of `sqrt(k)` since `k_last` to the protocol fee
+	/// recipient. This must be called before liquidity is added or removed.
+	///
+	/// Minting `s` LP tokens on a supply of `S` gives the recipient a `s / (S + s)` share of the
+	/// pool, so for it to own `share` of the growth
+	/// `s = S * share * (sqrt(k) - sqrt(k_last)) / ((1 - share) * sqrt(k) + share * sqrt(k_last))`.
+	/// Growth the recipient can't hold, for being below the LP token's minimum balance, stays
+	/// with the LPs.
+	pub fn mint_protocol_fee(lp_id: &AssetIdOf<T>) -> Result<(), DispatchError> {
+		let Some((recipient, share)) = Self::protocol_fee() else { return Ok(()) };
+		let Some(mut pool) = <PoolMap<T>>::get(lp_id) else { return Ok(()) };
+		if pool.k_last.is_zero() {
+			return Ok(())
+		}
+
+		let k = pool
+			.pool_pair
+			.amount_1
+			.checked_mul(&pool.pool_pair.amount_2)
+			.ok_or(ArithmeticError::Overflow)?;
+		let root_k = IntegerSquareRoot::integer_sqrt(&k);
+		let root_k_last = IntegerSquareRoot::integer_sqrt(&pool.k_last);
+		if root_k <= root_k_last {
+			return Ok(())
+		}
+
+		let growth = root_k.checked_sub(&root_k_last).ok_or(ArithmeticError::Underflow)?;
+		let numerator =
+			share.mul_floor(pool.lp_supply.checked_mul(&growth).ok_or(ArithmeticError::Overflow)?);
+		let denominator = share
+			.left_from_one()
+			.mul_floor(root_k)
+			.checked_add(&share.mul_floor(root_k_last))
+			.ok_or(ArithmeticError::Overflow)?;
+		let liquidity = numerator.checked_div(&denominator).unwrap_or_default();
+		if liquidity.is_zero() ||
+			T::Fungibles::can_deposit(lp_id.clone(), &recipient, liquidity, Provenance::Minted) !=
+				DepositConsequence::Success
+		{
+			return Ok(())
+		}
+
+		T::Fungibles::mint_into(lp_id.clone(), &recipient, liquidity)?;
+		pool.lp_supply = pool.lp_supply.checked_add(&liquidity).ok_or(ArithmeticError::Overflow)?;
+		<PoolMap<T>>::insert(lp_id, pool);
+
+		Self::deposit_event(Event::ProtocolFeeMinted {
+			lp_id: lp_id.clone(),
+			recipient,
+			amount: liquidity,
+		});
+		Ok(())
+	}
+
+	/// Works out how much of `asset_a` and `asset_b` to deposit into a pool, as
+	/// `UniswapV2Router02` does. A new pool takes the desired amounts as they are. An existing
+	/// pool takes the most it can at its current ratio without going over either desired amount,
+	/// and fails if that is below either minimum.
+	pub fn liquidity_amounts(
+		asset_a: &AssetKindOf<T>,
+		asset_b: &AssetKindOf<T>,
+		pool: Option<&Pool<T>>,
+		amount_a_desired: AssetBalanceOf<T>,
+		amount_b_desired: AssetBalanceOf<T>,
+		amount_a_min: AssetBalanceOf<T>,
+		amount_b_min: AssetBalanceOf<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>), DispatchError> {
+		let Some(pool) = pool else { return Ok((amount_a_desired, amount_b_desired)) };
+		let (reserve_a, reserve_b) = Self::reserves_of(asset_a, asset_b, pool);
+
+		let amount_b_optimal = Self::quote_amount(amount_a_desired, reserve_a, reserve_b)?;
+		if amount_b_optimal <= amount_b_desired {
+			ensure!(amount_b_optimal >= amount_b_min, Error::<T>::InsufficientAmountB);
+			return Ok((amount_a_desired, amount_b_optimal))
+		}
+		let amount_a_optimal = Self::quote_amount(amount_b_desired, reserve_b, reserve_a)?;
+		ensure!(amount_a_optimal >= amount_a_min, Error::<T>::InsufficientAmountA);
+		Ok((amount_a_optimal, amount_b_desired))
+	}
+
+	// the amount of one asset worth `amount` of the other at the ratio of the reserves
+	fn quote_amount(
+		amount: AssetBalanceOf<T>,
+		reserve_in: AssetBalanceOf<T>,
+		reserve_out: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let product = amount.checked_mul(&reserve_out).ok_or(ArithmeticError::Overflow)?;
+		product.checked_div(&reserve_in).ok_or(ArithmeticError::DivisionByZero.into())
 	}
 
 	// adds liquidity to an existing pool
@@ -607,73 +3792,514 @@ impl<T: Config> Pallet<T> {
 		pool_id: &AssetIdOf<T>,
 	) -> Result<(), DispatchError> {
 		let mut pool = <PoolMap<T>>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		Self::update_oracle(pool_id, &mut pool);
+		pool.pool_pair.amount_1 = pool
+			.pool_pair
+			.amount_1
+			.checked_add(&new_pair.amount_1)
+			.ok_or(ArithmeticError::Overflow)?;
+		pool.pool_pair.amount_2 = pool
+			.pool_pair
+			.amount_2
+			.checked_add(&new_pair.amount_2)
+			.ok_or(ArithmeticError::Overflow)?;
+		pool.lp_supply = pool.lp_supply.checked_add(&new_lp).ok_or(ArithmeticError::Overflow)?;
+		Self::update_k_last(&mut pool)?;
+		<PoolMap<T>>::insert(pool_id, pool);
+		Ok(())
+	}
+
+	// removes liquidity from an existing pool
+	pub fn decrease_pool(
+		amount_1: &AssetBalanceOf<T>,
+		amount_2: &AssetBalanceOf<T>,
+		new_lp: &AssetBalanceOf<T>,
+		pool_id: &AssetIdOf<T>,
+	) -> Result<(), DispatchError> {
+		let mut pool = <PoolMap<T>>::get(pool_id).ok_or(Error::<T>::NoPool)?;
+		Self::update_oracle(pool_id, &mut pool);
 		pool.pool_pair.amount_1 = pool
 			.pool_pair
 			.amount_1
-			.checked_add(&new_pair.amount_1)
+			.checked_sub(&amount_1)
+			.ok_or(ArithmeticError::Underflow)?;
+		pool.pool_pair.amount_2 = pool
+			.pool_pair
+			.amount_2
+			.checked_sub(&amount_2)
+			.ok_or(ArithmeticError::Underflow)?;
+		pool.lp_supply = pool.lp_supply.checked_sub(&new_lp).ok_or(ArithmeticError::Underflow)?;
+		Self::update_k_last(&mut pool)?;
+
+		// the locked minimum liquidity keeps the supply above zero, so the pool is always kept
+		<PoolMap<T>>::insert(pool_id, pool);
+		Ok(())
+	}
+
+	/// The amount of `amount_in` that `zap_in` swaps before depositing, such that the swap
+	/// leaves the rest of `amount_in` and the swap output at the pool's new ratio.
+	///
+	/// With `R` the pool's reserve of the input asset, `a` the amount in and `f` the fee, the
+	/// closed-form solution is `s = (sqrt(R^2 * (2 - f)^2 + 4 * (1 - f) * a * R) - R * (2 - f)) /
+	/// (2 * (1 - f))`. It is worked out in basis points, with `r = 10_000 - fee_tier`.
+	pub fn zap_swap_amount(
+		amount_in: AssetBalanceOf<T>,
+		reserve_in: AssetBalanceOf<T>,
+		fee_tier: u16,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let basis = U256::from(10_000u32);
+		let r = basis.checked_sub(U256::from(fee_tier)).ok_or(ArithmeticError::Underflow)?;
+		let reserve = U256::from(reserve_in.saturated_into::<u128>());
+		let amount = U256::from(amount_in.saturated_into::<u128>());
+
+		// `R * (2 - f)` and `4 * (1 - f) * a * R`, both scaled by 10_000
+		let b = reserve.checked_mul(basis + r).ok_or(ArithmeticError::Overflow)?;
+		let c = (U256::from(4u32) * r * basis)
+			.checked_mul(amount)
+			.and_then(|c| c.checked_mul(reserve))
+			.ok_or(ArithmeticError::Overflow)?;
+		let discriminant = b
+			.checked_mul(b)
+			.and_then(|b_squared| b_squared.checked_add(c))
+			.ok_or(ArithmeticError::Overflow)?;
+
+		let swap_amount = (discriminant.integer_sqrt() - b)
+			.checked_div(U256::from(2u32) * r)
+			.ok_or(ArithmeticError::DivisionByZero)?;
+		Ok(swap_amount.low_u128().saturated_into())
+	}
+
+	/// Lends `amount` of `asset` from the pools holding it to `borrower` and runs `borrow` with
+	/// the fee owed, which must pay the loan and the fee back to the DEX's account. The loan is
+	/// then returned to the pools it came from and the fee is credited to the pools holding
+	/// `asset`. Returns the weight `borrow` reports it used. The DEX is locked while `borrow`
+	/// runs, so it can't be traded with or changed.
+	pub fn do_flash_loan(
+		borrower: &T::AccountId,
+		asset: &AssetKindOf<T>,
+		amount: AssetBalanceOf<T>,
+		borrow: impl FnOnce(AssetBalanceOf<T>) -> Result<Weight, DispatchError>,
+	) -> Result<Weight, DispatchError> {
+		Self::ensure_unlocked()?;
+		let pools = Self::flash_loan_pools(asset);
+		let lenders = Self::flash_loan_lenders(&pools, amount)?;
+		let fee = Self::calculate_fees(&amount, T::DefaultFeeTier::get())?;
+		for (lp_id, lent) in &lenders {
+			NativeOrAssetUnion::<T>::transfer(
+				asset,
+				&Self::pool_account(lp_id),
+				borrower,
+				*lent,
+				Expendable,
+			)?;
+		}
+		let dex_account = Self::account_id();
+		let balance_before = NativeOrAssetUnion::<T>::balance(asset, &dex_account);
+
+		// released before checking the result, so the lock doesn't outlive a failed loan when
+		// this isn't called from a transactional extrinsic
+		<FlashLoanLock<T>>::put(true);
+		let borrowed = borrow(fee);
+		<FlashLoanLock<T>>::kill();
+		let weight = borrowed?;
+		let required = balance_before
+			.checked_add(&amount)
+			.and_then(|required| required.checked_add(&fee))
+			.ok_or(ArithmeticError::Overflow)?;
+		ensure!(
+			NativeOrAssetUnion::<T>::balance(asset, &dex_account) >= required,
+			Error::<T>::InsufficientRepayment
+		);
+		for (lp_id, lent) in lenders {
+			NativeOrAssetUnion::<T>::transfer(
+				asset,
+				&dex_account,
+				&Self::pool_account(&lp_id),
+				lent,
+				Expendable,
+			)?;
+		}
+		Self::distribute_flash_loan_fee(asset, pools, fee)?;
+
+		Self::deposit_event(Event::FlashLoanEvent { asset_id: asset.clone(), amount, fee });
+		Ok(weight)
+	}
+
+	/// Sends `amounts_out` of `asset_a` and the other asset from the pool with `fee_tier` to
+	/// `borrower` and runs `borrow`, then takes `amounts_in` of them from `borrower` and settles
+	/// the pool with `settle_flash_swap`. Returns the weight `borrow` reports it used. The DEX is
+	/// locked while `borrow` runs, so it can't be traded with or changed.
+	pub fn do_flash_swap(
+		borrower: &T::AccountId,
+		asset_a: &AssetKindOf<T>,
+		asset_b: &AssetKindOf<T>,
+		fee_tier: u16,
+		amounts_out: (AssetBalanceOf<T>, AssetBalanceOf<T>),
+		amounts_in: (AssetBalanceOf<T>, AssetBalanceOf<T>),
+		borrow: impl FnOnce() -> Result<Weight, DispatchError>,
+	) -> Result<Weight, DispatchError> {
+		Self::ensure_unlocked()?;
+		let cur_lp_id = Self::get_lp_id(asset_a, asset_b, fee_tier)?;
+		let mut pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+		Self::ensure_swappable(&pool)?;
+		// the repayment is only checked against the constant product
+		ensure!(matches!(pool.kind, PoolKind::ConstantProduct), Error::<T>::UnsupportedPoolKind);
+		let (amount_a_out, amount_b_out) = amounts_out;
+		let (amount_a_in, amount_b_in) = amounts_in;
+		let (reserve_a, reserve_b) = Self::reserves_of(asset_a, asset_b, &pool);
+		ensure!(
+			!amount_a_out.is_zero() || !amount_b_out.is_zero(),
+			Error::<T>::InsufficientOutputAmount
+		);
+		ensure!(
+			amount_a_out < reserve_a && amount_b_out < reserve_b,
+			Error::<T>::InsufficientLiquidity
+		);
+
+		// the output is sent optimistically, before anything is paid in
+		let pool_account = Self::pool_account(&cur_lp_id);
+		Self::transfer_nonzero(asset_a, &pool_account, borrower, amount_a_out)?;
+		Self::transfer_nonzero(asset_b, &pool_account, borrower, amount_b_out)?;
+
+		// the swap is settled against the reserves read above, so no pool may change until it
+		// is. Released before checking the result, as in `do_flash_loan`
+		<FlashLoanLock<T>>::put(true);
+		let borrowed = borrow();
+		<FlashLoanLock<T>>::kill();
+		let weight = borrowed?;
+
+		Self::transfer_nonzero(asset_a, borrower, &pool_account, amount_a_in)?;
+		Self::transfer_nonzero(asset_b, borrower, &pool_account, amount_b_in)?;
+		Self::update_oracle(&cur_lp_id, &mut pool);
+		let settled_pool = Self::settle_flash_swap(pool.clone(), asset_a, amounts_out, amounts_in)?;
+		Self::put_swapped_pool(&cur_lp_id, &pool, settled_pool)?;
+
+		Self::deposit_event(Event::FlashSwapped {
+			who: borrower.clone(),
+			asset_a: asset_a.clone(),
+			asset_b: asset_b.clone(),
+			fee_tier,
+			amount_a_out,
+			amount_b_out,
+			amount_a_in,
+			amount_b_in,
+		});
+		Ok(weight)
+	}
+
+	// the pools a flash loan of `asset` can be lent from, and whose LPs share its fee: the
+	// active pools of any kind holding some of `asset`, with their reserve of it. This goes
+	// through every pool, which the weight of `flash_loan` is charged for by `PoolCount`
+	fn flash_loan_pools(asset: &AssetKindOf<T>) -> Vec<(AssetIdOf<T>, AssetBalanceOf<T>)> {
+		<PoolMap<T>>::iter()
+			.filter(|(_, pool)| Self::ensure_swappable(pool).is_ok())
+			.filter_map(|(lp_id, pool)| {
+				let reserve = pool.reserve(asset)?;
+				(!reserve.is_zero()).then_some((lp_id, reserve))
+			})
+			.collect()
+	}
+
+	// picks the `pools` a flash loan of `amount` is lent from, taking as much as each has in
+	// turn, and returns how much is lent from each
+	fn flash_loan_lenders(
+		pools: &[(AssetIdOf<T>, AssetBalanceOf<T>)],
+		amount: AssetBalanceOf<T>,
+	) -> Result<Vec<(AssetIdOf<T>, AssetBalanceOf<T>)>, DispatchError> {
+		let mut lenders = Vec::new();
+		let mut remaining = amount;
+		for (lp_id, reserve) in pools {
+			if remaining.is_zero() {
+				break
+			}
+			let lent = (*reserve).min(remaining);
+			remaining -= lent;
+			lenders.push((lp_id.clone(), lent));
+		}
+		ensure!(remaining.is_zero(), Error::<T>::InsufficientLiquidity);
+		Ok(lenders)
+	}
+
+	// fails unless the pool is active
+	fn ensure_swappable(pool: &Pool<T>) -> DispatchResult {
+		match pool.status {
+			PoolStatus::Active => Ok(()),
+			PoolStatus::SwapsPaused => Err(Error::<T>::SwapsPaused.into()),
+			PoolStatus::Frozen => Err(Error::<T>::PoolFrozen.into()),
+		}
+	}
+
+	/// Stores the state of a pool after a swap, failing if the pool isn't active or the swap
+	/// moves its price further than `MaxPriceImpact`. If the swap moves the price further than
+	/// `MaxPriceMove` within the current price window, it goes through but pauses swaps with
+	/// the pool after it.
+	pub fn put_swapped_pool(
+		lp_id: &AssetIdOf<T>,
+		pool: &Pool<T>,
+		mut swapped_pool: Pool<T>,
+	) -> DispatchResult {
+		Self::ensure_swappable(pool)?;
+		if Self::check_circuit_breaker(lp_id, pool, &swapped_pool)? {
+			swapped_pool.status = PoolStatus::SwapsPaused;
+			Self::deposit_event(Event::CircuitBreakerTripped { lp_id: lp_id.clone() });
+		}
+		<PoolMap<T>>::insert(lp_id, swapped_pool);
+		Ok(())
+	}
+
+	// fails if the swap from `pool` to `swapped_pool` moves the price further than
+	// `MaxPriceImpact`, and returns whether it moves it further than `MaxPriceMove` from where
+	// it was before the first swap of the current price window. A swap failing later on rolls
+	// back the window along with the pause, so only moves that go through can trip the breaker
+	fn check_circuit_breaker(
+		lp_id: &AssetIdOf<T>,
+		pool: &Pool<T>,
+		swapped_pool: &Pool<T>,
+	) -> Result<bool, DispatchError> {
+		let (Some((price, _)), Some((swapped_price, _))) =
+			(Self::spot_prices(pool), Self::spot_prices(swapped_pool))
+		else {
+			return Ok(false)
+		};
+		let max_impact = T::MaxPriceImpact::get();
+		ensure!(
+			max_impact.is_zero() || Self::price_move(price, swapped_price) <= max_impact.into(),
+			Error::<T>::CircuitBreakerTripped
+		);
+
+		let now = frame_system::Pallet::<T>::block_number();
+		let (window_start, reference_price) = match <PriceWindows<T>>::get(lp_id) {
+			Some((start, reference_price))
+				if now < start.saturating_add(T::PriceMoveWindow::get()) =>
+				(start, reference_price),
+			_ => (now, price),
+		};
+		let max_move = T::MaxPriceMove::get();
+		let moved_too_far = !max_move.is_zero() &&
+			Self::price_move(reference_price, swapped_price) > max_move.into();
+		if moved_too_far {
+			// once swaps are resumed, the next window starts from the price they resume at
+			<PriceWindows<T>>::remove(lp_id);
+		} else {
+			<PriceWindows<T>>::insert(lp_id, (window_start, reference_price));
+		}
+		Ok(moved_too_far)
+	}
+
+	// how far a price moved, as the larger of the two prices over the smaller less one, so a
+	// move counts the same in either direction
+	fn price_move(from: FixedU128, to: FixedU128) -> FixedU128 {
+		let (high, low) = if to > from { (to, from) } else { (from, to) };
+		high.checked_div(&low)
+			.map(|ratio| ratio.saturating_sub(FixedU128::one()))
+			.unwrap_or_else(|| FixedU128::from_inner(u128::MAX))
+	}
+
+	// fails while a flash loan or a flash swap is out, so its borrower can't re-enter the DEX
+	fn ensure_unlocked() -> DispatchResult {
+		ensure!(!<FlashLoanLock<T>>::get(), Error::<T>::FlashLoanInProgress);
+		Ok(())
+	}
+
+	// moves `fee` of `asset` from the DEX's account to `pools`, every active pool holding
+	// `asset` whether the loan was lent from it or not, and credits it to their reserves, in
+	// proportion to their reserves of it. The rounding dust goes to the last pool. In a
+	// concentrated pool, the share is earned by the liquidity in range as a swap fee is
+	fn distribute_flash_loan_fee(
+		asset: &AssetKindOf<T>,
+		pools: Vec<(AssetIdOf<T>, AssetBalanceOf<T>)>,
+		fee: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		let total = pools
+			.iter()
+			.fold(U256::zero(), |total, (_, reserve)| total + Self::to_u256(*reserve));
+		if total.is_zero() {
+			return Ok(())
+		}
+
+		let last = pools.len() - 1;
+		let mut remaining = fee;
+		for (index, (lp_id, reserve)) in pools.into_iter().enumerate() {
+			let share = if index == last {
+				remaining
+			} else {
+				let share = Self::to_u256(fee) * Self::to_u256(reserve) / total;
+				share.low_u128().saturated_into()
+			};
+			remaining = remaining.checked_sub(&share).ok_or(ArithmeticError::Underflow)?;
+			if share.is_zero() {
+				continue
+			}
+			NativeOrAssetUnion::<T>::transfer(
+				asset,
+				&Self::account_id(),
+				&Self::pool_account(&lp_id),
+				share,
+				Expendable,
+			)?;
+
+			let mut pool = <PoolMap<T>>::get(&lp_id).ok_or(Error::<T>::NoPool)?;
+			Self::update_oracle(&lp_id, &mut pool);
+			let reserve = pool.reserve(asset).unwrap_or_default();
+			pool.set_reserve(asset, reserve.checked_add(&share).ok_or(ArithmeticError::Overflow)?)?;
+			let is_asset_1 = pool.pool_pair.asset_1 == *asset;
+			if let PoolKind::Concentrated(concentrated) = &mut pool.kind {
+				if concentrated.liquidity > 0 {
+					let growth =
+						concentrated_math::fee_growth(Self::to_u256(share), concentrated.liquidity)
+							.ok_or(ArithmeticError::Overflow)?;
+					let global = if is_asset_1 {
+						&mut concentrated.fee_growth_global_1
+					} else {
+						&mut concentrated.fee_growth_global_2
+					};
+					*global = global.overflowing_add(growth).0;
+				}
+			}
+			<PoolMap<T>>::insert(&lp_id, pool);
+		}
+		Ok(())
+	}
+
+	/// Applies a flash swap to `pool`, given the amounts of `asset_a` and the other asset taken
+	/// out and paid in, in that order. Like Uniswap V2, the new reserves less the fee on what was
+	/// paid in of each asset must multiply to at least the product the pool had before.
+	pub fn settle_flash_swap(
+		mut pool: Pool<T>,
+		asset_a: &AssetKindOf<T>,
+		amounts_out: (AssetBalanceOf<T>, AssetBalanceOf<T>),
+		amounts_in: (AssetBalanceOf<T>, AssetBalanceOf<T>),
+	) -> Result<Pool<T>, DispatchError> {
+		// in the pool's sorted order
+		let ((out_1, out_2), (in_1, in_2)) = if *asset_a == pool.pool_pair.asset_1 {
+			(amounts_out, amounts_in)
+		} else {
+			((amounts_out.1, amounts_out.0), (amounts_in.1, amounts_in.0))
+		};
+		let to_u256 = |amount: AssetBalanceOf<T>| U256::from(amount.saturated_into::<u128>());
+		let k = to_u256(pool.pool_pair.amount_1) * to_u256(pool.pool_pair.amount_2);
+
+		let new_1 = pool
+			.pool_pair
+			.amount_1
+			.checked_sub(&out_1)
+			.and_then(|reserve| reserve.checked_add(&in_1))
 			.ok_or(ArithmeticError::Overflow)?;
-		pool.pool_pair.amount_2 = pool
+		let new_2 = pool
 			.pool_pair
 			.amount_2
-			.checked_add(&new_pair.amount_2)
+			.checked_sub(&out_2)
+			.and_then(|reserve| reserve.checked_add(&in_2))
 			.ok_or(ArithmeticError::Overflow)?;
-		pool.lp_supply = pool.lp_supply.checked_add(&new_lp).ok_or(ArithmeticError::Overflow)?;
-		<PoolMap<T>>::insert(pool_id, pool);
-		Ok(())
-	}
 
-	// removes liquidity from an existing pool
-	pub fn decrease_pool(
-		amount_1: &AssetBalanceOf<T>,
-		amount_2: &AssetBalanceOf<T>,
-		new_lp: &AssetBalanceOf<T>,
-		pool_id: &AssetIdOf<T>,
-	) -> Result<(), DispatchError> {
-		let mut pool = <PoolMap<T>>::get(pool_id).ok_or(Error::<T>::NoPool)?;
-		pool.pool_pair.amount_1 = pool
-			.pool_pair
-			.amount_1
-			.checked_sub(&amount_1)
+		// the fee stays in the pool but doesn't count towards the product
+		let adjusted_1 = new_1
+			.checked_sub(&Self::calculate_fees(&in_1, pool.fee_tier)?)
 			.ok_or(ArithmeticError::Underflow)?;
-		pool.pool_pair.amount_2 = pool
-			.pool_pair
-			.amount_2
-			.checked_sub(&amount_2)
+		let adjusted_2 = new_2
+			.checked_sub(&Self::calculate_fees(&in_2, pool.fee_tier)?)
 			.ok_or(ArithmeticError::Underflow)?;
-		pool.lp_supply = pool.lp_supply.checked_sub(&new_lp).ok_or(ArithmeticError::Underflow)?;
+		ensure!(to_u256(adjusted_1) * to_u256(adjusted_2) >= k, Error::<T>::InsufficientRepayment);
 
-		if pool.lp_supply == Default::default() {
-			<PoolMap<T>>::remove(pool_id);
-		} else {
-			<PoolMap<T>>::insert(pool_id, pool);
+		pool.pool_pair.amount_1 = new_1;
+		pool.pool_pair.amount_2 = new_2;
+		Ok(pool)
+	}
+
+	// transfers `amount` of `asset` unless it is zero, for the optional legs of a flash swap, a
+	// skim or a position
+	fn transfer_nonzero(
+		asset: &AssetKindOf<T>,
+		source: &T::AccountId,
+		dest: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		if !amount.is_zero() {
+			NativeOrAssetUnion::<T>::transfer(asset, source, dest, amount, Expendable)?;
 		}
 		Ok(())
 	}
 
-	// calculates the amount of fees to be collected
-	// currently we have a hard coded 3% fee
+	// fails if a pool has no liquidity to swap with. A concentrated pool has no LP tokens to tell,
+	// a swap along it fails by itself if it runs out of liquidity
+	fn ensure_funded(pool: &Pool<T>) -> DispatchResult {
+		ensure!(
+			matches!(pool.kind, PoolKind::Concentrated(_)) || !pool.lp_supply.is_zero(),
+			Error::<T>::InsufficientLiquidity
+		);
+		Ok(())
+	}
+
+	// calculates the amount of fees to be collected for a fee tier in basis points
 	pub fn calculate_fees(
 		amount_in: &AssetBalanceOf<T>,
+		fee_tier: u16,
 	) -> Result<AssetBalanceOf<T>, DispatchError> {
-		let percent = Percent::from_rational(<Fee<T>>::get(), 100u16);
-		Ok(percent.mul_ceil(*amount_in))
+		let rate = Permill::from_rational(u32::from(fee_tier), 10_000u32);
+		Ok(rate.mul_ceil(*amount_in))
+	}
+
+	/// Calculates the output of swapping `amount_in` of `input_type` for `output_type` along the
+	/// invariant of the pool's kind, less the fee of its tier, which stays in the pool.
+	/// Returns both the output and the new pool.
+	pub fn calculate_out(
+		amount_in: &AssetBalanceOf<T>,
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> Result<(AssetBalanceOf<T>, Pool<T>), DispatchError> {
+		Self::ensure_funded(pool)?;
+		match &pool.kind {
+			PoolKind::ConstantProduct => Self::constant_product_out(amount_in, input_type, pool),
+			PoolKind::StableSwap(_) =>
+				Self::stable_swap_out(amount_in, input_type, output_type, pool),
+			PoolKind::Weighted(_) => Self::weighted_out(amount_in, input_type, output_type, pool),
+			PoolKind::Concentrated(_) =>
+				Self::concentrated_out(amount_in, input_type, output_type, pool),
+		}
+	}
+
+	/// Calculates the input needed to swap `input_type` for exactly `amount_out` of
+	/// `output_type` along the invariant of the pool's kind, including the fee of its tier.
+	/// Returns both the input and the new pool.
+	pub fn calculate_in(
+		amount_out: &AssetBalanceOf<T>,
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> Result<(AssetBalanceOf<T>, Pool<T>), DispatchError> {
+		Self::calculate_in_with_fee(amount_out, input_type, output_type, pool)
+			.map(|(amount_in, _, new_pool)| (amount_in, new_pool))
 	}
 
-	// getter for the interface to grab the fee
-	pub fn get_fee() -> u16 {
-		<Fee<T>>::get()
+	// `calculate_in`, also returning the part of the input that is the fee
+	fn calculate_in_with_fee(
+		amount_out: &AssetBalanceOf<T>,
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>, Pool<T>), DispatchError> {
+		Self::ensure_funded(pool)?;
+		match &pool.kind {
+			PoolKind::ConstantProduct => Self::constant_product_in(amount_out, output_type, pool),
+			PoolKind::StableSwap(_) =>
+				Self::stable_swap_in(amount_out, input_type, output_type, pool),
+			PoolKind::Weighted(_) => Self::weighted_in(amount_out, input_type, output_type, pool),
+			PoolKind::Concentrated(_) =>
+				Self::concentrated_in(amount_out, input_type, output_type, pool),
+		}
 	}
 
 	// calculates the output of the exchange based on constant product formula
 	// X * Y = K
 	// returns both the output and the new pool
-	pub fn calculate_out(
+	fn constant_product_out(
 		amount_in: &AssetBalanceOf<T>,
-		input_type: &AssetIdOf<T>,
+		input_type: &AssetKindOf<T>,
 		pool: &Pool<T>,
 	) -> Result<(AssetBalanceOf<T>, Pool<T>), DispatchError> {
-		// remove the fee from the input
-		let fee = Self::calculate_fees(&amount_in)?; // 3 percent fee
+		// remove the fee of the pool's tier from the input
+		let fee = Self::calculate_fees(&amount_in, pool.fee_tier)?;
 		let exact_in_after_fee = amount_in.checked_sub(&fee).ok_or(ArithmeticError::Underflow)?;
 
 		// get the constant k
@@ -700,39 +4326,26 @@ impl<T: Config> Pallet<T> {
 		// old Y - Y = output
 		let output = output_pool.checked_sub(&new_output_pool).ok_or(ArithmeticError::Underflow)?;
 
-		let new_pool: Pool<T>;
+		// only the reserves change, everything else about the pool is carried over
+		let mut new_pool = pool.clone();
 		if *input_type == pool.pool_pair.asset_1 {
-			new_pool = Pool::<T>::new(
-				PoolPair::<T>::new(
-					pool.pool_pair.asset_1.clone(),
-					new_input_pool + fee,
-					pool.pool_pair.asset_2.clone(),
-					new_output_pool,
-				)?,
-				pool.lp_supply,
-			);
+			new_pool.pool_pair.amount_1 = new_input_pool + fee;
+			new_pool.pool_pair.amount_2 = new_output_pool;
 		} else {
-			new_pool = Pool::<T>::new(
-				PoolPair::<T>::new(
-					pool.pool_pair.asset_1.clone(),
-					new_output_pool,
-					pool.pool_pair.asset_2.clone(),
-					new_input_pool + fee,
-				)?,
-				pool.lp_supply,
-			);
+			new_pool.pool_pair.amount_1 = new_output_pool;
+			new_pool.pool_pair.amount_2 = new_input_pool + fee;
 		}
 		Ok((output, new_pool))
 	}
 
 	// calculates the input of the exchange based on constant product formula
 	// X * Y = K
-	// returns both the input and the new pool
-	pub fn calculate_in(
+	// returns the input, the fee included in it and the new pool
+	fn constant_product_in(
 		amount_out: &AssetBalanceOf<T>,
-		output_type: &AssetIdOf<T>,
+		output_type: &AssetKindOf<T>,
 		pool: &Pool<T>,
-	) -> Result<(AssetBalanceOf<T>, Pool<T>), DispatchError> {
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>, Pool<T>), DispatchError> {
 		// get the constant k
 		let k = pool
 			.pool_pair
@@ -758,31 +4371,924 @@ impl<T: Config> Pallet<T> {
 		let mut input_required =
 			new_input_pool.checked_sub(&input_pool).ok_or(ArithmeticError::Underflow)?;
 
-		let fee = Self::calculate_fees(&input_required)?;
+		let fee = Self::calculate_fees(&input_required, pool.fee_tier)?;
 		input_required = input_required.checked_add(&fee).ok_or(ArithmeticError::Overflow)?;
-		let new_pool: Pool<T>;
+		let mut new_pool = pool.clone();
 		if *output_type == pool.pool_pair.asset_1 {
-			new_pool = Pool::<T>::new(
-				PoolPair::<T>::new(
-					pool.pool_pair.asset_1.clone(),
-					new_output_pool,
-					pool.pool_pair.asset_2.clone(),
-					new_input_pool + fee,
-				)?,
-				pool.lp_supply,
-			);
+			new_pool.pool_pair.amount_1 = new_output_pool;
+			new_pool.pool_pair.amount_2 = new_input_pool + fee;
 		} else {
-			new_pool = Pool::<T>::new(
-				PoolPair::<T>::new(
-					pool.pool_pair.asset_1.clone(),
-					new_input_pool + fee,
-					pool.pool_pair.asset_2.clone(),
-					new_output_pool,
-				)?,
-				pool.lp_supply,
-			);
+			new_pool.pool_pair.amount_1 = new_input_pool + fee;
+			new_pool.pool_pair.amount_2 = new_output_pool;
+		}
+		Ok((input_required, fee, new_pool))
+	}
+
+	// calculates the output of the exchange along the StableSwap invariant: the fee is taken
+	// from the input, and the output reserve is solved for so `D` stays the same with the rest
+	// of the input added. One less than that is paid out, so rounding never favours the trader
+	// returns both the output and the new pool
+	fn stable_swap_out(
+		amount_in: &AssetBalanceOf<T>,
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> Result<(AssetBalanceOf<T>, Pool<T>), DispatchError> {
+		let amplification = Self::amplification(pool)?;
+		let fee = Self::calculate_fees(amount_in, pool.fee_tier)?;
+		let exact_in_after_fee = amount_in.checked_sub(&fee).ok_or(ArithmeticError::Underflow)?;
+
+		let mut reserves = Self::stable_reserves(pool);
+		let (index_in, index_out) = Self::stable_indices(pool, input_type, output_type)?;
+		let d =
+			stable_swap::compute_d(&reserves, amplification).ok_or(ArithmeticError::Overflow)?;
+		let reserve_out = reserves[index_out];
+		reserves[index_in] = reserves[index_in].saturating_add(Self::to_u256(exact_in_after_fee));
+		let new_reserve_out = stable_swap::compute_y(&reserves, index_out, amplification, d)
+			.ok_or(ArithmeticError::Overflow)?;
+		let output = Self::from_u256(
+			reserve_out.saturating_sub(new_reserve_out).saturating_sub(U256::one()),
+		)?;
+
+		let mut new_pool = pool.clone();
+		let (old_in, old_out) = Self::reserves_of(input_type, output_type, pool);
+		new_pool.set_reserve(
+			input_type,
+			old_in.checked_add(amount_in).ok_or(ArithmeticError::Overflow)?,
+		)?;
+		new_pool.set_reserve(
+			output_type,
+			old_out.checked_sub(&output).ok_or(ArithmeticError::Underflow)?,
+		)?;
+		Ok((output, new_pool))
+	}
+
+	// calculates the input of the exchange along the StableSwap invariant: the input reserve is
+	// solved for so `D` stays the same with `amount_out` taken out, one more than that is needed
+	// so rounding never favours the trader, and the fee is added on top as in a constant product
+	// pool
+	// returns the input, the fee included in it and the new pool
+	fn stable_swap_in(
+		amount_out: &AssetBalanceOf<T>,
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>, Pool<T>), DispatchError> {
+		let amplification = Self::amplification(pool)?;
+		let mut reserves = Self::stable_reserves(pool);
+		let (index_in, index_out) = Self::stable_indices(pool, input_type, output_type)?;
+		let d =
+			stable_swap::compute_d(&reserves, amplification).ok_or(ArithmeticError::Overflow)?;
+		let reserve_in = reserves[index_in];
+		reserves[index_out] = reserves[index_out]
+			.checked_sub(Self::to_u256(*amount_out))
+			.filter(|reserve| !reserve.is_zero())
+			.ok_or(Error::<T>::InsufficientLiquidity)?;
+		let new_reserve_in = stable_swap::compute_y(&reserves, index_in, amplification, d)
+			.ok_or(ArithmeticError::Overflow)?;
+		let input_without_fee =
+			Self::from_u256(new_reserve_in.saturating_sub(reserve_in).saturating_add(U256::one()))?;
+		let fee = Self::calculate_fees(&input_without_fee, pool.fee_tier)?;
+		let input_required =
+			input_without_fee.checked_add(&fee).ok_or(ArithmeticError::Overflow)?;
+
+		let mut new_pool = pool.clone();
+		let (old_in, old_out) = Self::reserves_of(input_type, output_type, pool);
+		new_pool.set_reserve(
+			input_type,
+			old_in.checked_add(&input_required).ok_or(ArithmeticError::Overflow)?,
+		)?;
+		new_pool.set_reserve(
+			output_type,
+			old_out.checked_sub(amount_out).ok_or(ArithmeticError::Underflow)?,
+		)?;
+		Ok((input_required, fee, new_pool))
+	}
+
+	// calculates the output of the exchange along the weighted product invariant, with the fee
+	// taken from the input as in a constant product pool
+	// returns both the output and the new pool
+	fn weighted_out(
+		amount_in: &AssetBalanceOf<T>,
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> Result<(AssetBalanceOf<T>, Pool<T>), DispatchError> {
+		let (weight_in, weight_out) = Self::weights_of(pool, input_type, output_type)?;
+		let (reserve_in, reserve_out) = Self::reserves_of(input_type, output_type, pool);
+		let fee = Self::calculate_fees(amount_in, pool.fee_tier)?;
+		let exact_in_after_fee = amount_in.checked_sub(&fee).ok_or(ArithmeticError::Underflow)?;
+		ensure!(
+			exact_in_after_fee <= weighted_math::MAX_IN_RATIO.mul_floor(reserve_in),
+			Error::<T>::TradeTooLarge
+		);
+		let output = weighted_math::out_given_in(
+			reserve_in.saturated_into(),
+			weight_in,
+			reserve_out.saturated_into(),
+			weight_out,
+			exact_in_after_fee.saturated_into(),
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		let output = Self::from_u128(output)?;
+		ensure!(
+			output <= weighted_math::MAX_OUT_RATIO.mul_floor(reserve_out),
+			Error::<T>::TradeTooLarge
+		);
+
+		let mut new_pool = pool.clone();
+		new_pool.set_reserve(
+			input_type,
+			reserve_in.checked_add(amount_in).ok_or(ArithmeticError::Overflow)?,
+		)?;
+		new_pool.set_reserve(
+			output_type,
+			reserve_out.checked_sub(&output).ok_or(ArithmeticError::Underflow)?,
+		)?;
+		Ok((output, new_pool))
+	}
+
+	// calculates the input of the exchange along the weighted product invariant, rounded up, with
+	// the fee added on top as in a constant product pool
+	// returns the input, the fee included in it and the new pool
+	fn weighted_in(
+		amount_out: &AssetBalanceOf<T>,
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>, Pool<T>), DispatchError> {
+		let (weight_in, weight_out) = Self::weights_of(pool, input_type, output_type)?;
+		let (reserve_in, reserve_out) = Self::reserves_of(input_type, output_type, pool);
+		ensure!(
+			*amount_out <= weighted_math::MAX_OUT_RATIO.mul_floor(reserve_out),
+			Error::<T>::TradeTooLarge
+		);
+		let input_without_fee = weighted_math::in_given_out(
+			reserve_in.saturated_into(),
+			weight_in,
+			reserve_out.saturated_into(),
+			weight_out,
+			(*amount_out).saturated_into(),
+		)
+		.ok_or(ArithmeticError::Overflow)?;
+		let input_without_fee = Self::from_u128(input_without_fee)?;
+		ensure!(
+			input_without_fee <= weighted_math::MAX_IN_RATIO.mul_floor(reserve_in),
+			Error::<T>::TradeTooLarge
+		);
+		let fee = Self::calculate_fees(&input_without_fee, pool.fee_tier)?;
+		let input_required =
+			input_without_fee.checked_add(&fee).ok_or(ArithmeticError::Overflow)?;
+
+		let mut new_pool = pool.clone();
+		new_pool.set_reserve(
+			input_type,
+			reserve_in.checked_add(&input_required).ok_or(ArithmeticError::Overflow)?,
+		)?;
+		new_pool.set_reserve(
+			output_type,
+			reserve_out.checked_sub(amount_out).ok_or(ArithmeticError::Underflow)?,
+		)?;
+		Ok((input_required, fee, new_pool))
+	}
+
+	// the weights of `asset_a` and `asset_b` in a weighted pool
+	fn weights_of(
+		pool: &Pool<T>,
+		asset_a: &AssetKindOf<T>,
+		asset_b: &AssetKindOf<T>,
+	) -> Result<(Perbill, Perbill), DispatchError> {
+		match (pool.weight(asset_a), pool.weight(asset_b)) {
+			(Some(weight_a), Some(weight_b)) if asset_a != asset_b => Ok((weight_a, weight_b)),
+			_ => Err(Error::<T>::AssetNotInPool.into()),
+		}
+	}
+
+	// a result of the weighted maths as a balance, failing if it doesn't fit
+	fn from_u128(value: u128) -> Result<AssetBalanceOf<T>, DispatchError> {
+		value.try_into().map_err(|_| ArithmeticError::Overflow.into())
+	}
+
+	// calculates the output of the exchange along the liquidity of a concentrated pool, with the
+	// fee taken from the input of every step
+	// returns both the output and the new pool
+	fn concentrated_out(
+		amount_in: &AssetBalanceOf<T>,
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> Result<(AssetBalanceOf<T>, Pool<T>), DispatchError> {
+		let amount_in = Self::to_u256(*amount_in);
+		let (paid_in, output, _, new_pool) =
+			Self::concentrated_swap(input_type, output_type, amount_in, true, pool)?;
+		ensure!(paid_in == amount_in, Error::<T>::InsufficientLiquidity);
+		Ok((Self::from_u256(output)?, new_pool))
+	}
+
+	// calculates the input of the exchange along the liquidity of a concentrated pool, with the
+	// fee of every step added on top
+	// returns the input, the fee included in it and the new pool
+	fn concentrated_in(
+		amount_out: &AssetBalanceOf<T>,
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> Result<(AssetBalanceOf<T>, AssetBalanceOf<T>, Pool<T>), DispatchError> {
+		let amount_out = Self::to_u256(*amount_out);
+		let (input_required, output, fee, new_pool) =
+			Self::concentrated_swap(input_type, output_type, amount_out, false, pool)?;
+		ensure!(output == amount_out, Error::<T>::InsufficientLiquidity);
+		Ok((Self::from_u256(input_required)?, Self::from_u256(fee)?, new_pool))
+	}
+
+	// swaps `input_type` for `output_type` along the liquidity of a concentrated pool until
+	// `amount` is paid in, fees included, or taken out if not `exact_in`, crossing the ticks the
+	// price moves over. Returns what was paid in with the fees, what was taken out, the fees and
+	// the new pool. Without enough liquidity the swap stops at the bounds of prices, short of
+	// `amount`, which the callers reject
+	fn concentrated_swap(
+		input_type: &AssetKindOf<T>,
+		output_type: &AssetKindOf<T>,
+		amount: U256,
+		exact_in: bool,
+		pool: &Pool<T>,
+	) -> Result<(U256, U256, U256, Pool<T>), DispatchError> {
+		let pair = &pool.pool_pair;
+		// paying in the first asset moves the price down
+		let first_in = match (input_type, output_type) {
+			(input, output) if *input == pair.asset_1 && *output == pair.asset_2 => true,
+			(input, output) if *input == pair.asset_2 && *output == pair.asset_1 => false,
+			_ => return Err(Error::<T>::AssetNotInPool.into()),
+		};
+		let mut new_pool = pool.clone();
+		let PoolKind::Concentrated(concentrated) = &mut new_pool.kind else {
+			return Err(Error::<T>::UnsupportedPoolKind.into())
+		};
+		let limit = if first_in {
+			concentrated_math::MIN_SQRT_PRICE + U256::one()
+		} else {
+			concentrated_math::MAX_SQRT_PRICE - U256::one()
+		};
+
+		let (mut remaining, mut paid_in, mut paid_out, mut fees) =
+			(amount, U256::zero(), U256::zero(), U256::zero());
+		while !remaining.is_zero() && concentrated.sqrt_price != limit {
+			// the next initialized tick the price moves towards, a step never goes past one
+			let mut ticks = concentrated.ticks.iter().map(|(tick, _)| *tick);
+			let next_tick = if first_in {
+				ticks.rev().find(|tick| *tick <= concentrated.tick)
+			} else {
+				ticks.find(|tick| *tick > concentrated.tick)
+			};
+			let next_sqrt_price = next_tick
+				.map(|tick| {
+					concentrated_math::sqrt_price_at_tick(tick).ok_or(ArithmeticError::Overflow)
+				})
+				.transpose()?;
+			let target = match next_sqrt_price {
+				Some(sqrt_price) if first_in => sqrt_price.max(limit),
+				Some(sqrt_price) => sqrt_price.min(limit),
+				None => limit,
+			};
+
+			let step = concentrated_math::swap_step(
+				concentrated.sqrt_price,
+				target,
+				concentrated.liquidity,
+				remaining,
+				pool.fee_tier,
+				exact_in,
+			)
+			.ok_or(ArithmeticError::Overflow)?;
+			let step_in = step.amount_in.checked_add(step.fee).ok_or(ArithmeticError::Overflow)?;
+			remaining = remaining
+				.checked_sub(if exact_in { step_in } else { step.amount_out })
+				.ok_or(ArithmeticError::Underflow)?;
+			paid_in = paid_in.checked_add(step_in).ok_or(ArithmeticError::Overflow)?;
+			paid_out = paid_out.checked_add(step.amount_out).ok_or(ArithmeticError::Overflow)?;
+			fees = fees.checked_add(step.fee).ok_or(ArithmeticError::Overflow)?;
+			// the fee is shared by the liquidity in range, without any it stays in the pool
+			if concentrated.liquidity > 0 {
+				let growth = concentrated_math::fee_growth(step.fee, concentrated.liquidity)
+					.ok_or(ArithmeticError::Overflow)?;
+				let global = if first_in {
+					&mut concentrated.fee_growth_global_1
+				} else {
+					&mut concentrated.fee_growth_global_2
+				};
+				*global = global.overflowing_add(growth).0;
+			}
+
+			let price_before = concentrated.sqrt_price;
+			concentrated.sqrt_price = step.sqrt_price;
+			match next_tick {
+				Some(tick) if step.reached && Some(step.sqrt_price) == next_sqrt_price => {
+					let liquidity_net = concentrated.cross(tick);
+					let liquidity_delta = if first_in {
+						liquidity_net.checked_neg().ok_or(ArithmeticError::Overflow)?
+					} else {
+						liquidity_net
+					};
+					concentrated.liquidity = concentrated_math::add_liquidity_delta(
+						concentrated.liquidity,
+						liquidity_delta,
+					)
+					.ok_or(ArithmeticError::Overflow)?;
+					concentrated.tick = if first_in { tick - 1 } else { tick };
+				},
+				_ if step.sqrt_price != price_before => {
+					let tick = concentrated_math::tick_at_sqrt_price(step.sqrt_price)
+						.ok_or(ArithmeticError::Overflow)?;
+					// rounding can't carry a price that stopped short of the next tick onto it
+					concentrated.tick = match next_tick {
+						Some(next_tick) if !first_in => tick.min(next_tick - 1),
+						_ => tick,
+					};
+				},
+				_ => {},
+			}
+		}
+
+		let (reserve_in, reserve_out) = Self::reserves_of(input_type, output_type, &new_pool);
+		new_pool.set_reserve(
+			input_type,
+			reserve_in
+				.checked_add(&Self::from_u256(paid_in)?)
+				.ok_or(ArithmeticError::Overflow)?,
+		)?;
+		new_pool.set_reserve(
+			output_type,
+			reserve_out
+				.checked_sub(&Self::from_u256(paid_out)?)
+				.ok_or(Error::<T>::InsufficientLiquidity)?,
+		)?;
+		Ok((paid_in, paid_out, fees, new_pool))
+	}
+
+	// the current amplification coefficient of a StableSwap pool
+	fn amplification(pool: &Pool<T>) -> Result<u32, DispatchError> {
+		match &pool.kind {
+			PoolKind::StableSwap(stable) =>
+				Ok(stable.amplification.at(frame_system::Pallet::<T>::block_number())),
+			PoolKind::ConstantProduct | PoolKind::Weighted(_) | PoolKind::Concentrated(_) =>
+				Err(Error::<T>::UnsupportedPoolKind.into()),
+		}
+	}
+
+	// the reserves of a pool for the StableSwap maths, in the pool's order of its assets
+	fn stable_reserves(pool: &Pool<T>) -> Vec<U256> {
+		pool.reserves().into_iter().map(|(_, reserve)| Self::to_u256(reserve)).collect()
+	}
+
+	// the positions of `asset_in` and `asset_out` in `stable_reserves`
+	fn stable_indices(
+		pool: &Pool<T>,
+		asset_in: &AssetKindOf<T>,
+		asset_out: &AssetKindOf<T>,
+	) -> Result<(usize, usize), DispatchError> {
+		let reserves = pool.reserves();
+		let index_of = |asset: &AssetKindOf<T>| {
+			reserves.iter().position(|(reserve_asset, _)| reserve_asset == asset)
+		};
+		match (index_of(asset_in), index_of(asset_out)) {
+			(Some(index_in), Some(index_out)) if index_in != index_out => Ok((index_in, index_out)),
+			_ => Err(Error::<T>::AssetNotInPool.into()),
+		}
+	}
+
+	fn to_u256(amount: AssetBalanceOf<T>) -> U256 {
+		U256::from(amount.saturated_into::<u128>())
+	}
+
+	// a result of the StableSwap maths as a balance, failing if it doesn't fit
+	fn from_u256(value: U256) -> Result<AssetBalanceOf<T>, DispatchError> {
+		ensure!(value <= U256::from(u128::MAX), ArithmeticError::Overflow);
+		value.low_u128().try_into().map_err(|_| ArithmeticError::Overflow.into())
+	}
+
+	// checks that a swap path is usable
+	// a path must have at least two assets and a fee tier for every hop, and may not route through
+	// the same pool twice, as every hop is priced against the pool state left by the previous one
+	fn ensure_valid_path(path: &[AssetKindOf<T>], fee_tiers: &[u16]) -> DispatchResult {
+		ensure!(path.len() >= 2, Error::<T>::InvalidPath);
+		ensure!(fee_tiers.len() == path.len() - 1, Error::<T>::InvalidPath);
+		let mut lp_ids = Vec::with_capacity(path.len() - 1);
+		for (hop, fee_tier) in path.windows(2).zip(fee_tiers) {
+			let lp_id = Self::get_lp_id(&hop[0], &hop[1], *fee_tier)?;
+			ensure!(!lp_ids.contains(&lp_id), Error::<T>::InvalidPath);
+			lp_ids.push(lp_id);
+		}
+		Ok(())
+	}
+
+	// walks `path` forwards, feeding the output of each hop into the next one
+	// updates every pool on the way and returns the output of the final hop, along with the LP
+	// token id and input of every hop for `transfer_along_path`
+	fn swap_along_path_exact_in(
+		path: &[AssetKindOf<T>],
+		fee_tiers: &[u16],
+		exact_in: AssetBalanceOf<T>,
+	) -> Result<(AssetBalanceOf<T>, Vec<(AssetIdOf<T>, AssetBalanceOf<T>)>), DispatchError> {
+		let mut lp_hops = Vec::with_capacity(path.len() - 1);
+		let mut amount = exact_in;
+		for (hop, fee_tier) in path.windows(2).zip(fee_tiers) {
+			let cur_lp_id = Self::get_lp_id(&hop[0], &hop[1], *fee_tier)?;
+			let mut pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+			Self::update_oracle(&cur_lp_id, &mut pool);
+			let (amount_out, new_pool) = Self::calculate_out(&amount, &hop[0], &hop[1], &pool)?;
+			Self::put_swapped_pool(&cur_lp_id, &pool, new_pool)?;
+
+			Self::deposit_event(Event::SwapEvent {
+				asset_in: hop[0].clone(),
+				asset_out: hop[1].clone(),
+				fee_tier: *fee_tier,
+				amount_in: amount,
+				amount_out,
+			});
+			lp_hops.push((cur_lp_id, amount));
+			amount = amount_out;
+		}
+		Ok((amount, lp_hops))
+	}
+
+	// walks `path` backwards, working out how much each hop needs so the next one can pay out
+	// updates every pool on the way and returns the input required by the first hop, along with
+	// the LP token id and input of every hop for `transfer_along_path`
+	fn swap_along_path_exact_out(
+		path: &[AssetKindOf<T>],
+		fee_tiers: &[u16],
+		exact_out: AssetBalanceOf<T>,
+	) -> Result<(AssetBalanceOf<T>, Vec<(AssetIdOf<T>, AssetBalanceOf<T>)>), DispatchError> {
+		let mut hops = Vec::with_capacity(path.len() - 1);
+		let mut lp_hops = Vec::with_capacity(path.len() - 1);
+		let mut amount = exact_out;
+		for (hop, fee_tier) in path.windows(2).zip(fee_tiers).rev() {
+			let cur_lp_id = Self::get_lp_id(&hop[0], &hop[1], *fee_tier)?;
+			let mut pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+			Self::update_oracle(&cur_lp_id, &mut pool);
+			let (amount_in, new_pool) = Self::calculate_in(&amount, &hop[0], &hop[1], &pool)?;
+			Self::put_swapped_pool(&cur_lp_id, &pool, new_pool)?;
+
+			hops.push((hop[0].clone(), hop[1].clone(), *fee_tier, amount_in, amount));
+			lp_hops.push((cur_lp_id, amount_in));
+			amount = amount_in;
+		}
+		lp_hops.reverse();
+
+		// emit the hop events in the order the assets actually flow
+		for (asset_in, asset_out, fee_tier, amount_in, amount_out) in hops.into_iter().rev() {
+			Self::deposit_event(Event::SwapEvent {
+				asset_in,
+				asset_out,
+				fee_tier,
+				amount_in,
+				amount_out,
+			});
+		}
+		Ok((amount, lp_hops))
+	}
+
+	// moves the assets of a path swap: `who` pays the input of the first hop into its pool, each
+	// pool pays the input of the next hop into that one, and the last pool pays `amount_out` to
+	// `who`. `preservation` is for the caller's payment and the final payout, in that order.
+	fn transfer_along_path(
+		who: &T::AccountId,
+		path: &[AssetKindOf<T>],
+		lp_hops: &[(AssetIdOf<T>, AssetBalanceOf<T>)],
+		amount_out: AssetBalanceOf<T>,
+		preservation: (Preservation, Preservation),
+	) -> DispatchResult {
+		let mut payer = who.clone();
+		for (index, (asset_in, (lp_id, amount_in))) in path.iter().zip(lp_hops).enumerate() {
+			let pool_account = Self::pool_account(lp_id);
+			let payer_preservation = if index == 0 { preservation.0 } else { Expendable };
+			NativeOrAssetUnion::<T>::transfer(
+				asset_in,
+				&payer,
+				&pool_account,
+				*amount_in,
+				payer_preservation,
+			)?;
+			payer = pool_account;
+		}
+		NativeOrAssetUnion::<T>::transfer(
+			&path[path.len() - 1],
+			&payer,
+			who,
+			amount_out,
+			preservation.1,
+		)?;
+		Ok(())
+	}
+
+	// returns the reserves of `asset_a` and `asset_b` in that order
+	fn reserves_of(
+		asset_a: &AssetKindOf<T>,
+		asset_b: &AssetKindOf<T>,
+		pool: &Pool<T>,
+	) -> (AssetBalanceOf<T>, AssetBalanceOf<T>) {
+		(pool.reserve(asset_a).unwrap_or_default(), pool.reserve(asset_b).unwrap_or_default())
+	}
+
+	/// Quotes swapping an exact `amount_in` of `asset_in` for `asset_out` against the current
+	/// state of the pool with `fee_tier`. Returns `None` if there is no pool or the swap would
+	/// fail.
+	pub fn quote_exact_in(
+		asset_in: AssetKindOf<T>,
+		asset_out: AssetKindOf<T>,
+		fee_tier: u16,
+		amount_in: AssetBalanceOf<T>,
+	) -> Option<Quote<AssetBalanceOf<T>>> {
+		let pool = <PoolMap<T>>::get(Self::get_lp_id(&asset_in, &asset_out, fee_tier).ok()?)?;
+		Self::ensure_swappable(&pool).ok()?;
+		let (amount_out, _) = Self::calculate_out(&amount_in, &asset_in, &asset_out, &pool).ok()?;
+		let fee = Self::calculate_fees(&amount_in, pool.fee_tier).ok()?;
+
+		// the output the trade would get if the spot price did not move
+		let spot_out =
+			Self::spot_amount(&pool, &asset_in, &asset_out, amount_in.checked_sub(&fee)?)?;
+		let price_impact =
+			Permill::from_rational(spot_out.saturating_sub(amount_out), spot_out.max(One::one()));
+
+		Some(Quote { amount_in, amount_out, fee, price_impact })
+	}
+
+	/// Quotes swapping `asset_in` for an exact `amount_out` of `asset_out` against the current
+	/// state of the pool with `fee_tier`. Returns `None` if there is no pool or the swap would
+	/// fail.
+	pub fn quote_exact_out(
+		asset_in: AssetKindOf<T>,
+		asset_out: AssetKindOf<T>,
+		fee_tier: u16,
+		amount_out: AssetBalanceOf<T>,
+	) -> Option<Quote<AssetBalanceOf<T>>> {
+		let pool = <PoolMap<T>>::get(Self::get_lp_id(&asset_in, &asset_out, fee_tier).ok()?)?;
+		Self::ensure_swappable(&pool).ok()?;
+		let (amount_in, fee, _) =
+			Self::calculate_in_with_fee(&amount_out, &asset_in, &asset_out, &pool).ok()?;
+		let input_without_fee = amount_in.checked_sub(&fee)?;
+
+		// the input the trade would need if the spot price did not move
+		let spot_in = Self::spot_amount(&pool, &asset_out, &asset_in, amount_out)?;
+		let price_impact = Permill::from_rational(
+			input_without_fee.saturating_sub(spot_in),
+			input_without_fee.max(One::one()),
+		);
+
+		Some(Quote { amount_in, amount_out, fee, price_impact })
+	}
+
+	/// Returns the reserves of `asset_a` and `asset_b` in the pool with `fee_tier`, in that order.
+	pub fn get_reserves(
+		asset_a: AssetKindOf<T>,
+		asset_b: AssetKindOf<T>,
+		fee_tier: u16,
+	) -> Option<(AssetBalanceOf<T>, AssetBalanceOf<T>)> {
+		let pool = <PoolMap<T>>::get(Self::get_lp_id(&asset_a, &asset_b, fee_tier).ok()?)?;
+		Some(Self::reserves_of(&asset_a, &asset_b, &pool))
+	}
+
+	/// Returns a snapshot of the pool of `asset_a` and `asset_b` with `fee_tier`, if it exists.
+	pub fn pool_info(
+		asset_a: AssetKindOf<T>,
+		asset_b: AssetKindOf<T>,
+		fee_tier: u16,
+	) -> Option<PoolInfo<AssetIdOf<T>, AssetBalanceOf<T>>> {
+		let lp_id = Self::get_lp_id(&asset_a, &asset_b, fee_tier).ok()?;
+		let pool = <PoolMap<T>>::get(&lp_id)?;
+		Some(Self::to_pool_info(lp_id, pool))
+	}
+
+	/// Returns a snapshot of every pool. This iterates all of `PoolMap`, so it is only meant to
+	/// be called from outside the runtime.
+	pub fn pools() -> Vec<PoolInfo<AssetIdOf<T>, AssetBalanceOf<T>>> {
+		<PoolMap<T>>::iter()
+			.map(|(lp_id, pool)| Self::to_pool_info(lp_id, pool))
+			.collect()
+	}
+
+	fn to_pool_info(
+		lp_id: AssetIdOf<T>,
+		pool: Pool<T>,
+	) -> PoolInfo<AssetIdOf<T>, AssetBalanceOf<T>> {
+		PoolInfo {
+			lp_id,
+			asset_1: pool.pool_pair.asset_1,
+			amount_1: pool.pool_pair.amount_1,
+			asset_2: pool.pool_pair.asset_2,
+			amount_2: pool.pool_pair.amount_2,
+			fee_tier: pool.fee_tier,
+			lp_supply: pool.lp_supply,
 		}
-		Ok((input_required, new_pool))
+	}
+
+	// the current time in seconds
+	pub fn now() -> u64 {
+		T::Timestamp::now().as_secs()
+	}
+
+	// the price of `asset_1` in `asset_2` and of `asset_2` in `asset_1` at the current reserves.
+	// The TWAP and the circuit breaker follow this price, in a StableSwap pool as well
+	fn spot_prices(pool: &Pool<T>) -> Option<(FixedU128, FixedU128)> {
+		Self::spot_prices_of(pool, &pool.pool_pair.asset_1, &pool.pool_pair.asset_2)
+	}
+
+	// the price of `asset_a` in `asset_b` and of `asset_b` in `asset_a` at the current reserves,
+	// the marginal rate an infinitely small swap would get along the pool's invariant
+	fn spot_prices_of(
+		pool: &Pool<T>,
+		asset_a: &AssetKindOf<T>,
+		asset_b: &AssetKindOf<T>,
+	) -> Option<(FixedU128, FixedU128)> {
+		match &pool.kind {
+			PoolKind::ConstantProduct => {
+				let (amount_a, amount_b) = Self::reserves_of(asset_a, asset_b, pool);
+				let amount_a = amount_a.saturated_into::<u128>();
+				let amount_b = amount_b.saturated_into::<u128>();
+				Some((
+					FixedU128::checked_from_rational(amount_b, amount_a)?,
+					FixedU128::checked_from_rational(amount_a, amount_b)?,
+				))
+			},
+			PoolKind::StableSwap(_) => {
+				let amplification = Self::amplification(pool).ok()?;
+				let reserves = Self::stable_reserves(pool);
+				let (index_a, index_b) = Self::stable_indices(pool, asset_a, asset_b).ok()?;
+				Some((
+					stable_swap::spot_price(&reserves, index_a, index_b, amplification)?,
+					stable_swap::spot_price(&reserves, index_b, index_a, amplification)?,
+				))
+			},
+			PoolKind::Weighted(_) => {
+				let (weight_a, weight_b) = Self::weights_of(pool, asset_a, asset_b).ok()?;
+				let (amount_a, amount_b) = Self::reserves_of(asset_a, asset_b, pool);
+				let amount_a = amount_a.saturated_into::<u128>();
+				let amount_b = amount_b.saturated_into::<u128>();
+				Some((
+					weighted_math::spot_price(amount_a, weight_a, amount_b, weight_b)?,
+					weighted_math::spot_price(amount_b, weight_b, amount_a, weight_a)?,
+				))
+			},
+			PoolKind::Concentrated(concentrated) => {
+				let pair = &pool.pool_pair;
+				if asset_a == asset_b || !pool.contains(asset_a) || !pool.contains(asset_b) {
+					return None
+				}
+				// the price is followed by its square root, regardless of the reserves
+				let price = concentrated_math::price_of(concentrated.sqrt_price)?;
+				let inverse = price.reciprocal()?;
+				Some(if *asset_a == pair.asset_1 { (price, inverse) } else { (inverse, price) })
+			},
+		}
+	}
+
+	// what `amount` of `asset_in` is worth in `asset_out` at the pool's spot price
+	fn spot_amount(
+		pool: &Pool<T>,
+		asset_in: &AssetKindOf<T>,
+		asset_out: &AssetKindOf<T>,
+		amount: AssetBalanceOf<T>,
+	) -> Option<AssetBalanceOf<T>> {
+		match &pool.kind {
+			PoolKind::ConstantProduct => {
+				let (reserve_in, reserve_out) = Self::reserves_of(asset_in, asset_out, pool);
+				amount.checked_mul(&reserve_out)?.checked_div(&reserve_in)
+			},
+			PoolKind::StableSwap(_) | PoolKind::Weighted(_) | PoolKind::Concentrated(_) => {
+				let (price, _) = Self::spot_prices_of(pool, asset_in, asset_out)?;
+				Some(price.saturating_mul_int(amount))
+			},
+		}
+	}
+
+	// scales a price in raw units of `asset_in` and `asset_out` to whole units of both, using the
+	// decimals in their metadata
+	fn adjust_for_decimals(
+		price: FixedU128,
+		asset_in: &AssetKindOf<T>,
+		asset_out: &AssetKindOf<T>,
+	) -> Result<FixedU128, DispatchError> {
+		let decimals_in = NativeOrAssetUnion::<T>::decimals(asset_in);
+		let decimals_out = NativeOrAssetUnion::<T>::decimals(asset_out);
+		let scale = |decimals: u8| {
+			10u128
+				.checked_pow(decimals.into())
+				.and_then(FixedU128::checked_from_integer)
+				.ok_or(ArithmeticError::Overflow)
+		};
+		let adjusted = if decimals_in >= decimals_out {
+			price.checked_mul(&scale(decimals_in - decimals_out)?)
+		} else {
+			price.checked_div(&scale(decimals_out - decimals_in)?)
+		};
+		adjusted.ok_or(ArithmeticError::Overflow.into())
+	}
+
+	/// Returns the spot price of `asset_in` in `asset_out` and the inverse in the pool with
+	/// `fee_tier`, in whole units of both assets.
+	pub fn spot_price(
+		asset_in: &AssetKindOf<T>,
+		asset_out: &AssetKindOf<T>,
+		fee_tier: u16,
+	) -> Result<(FixedU128, FixedU128), DispatchError> {
+		let cur_lp_id = Self::get_lp_id(asset_in, asset_out, fee_tier)?;
+		let pool = <PoolMap<T>>::get(&cur_lp_id).ok_or(Error::<T>::NoPool)?;
+		let (rate, inverse_rate) = Self::spot_prices_of(&pool, asset_in, asset_out)
+			.ok_or(ArithmeticError::DivisionByZero)?;
+		Ok((
+			Self::adjust_for_decimals(rate, asset_in, asset_out)?,
+			Self::adjust_for_decimals(inverse_rate, asset_out, asset_in)?,
+		))
+	}
+
+	// adds the current prices, held since `block_timestamp_last`, to the pool's accumulators
+	fn accumulate_prices(pool: &mut Pool<T>, now: u64) {
+		let elapsed = now.saturating_sub(pool.block_timestamp_last) as u128;
+		if let Some((price_1, price_2)) = Self::spot_prices(pool) {
+			pool.price_cumulative_1 =
+				pool.price_cumulative_1.wrapping_add(price_1.into_inner().wrapping_mul(elapsed));
+			pool.price_cumulative_2 =
+				pool.price_cumulative_2.wrapping_add(price_2.into_inner().wrapping_mul(elapsed));
+		}
+		pool.block_timestamp_last = now;
+	}
+
+	/// Updates the TWAP accumulators of a pool and records an observation. This must be called
+	/// before the reserves change. Only the first call in a block has an effect, so the price
+	/// every block contributes is the one it started with.
+	pub fn update_oracle(lp_id: &AssetIdOf<T>, pool: &mut Pool<T>) {
+		let now = Self::now();
+		if now <= pool.block_timestamp_last {
+			return
+		}
+		Self::accumulate_prices(pool, now);
+		Self::record_observation(lp_id, pool);
+	}
+
+	// stores the current accumulators of a pool, overwriting the oldest observation when full
+	fn record_observation(lp_id: &AssetIdOf<T>, pool: &Pool<T>) {
+		let observation = Observation {
+			timestamp: pool.block_timestamp_last,
+			price_cumulative_1: pool.price_cumulative_1,
+			price_cumulative_2: pool.price_cumulative_2,
+		};
+		<Observations<T>>::mutate(lp_id, |observations| {
+			if let Err(observation) = observations.try_push(observation) {
+				let index = <ObservationIndex<T>>::get(lp_id);
+				if let Some(slot) = observations.get_mut(index as usize) {
+					*slot = observation;
+				}
+				<ObservationIndex<T>>::insert(
+					lp_id,
+					(index + 1) % T::MaxObservations::get().max(1),
+				);
+			}
+		});
+	}
+
+	/// Returns the time weighted average price of `asset_in` in `asset_out` in the pool with
+	/// `fee_tier` over at least the last `window` seconds, in whole units of both assets. The
+	/// newest observation at least `window` seconds old is used as the start, so the real window
+	/// can be longer when the pool was not touched every block.
+	pub fn consult_twap(
+		asset_in: &AssetKindOf<T>,
+		asset_out: &AssetKindOf<T>,
+		fee_tier: u16,
+		window: u64,
+	) -> Result<FixedU128, DispatchError> {
+		ensure!(window > 0, Error::<T>::InvalidTwapWindow);
+		let lp_id = Self::get_lp_id(asset_in, asset_out, fee_tier)?;
+		let mut pool = <PoolMap<T>>::get(&lp_id).ok_or(Error::<T>::NoPool)?;
+		// the accumulators only follow the price of the pool's first two assets
+		let pair = &pool.pool_pair;
+		ensure!(
+			[&pair.asset_1, &pair.asset_2].contains(&asset_in) &&
+				[&pair.asset_1, &pair.asset_2].contains(&asset_out),
+			Error::<T>::UnsupportedPoolKind
+		);
+
+		let now = Self::now();
+		let start = now.checked_sub(window).ok_or(Error::<T>::NoObservationForWindow)?;
+		let observation = <Observations<T>>::get(&lp_id)
+			.into_iter()
+			.filter(|observation| observation.timestamp <= start)
+			.max_by_key(|observation| observation.timestamp)
+			.ok_or(Error::<T>::NoObservationForWindow)?;
+
+		// bring the accumulators up to date without storing them
+		Self::accumulate_prices(&mut pool, now);
+		let (cumulative_now, cumulative_then) = if *asset_in == pool.pool_pair.asset_1 {
+			(pool.price_cumulative_1, observation.price_cumulative_1)
+		} else {
+			(pool.price_cumulative_2, observation.price_cumulative_2)
+		};
+		let elapsed = (now - observation.timestamp) as u128;
+		let twap = FixedU128::from_inner(cumulative_now.wrapping_sub(cumulative_then) / elapsed);
+		Self::adjust_for_decimals(twap, asset_in, asset_out)
+	}
+
+	/// The LP tokens `who` has staked in the farm of `lp_id`. The runtime must report this as
+	/// frozen through the `Freezer` of the assets pallet, so staked LP tokens can't be moved or
+	/// withdrawn.
+	pub fn staked_balance(lp_id: &AssetIdOf<T>, who: &T::AccountId) -> Option<AssetBalanceOf<T>> {
+		<Stakes<T>>::get(lp_id, who)
+			.map(|stake| stake.amount)
+			.filter(|amount| !amount.is_zero())
+	}
+
+	/// The rewards `who` could claim from the farm of `lp_id` at the current block.
+	pub fn pending_rewards(lp_id: &AssetIdOf<T>, who: &T::AccountId) -> AssetBalanceOf<T> {
+		let (Some(mut farm), Some(mut stake)) =
+			(<Farms<T>>::get(lp_id), <Stakes<T>>::get(lp_id, who))
+		else {
+			return Zero::zero()
+		};
+		Self::update_farm(&mut farm);
+		Self::accrue_rewards(&farm, &mut stake)
+			.map(|_| stake.pending)
+			.unwrap_or_default()
+	}
+
+	// brings the rewards earned by a single staked LP token in a farm up to the current block
+	fn update_farm(farm: &mut Farm<T>) {
+		let now = frame_system::Pallet::<T>::block_number();
+		if now <= farm.last_update {
+			return
+		}
+		if !farm.total_staked.is_zero() {
+			let blocks = now.saturating_sub(farm.last_update).saturated_into::<u128>();
+			let emission = farm.reward_per_block.saturated_into::<u128>().saturating_mul(blocks);
+			farm.reward_per_share =
+				farm.reward_per_share.saturating_add(FixedU128::saturating_from_rational(
+					emission,
+					farm.total_staked.saturated_into::<u128>(),
+				));
+		}
+		farm.last_update = now;
+	}
+
+	// adds the rewards a stake earned since it was last updated to its pending rewards
+	fn accrue_rewards(farm: &Farm<T>, stake: &mut Stake<T>) -> Result<(), DispatchError> {
+		let earned = farm
+			.reward_per_share
+			.saturating_sub(stake.reward_per_share_paid)
+			.saturating_mul_int(stake.amount.saturated_into::<u128>());
+		stake.pending = stake
+			.pending
+			.checked_add(&earned.saturated_into())
+			.ok_or(ArithmeticError::Overflow)?;
+		stake.reward_per_share_paid = farm.reward_per_share;
+		Ok(())
+	}
+
+	// stores a stake, or removes it once nothing is staked and all its rewards are claimed
+	fn put_stake(lp_id: &AssetIdOf<T>, who: &T::AccountId, stake: Stake<T>) {
+		if stake.amount.is_zero() && stake.pending.is_zero() {
+			<Stakes<T>>::remove(lp_id, who);
+		} else {
+			<Stakes<T>>::insert(lp_id, who, stake);
+		}
+	}
+
+	// adds claimed rewards to the vesting schedule of `who` for `reward_asset` and pays out what
+	// has vested, returning the amount paid out
+	// the part of the schedule still vesting restarts over a full period along with new rewards
+	fn vest_rewards(
+		who: &T::AccountId,
+		reward_asset: &AssetKindOf<T>,
+		amount: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		let now = frame_system::Pallet::<T>::block_number();
+		let mut schedule = <RewardVesting<T>>::get(reward_asset, who)
+			.unwrap_or_else(|| VestingSchedule::<T>::new(now));
+		let vested = schedule.vested(now);
+		let mut released =
+			vested.checked_sub(&schedule.released).ok_or(ArithmeticError::Underflow)?;
+		schedule.released = vested;
+
+		if T::RewardVestingPeriod::get().is_zero() {
+			released = released.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+		} else if !amount.is_zero() {
+			let locked = schedule.total.checked_sub(&vested).ok_or(ArithmeticError::Underflow)?;
+			schedule = VestingSchedule::<T>::new(now);
+			schedule.total = locked.checked_add(&amount).ok_or(ArithmeticError::Overflow)?;
+		}
+
+		if schedule.released >= schedule.total {
+			<RewardVesting<T>>::remove(reward_asset, who);
+		} else {
+			<RewardVesting<T>>::insert(reward_asset, who, schedule);
+		}
+		if !released.is_zero() {
+			NativeOrAssetUnion::<T>::transfer(
+				reward_asset,
+				&Self::rewards_account(),
+				who,
+				released,
+				Expendable,
+			)?;
+		}
+		Ok(released)
 	}
 
 	// function for setting up accounts while testing
@@ -822,24 +5328,23 @@ impl<T: Config> pba_interface::DexInterface for Pallet<T> {
 		_token_id: Self::AssetId,
 		_amount: Self::AssetBalance,
 	) -> DispatchResult {
-		unimplemented!()
+		// creates the asset first if it doesn't exist yet
+		Self::setup_account(_who, vec![(_token_id, _amount)])
 	}
 
 	fn asset_balance(_who: Self::AccountId, _token_id: Self::AssetId) -> Self::AssetBalance {
 		Self::asset_balance(_who, _token_id)
 	}
 
+	// the interface has no notion of fee tiers, so it always uses the default one
 	fn swap_fee() -> u16 {
-		// convert my percentage to basis points
-		// default is 3 u16 to represent 3%
-		let fee = Self::get_fee();
-
-		// 3% -> 300 basis points
-		fee * 100
+		T::DefaultFeeTier::get()
 	}
 
 	fn lp_id(_asset_a: Self::AssetId, _asset_b: Self::AssetId) -> Self::AssetId {
-		Self::get_lp_id(&_asset_a, &_asset_b).unwrap()
+		// a pair without a pool yet gets the id of the next LP token
+		Self::get_lp_id(&_asset_a.into(), &_asset_b.into(), T::DefaultFeeTier::get())
+			.unwrap_or_else(|_| Self::next_lp_id())
 	}
 
 	fn add_liquidity(
@@ -851,11 +5356,16 @@ impl<T: Config> pba_interface::DexInterface for Pallet<T> {
 	) -> DispatchResult {
 		Self::add_liquidity(
 			frame_system::RawOrigin::Signed(_who).into(),
-			_asset_a,
-			_asset_b,
+			_asset_a.into(),
+			_asset_b.into(),
+			T::DefaultFeeTier::get(),
 			_amount_a,
 			_amount_b,
+			Zero::zero(),
+			Zero::zero(),
 		)
+		.map(|_| ())
+		.map_err(|e| e.error)
 	}
 
 	fn remove_liquidity(
@@ -866,9 +5376,12 @@ impl<T: Config> pba_interface::DexInterface for Pallet<T> {
 	) -> DispatchResult {
 		Self::remove_liquidity(
 			frame_system::RawOrigin::Signed(_who).into(),
-			_asset_a,
-			_asset_b,
+			_asset_a.into(),
+			_asset_b.into(),
+			T::DefaultFeeTier::get(),
 			_token_amount,
+			Zero::zero(),
+			Zero::zero(),
 		)
 	}
 
@@ -881,8 +5394,9 @@ impl<T: Config> pba_interface::DexInterface for Pallet<T> {
 	) -> DispatchResult {
 		Self::swap_exact_in_for_out(
 			frame_system::RawOrigin::Signed(_who).into(),
-			_asset_in,
-			_asset_out,
+			_asset_in.into(),
+			_asset_out.into(),
+			T::DefaultFeeTier::get(),
 			_exact_in,
 			_min_out,
 		)
@@ -897,8 +5411,9 @@ impl<T: Config> pba_interface::DexInterface for Pallet<T> {
 	) -> DispatchResult {
 		Self::swap_in_for_exact_out(
 			frame_system::RawOrigin::Signed(_origin).into(),
-			_asset_in,
-			_asset_out,
+			_asset_in.into(),
+			_asset_out.into(),
+			T::DefaultFeeTier::get(),
 			_max_in,
 			_exact_out,
 		)
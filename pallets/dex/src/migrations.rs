@@ -0,0 +1,297 @@
+//! Storage migrations of the DEX pallet.
+
+use crate::{AssetBalanceOf, AssetIdOf, Config, Pallet, PoolPair, PoolStatus};
+use frame_support::{pallet_prelude::*, storage_alias};
+
+/// A pair as it was stored before `v1::MigrateToV1`, of assets of `pallet_assets` only.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+#[scale_info(skip_type_params(T))]
+pub struct PoolPairV0<T: Config> {
+	pub asset_1: AssetIdOf<T>,
+	pub amount_1: AssetBalanceOf<T>,
+	pub asset_2: AssetIdOf<T>,
+	pub amount_2: AssetBalanceOf<T>,
+}
+
+/// A pool as it was stored before `v1::MigrateToV1`, when every pool charged the fee in
+/// `FeeV0`.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+#[scale_info(skip_type_params(T))]
+pub struct PoolV0<T: Config> {
+	pub pool_pair: PoolPairV0<T>,
+	pub lp_supply: AssetBalanceOf<T>,
+}
+
+// `storage_alias` keys a storage item by the name of its alias, so the aliases of the old
+// layouts are declared under the names they were stored with in modules of their own.
+mod v0_storage {
+	use super::*;
+
+	#[storage_alias]
+	pub type PoolMap<T: Config> = StorageMap<Pallet<T>, Blake2_128Concat, AssetIdOf<T>, PoolV0<T>>;
+
+	#[storage_alias]
+	pub type Fee<T: Config> = StorageValue<Pallet<T>, u16>;
+}
+
+mod old_storage {
+	use super::*;
+
+	#[storage_alias]
+	pub type PoolMap<T: Config> = StorageMap<Pallet<T>, Blake2_128Concat, AssetIdOf<T>, OldPool<T>>;
+}
+
+/// `PoolMap` as it was stored before `v1::MigrateToV1`.
+pub use v0_storage::PoolMap as PoolMapV0;
+
+/// The fee of every pool before `v1::MigrateToV1`, in percent.
+pub use v0_storage::Fee as FeeV0;
+
+/// The fee pools charged before `v1::MigrateToV1` if it was never set.
+pub const DEFAULT_FEE_V0: u16 = 3;
+
+/// A pool as it was stored before `v3::MigrateToV3`, without a kind.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone)]
+#[scale_info(skip_type_params(T))]
+pub struct OldPool<T: Config> {
+	pub pool_pair: PoolPair<T>,
+	pub fee_tier: u16,
+	pub lp_supply: AssetBalanceOf<T>,
+	pub price_cumulative_1: u128,
+	pub price_cumulative_2: u128,
+	pub block_timestamp_last: u64,
+	pub k_last: AssetBalanceOf<T>,
+	pub status: PoolStatus,
+}
+
+/// `PoolMap` as it was stored before `v3::MigrateToV3`, which the earlier migrations read.
+pub use old_storage::PoolMap as OldPoolMap;
+
+/// Moves to allocated LP token ids, indexed by `PairToLp` and `LpToPair`.
+pub mod v1 {
+	use super::{FeeV0, OldPool, OldPoolMap as PoolMap, PoolMapV0, PoolV0, DEFAULT_FEE_V0};
+	use crate::{
+		Config, LpToPair, NativeOrAsset, PairToLp, Pallet, PoolCount, PoolPair, PoolStatus,
+	};
+	#[cfg(feature = "try-runtime")]
+	use frame_support::{dispatch::Vec, sp_runtime::TryRuntimeError};
+	use frame_support::{
+		pallet_prelude::*,
+		sp_runtime::traits::Zero,
+		traits::{DefensiveResult, GetStorageVersion, OnRuntimeUpgrade},
+	};
+
+	/// Stores the pools of the first release in the layout the later migrations read, and
+	/// indexes them, as they were created while LP token ids were hashes of their pair.
+	///
+	/// Every pool gets the fee all of them charged as its fee tier, in basis points, and starts
+	/// active with its price accumulators and `k_last` at zero. The pools keep their LP token
+	/// ids, as their LP tokens are already held. New pools get theirs from `LpAssetId`, skipping
+	/// any of these that is in its range.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() != 0 {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let fee_tier = <FeeV0<T>>::take().unwrap_or(DEFAULT_FEE_V0).saturating_mul(100);
+			let now = Pallet::<T>::now();
+			let mut pools = 0u64;
+			<PoolMapV0<T>>::translate::<PoolV0<T>, _>(|_, old| {
+				let pair = old.pool_pair;
+				// pairs were sorted by their encoded ids, which `Asset` keeps the order of
+				let pool_pair = PoolPair::<T>::new(
+					NativeOrAsset::Asset(pair.asset_1),
+					pair.amount_1,
+					NativeOrAsset::Asset(pair.asset_2),
+					pair.amount_2,
+				)
+				.defensive()
+				.ok()?;
+				Some(OldPool {
+					pool_pair,
+					fee_tier,
+					lp_supply: old.lp_supply,
+					price_cumulative_1: 0,
+					price_cumulative_2: 0,
+					block_timestamp_last: now,
+					k_last: Zero::zero(),
+					status: PoolStatus::Active,
+				})
+			});
+			for (lp_id, pool) in <PoolMap<T>>::iter() {
+				let pair_key = (pool.pool_pair.asset_1, pool.pool_pair.asset_2, pool.fee_tier);
+				<PairToLp<T>>::insert(&pair_key, &lp_id);
+				<LpToPair<T>>::insert(&lp_id, pair_key);
+				pools += 1;
+			}
+			<PoolCount<T>>::put(pools as u32);
+			StorageVersion::new(1).put::<Pallet<T>>();
+
+			T::DbWeight::get().reads_writes(pools * 2 + 3, pools * 3 + 3)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			Ok((<PoolMapV0<T>>::iter_keys().count() as u32).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let pools = u32::decode(&mut &state[..]).map_err(|_| "pool count doesn't decode")?;
+			ensure!(<PoolMap<T>>::iter().count() as u32 == pools, "every pool still decodes");
+			ensure!(<PoolCount<T>>::get() == pools, "`PoolCount` counts every pool");
+			ensure!(
+				<PairToLp<T>>::iter_keys().count() as u32 == pools,
+				"every pool is in `PairToLp`"
+			);
+			ensure!(
+				<PoolMap<T>>::iter_keys().all(|lp_id| <LpToPair<T>>::contains_key(lp_id)),
+				"every pool is in `LpToPair`"
+			);
+			ensure!(Pallet::<T>::on_chain_storage_version() == 1, "the storage version is 1");
+			Ok(())
+		}
+	}
+}
+
+/// Moves the reserves of every pool out of the DEX's account and into the pool's own account.
+pub mod v2 {
+	use super::OldPoolMap as PoolMap;
+	use crate::{Config, NativeOrAssetUnion, Pallet};
+	#[cfg(feature = "try-runtime")]
+	use frame_support::{dispatch::Vec, sp_runtime::TryRuntimeError};
+	use frame_support::{
+		pallet_prelude::*,
+		sp_runtime::traits::Zero,
+		traits::{
+			tokens::Preservation::Expendable, DefensiveResult, GetStorageVersion, OnRuntimeUpgrade,
+		},
+	};
+
+	/// Gives every pool a `pool_account` holding the reserves its record says it has.
+	///
+	/// Must run after `v1::MigrateToV1`. Anything else the DEX's account holds, like the LP
+	/// token metadata deposits, stays there.
+	pub struct MigrateToV2<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() != 1 {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let dex_account = Pallet::<T>::account_id();
+			let mut pools = 0u64;
+			for (lp_id, pool) in <PoolMap<T>>::iter() {
+				let pool_account = Pallet::<T>::pool_account(&lp_id);
+				frame_system::Pallet::<T>::inc_providers(&pool_account);
+				let pair = pool.pool_pair;
+				for (asset, amount) in
+					[(pair.asset_1, pair.amount_1), (pair.asset_2, pair.amount_2)]
+				{
+					if !amount.is_zero() {
+						let _ = NativeOrAssetUnion::<T>::transfer(
+							&asset,
+							&dex_account,
+							&pool_account,
+							amount,
+							Expendable,
+						)
+						.defensive();
+					}
+				}
+				pools += 1;
+			}
+			StorageVersion::new(2).put::<Pallet<T>>();
+
+			// the pool, and the balances of both accounts in both assets
+			T::DbWeight::get().reads_writes(pools * 5 + 1, pools * 5 + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			Ok((<PoolMap<T>>::iter_keys().count() as u32).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let pools = u32::decode(&mut &state[..]).map_err(|_| "pool count doesn't decode")?;
+			ensure!(<PoolMap<T>>::iter().count() as u32 == pools, "every pool still decodes");
+			for (lp_id, pool) in <PoolMap<T>>::iter() {
+				let pool_account = Pallet::<T>::pool_account(&lp_id);
+				let pair = pool.pool_pair;
+				ensure!(
+					NativeOrAssetUnion::<T>::balance(&pair.asset_1, &pool_account) >= pair.amount_1 &&
+						NativeOrAssetUnion::<T>::balance(&pair.asset_2, &pool_account) >=
+							pair.amount_2,
+					"every pool account holds the pool's reserves"
+				);
+			}
+			ensure!(Pallet::<T>::on_chain_storage_version() == 2, "the storage version is 2");
+			Ok(())
+		}
+	}
+}
+
+/// Gives every pool a kind, all of them being constant product pools until now.
+pub mod v3 {
+	use super::OldPool;
+	#[cfg(feature = "try-runtime")]
+	use crate::PoolCount;
+	use crate::{Config, Pallet, Pool, PoolKind, PoolMap};
+	#[cfg(feature = "try-runtime")]
+	use frame_support::{dispatch::Vec, sp_runtime::TryRuntimeError};
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{GetStorageVersion, OnRuntimeUpgrade},
+	};
+
+	/// Stores every pool in the layout with `Pool::kind`, as a constant product pool.
+	///
+	/// Must run after `v2::MigrateToV2`.
+	pub struct MigrateToV3<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV3<T> {
+		fn on_runtime_upgrade() -> Weight {
+			if Pallet::<T>::on_chain_storage_version() != 2 {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let mut pools = 0u64;
+			<PoolMap<T>>::translate::<OldPool<T>, _>(|_, old| {
+				pools += 1;
+				Some(Pool {
+					pool_pair: old.pool_pair,
+					fee_tier: old.fee_tier,
+					lp_supply: old.lp_supply,
+					price_cumulative_1: old.price_cumulative_1,
+					price_cumulative_2: old.price_cumulative_2,
+					block_timestamp_last: old.block_timestamp_last,
+					k_last: old.k_last,
+					status: old.status,
+					kind: PoolKind::ConstantProduct,
+				})
+			});
+			StorageVersion::new(3).put::<Pallet<T>>();
+
+			T::DbWeight::get().reads_writes(pools + 1, pools + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+			Ok((<PoolMap<T>>::iter_keys().count() as u32).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+			let pools = u32::decode(&mut &state[..]).map_err(|_| "pool count doesn't decode")?;
+			ensure!(<PoolMap<T>>::iter().count() as u32 == pools, "every pool still decodes");
+			ensure!(<PoolCount<T>>::get() == pools, "`PoolCount` counts every pool");
+			ensure!(Pallet::<T>::on_chain_storage_version() == 3, "the storage version is 3");
+			Ok(())
+		}
+	}
+}
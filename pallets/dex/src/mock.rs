@@ -1,14 +1,18 @@
-use crate as pallet_dex;
+use crate::{self as pallet_dex, NativeOrAsset, NativeOrAssetUnion};
 use frame_support::{
 	parameter_types,
-	traits::{AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64},
+	traits::{
+		tokens::Preservation, AsEnsureOriginWithArg, ConstU128, ConstU16, ConstU32, ConstU64,
+		ConstU8, Contains,
+	},
+	weights::Weight,
 	PalletId,
 };
 use frame_system::{EnsureRoot, EnsureSigned};
 use sp_core::H256;
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup},
-	BuildStorage,
+	traits::{BlakeTwo256, Convert, IdentityLookup},
+	BuildStorage, DispatchError, Permill,
 };
 
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -19,6 +23,7 @@ frame_support::construct_runtime!(
 	pub enum Test
 	{
 		System: frame_system,
+		Timestamp: pallet_timestamp,
 		Balances: pallet_balances,
 		Assets: pallet_assets,
 		Dex: pallet_dex,
@@ -51,6 +56,13 @@ impl frame_system::Config for Test {
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<1>;
+	type WeightInfo = ();
+}
+
 impl pallet_balances::Config for Test {
 	type Balance = Balance;
 	type DustRemoval = ();
@@ -61,7 +73,7 @@ impl pallet_balances::Config for Test {
 	type MaxLocks = ConstU32<10>;
 	type MaxReserves = ();
 	type ReserveIdentifier = [u8; 8];
-	type RuntimeHoldReason = ();
+	type RuntimeHoldReason = RuntimeHoldReason;
 	type FreezeIdentifier = ();
 	type MaxHolds = ConstU32<10>;
 	type MaxFreezes = ConstU32<10>;
@@ -77,11 +89,11 @@ impl pallet_assets::Config for Test {
 	type ForceOrigin = EnsureRoot<Self::AccountId>;
 	type AssetDeposit = ConstU128<100>;
 	type AssetAccountDeposit = ConstU128<1>;
-	type MetadataDepositBase = ConstU128<10>;
-	type MetadataDepositPerByte = ConstU128<1>;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
 	type ApprovalDeposit = ConstU128<1>;
 	type StringLimit = ConstU32<50>;
-	type Freezer = ();
+	type Freezer = StakedLp;
 	type Extra = ();
 	type CallbackHandle = ();
 	type WeightInfo = ();
@@ -90,16 +102,107 @@ impl pallet_assets::Config for Test {
 	type BenchmarkHelper = ();
 }
 
+// reports the LP tokens staked in the dex's farms as frozen
+pub struct StakedLp;
+impl pallet_assets::FrozenBalance<u32, u64, Balance> for StakedLp {
+	fn frozen_balance(asset: u32, who: &u64) -> Option<Balance> {
+		Dex::staked_balance(&asset, who)
+	}
+
+	fn died(_asset: u32, _who: &u64) {}
+}
+
+// refuses the system calls in flash loans, anything else may be borrowed for
+pub struct NoSystemCalls;
+impl Contains<RuntimeCall> for NoSystemCalls {
+	fn contains(call: &RuntimeCall) -> bool {
+		!matches!(call, RuntimeCall::System(_))
+	}
+}
+
+// pays flash loans taken without a call back with the fee from the borrower's own funds
+pub struct RepayingReceiver;
+impl pallet_dex::FlashLoanReceiver<u64, NativeOrAsset<u32>, Balance> for RepayingReceiver {
+	fn max_weight() -> Weight {
+		Weight::zero()
+	}
+
+	fn on_flash_loan(
+		borrower: &u64,
+		asset: &NativeOrAsset<u32>,
+		amount: Balance,
+		fee: Balance,
+	) -> Result<Weight, DispatchError> {
+		NativeOrAssetUnion::<Test>::transfer(
+			asset,
+			borrower,
+			&Dex::account_id(),
+			amount + fee,
+			Preservation::Expendable,
+		)?;
+		Ok(Weight::zero())
+	}
+}
+
+// the first asset id of LP tokens
+pub const FIRST_LP_ASSET_ID: u32 = 1_000;
+
+// hands out LP token ids from `FIRST_LP_ASSET_ID` on
+pub struct LpAssetIds;
+impl Convert<u32, u32> for LpAssetIds {
+	fn convert(index: u32) -> u32 {
+		FIRST_LP_ASSET_ID + index
+	}
+}
+
 parameter_types! {
 	pub const DexPalletId: PalletId = PalletId(*b"pba/dex0");
+	pub static RewardVestingPeriod: u64 = 0;
+	pub static PoolCreationDeposit: Balance = 0;
+	pub static MaxPools: u32 = 64;
+	pub static MaxPriceMove: Permill = Permill::zero();
+	pub static MaxPriceImpact: Permill = Permill::zero();
+	pub NativeSymbol: Vec<u8> = b"UNIT".to_vec();
+	pub static MetadataDepositBase: Balance = 0;
+	pub static MetadataDepositPerByte: Balance = 0;
 }
 
 impl pallet_dex::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type RuntimeHoldReason = RuntimeHoldReason;
 	type NativeBalance = Balances;
 	type Fungibles = Assets;
 	type PalletId = DexPalletId;
 	type PermissionOrigin = EnsureRoot<Self::AccountId>;
+	type MaxPathLength = ConstU32<4>;
+	type Timestamp = Timestamp;
+	type MaxObservations = ConstU32<4>;
+	type DefaultFeeTier = ConstU16<300>;
+	type MaxFeeTiers = ConstU32<8>;
+	type MinimumLiquidity = ConstU128<10>;
+	type RewardVestingPeriod = RewardVestingPeriod;
+	type NativeDecimals = ConstU8<12>;
+	type NativeSymbol = NativeSymbol;
+	type LpMinBalance = ConstU128<1>;
+	type LpAssetId = LpAssetIds;
+	type PoolCreationDeposit = PoolCreationDeposit;
+	type MaxPools = MaxPools;
+	type MaxStableAssets = ConstU32<4>;
+	type MaxAmplification = ConstU32<10_000>;
+	type MinAmplificationRamp = ConstU64<10>;
+	type AmplificationOrigin = EnsureRoot<Self::AccountId>;
+	type MaxWeightedAssets = ConstU32<4>;
+	type MaxTicks = ConstU32<16>;
+	type FlashLoanCallFilter = NoSystemCalls;
+	type PauseOrigin = EnsureRoot<Self::AccountId>;
+	type PriceMoveWindow = ConstU64<10>;
+	type MaxPriceMove = MaxPriceMove;
+	type MaxPriceImpact = MaxPriceImpact;
+	type FlashLoanReceiver = RepayingReceiver;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
 }
 
 // Build genesis storage according to the mock runtime.
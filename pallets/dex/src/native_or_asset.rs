@@ -0,0 +1,192 @@
+use crate::{AssetBalanceOf, AssetIdOf, AssetKindOf, Config};
+use frame_support::{
+	dispatch::Vec,
+	pallet_prelude::*,
+	traits::{
+		fungible,
+		fungibles::{self, metadata::Inspect as _},
+		tokens::{DepositConsequence, Fortitude, Precision, Preservation, Provenance},
+	},
+};
+use scale_info::prelude::format;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// An asset the DEX can trade, either the chain's native currency or an asset of
+/// `Config::Fungibles`.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum NativeOrAsset<AssetId> {
+	/// The native currency, held in `Config::NativeBalance`.
+	Native,
+	/// An asset of `Config::Fungibles`.
+	Asset(AssetId),
+}
+
+impl<AssetId> From<AssetId> for NativeOrAsset<AssetId> {
+	fn from(asset_id: AssetId) -> Self {
+		Self::Asset(asset_id)
+	}
+}
+
+/// Routes balance queries and transfers of a `NativeOrAsset` to `Config::NativeBalance` for the
+/// native currency and to `Config::Fungibles` for everything else, like `fungibles::UnionOf`.
+pub struct NativeOrAssetUnion<T>(PhantomData<T>);
+
+impl<T: Config> NativeOrAssetUnion<T> {
+	/// The total issuance of `asset`.
+	pub fn total_issuance(asset: &AssetKindOf<T>) -> AssetBalanceOf<T> {
+		match asset {
+			NativeOrAsset::Native =>
+				<T::NativeBalance as fungible::Inspect<T::AccountId>>::total_issuance(),
+			NativeOrAsset::Asset(id) =>
+				<T::Fungibles as fungibles::Inspect<T::AccountId>>::total_issuance(id.clone()),
+		}
+	}
+
+	/// The free balance of `asset` held by `who`.
+	pub fn balance(asset: &AssetKindOf<T>, who: &T::AccountId) -> AssetBalanceOf<T> {
+		match asset {
+			NativeOrAsset::Native =>
+				<T::NativeBalance as fungible::Inspect<T::AccountId>>::balance(who),
+			NativeOrAsset::Asset(id) =>
+				<T::Fungibles as fungibles::Inspect<T::AccountId>>::balance(id.clone(), who),
+		}
+	}
+
+	/// The balance of `asset` `who` can move out of its account.
+	pub fn reducible_balance(
+		asset: &AssetKindOf<T>,
+		who: &T::AccountId,
+		preservation: Preservation,
+		force: Fortitude,
+	) -> AssetBalanceOf<T> {
+		match asset {
+			NativeOrAsset::Native =>
+				<T::NativeBalance as fungible::Inspect<T::AccountId>>::reducible_balance(
+					who,
+					preservation,
+					force,
+				),
+			NativeOrAsset::Asset(id) =>
+				<T::Fungibles as fungibles::Inspect<T::AccountId>>::reducible_balance(
+					id.clone(),
+					who,
+					preservation,
+					force,
+				),
+		}
+	}
+
+	/// Whether `amount` of existing `asset` could be transferred into the account of `who`.
+	pub fn can_deposit(
+		asset: &AssetKindOf<T>,
+		who: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> DepositConsequence {
+		match asset {
+			NativeOrAsset::Native =>
+				<T::NativeBalance as fungible::Inspect<T::AccountId>>::can_deposit(
+					who,
+					amount,
+					Provenance::Extant,
+				),
+			NativeOrAsset::Asset(id) =>
+				<T::Fungibles as fungibles::Inspect<T::AccountId>>::can_deposit(
+					id.clone(),
+					who,
+					amount,
+					Provenance::Extant,
+				),
+		}
+	}
+
+	/// The decimals of `asset`, the native currency's are `Config::NativeDecimals`.
+	pub fn decimals(asset: &AssetKindOf<T>) -> u8 {
+		match asset {
+			NativeOrAsset::Native => T::NativeDecimals::get(),
+			NativeOrAsset::Asset(id) => T::Fungibles::decimals(id.clone()),
+		}
+	}
+
+	/// The symbol of `asset`, the native currency's is `Config::NativeSymbol`. An asset without a
+	/// symbol in its metadata goes by its id in decimal digits if the id is an unsigned integer,
+	/// as in `pallet_assets`, and has an empty symbol otherwise.
+	pub fn symbol(asset: &AssetKindOf<T>) -> Vec<u8> {
+		match asset {
+			NativeOrAsset::Native => T::NativeSymbol::get(),
+			NativeOrAsset::Asset(id) => {
+				let symbol = T::Fungibles::symbol(id.clone());
+				if symbol.is_empty() {
+					Self::id_digits(id)
+				} else {
+					symbol
+				}
+			},
+		}
+	}
+
+	// the decimal digits of `id` if it encodes like an unsigned integer of up to 128 bits,
+	// little endian in 1, 2, 4, 8 or 16 bytes, and nothing for ids of any other encoding
+	fn id_digits(id: &AssetIdOf<T>) -> Vec<u8> {
+		let encoded = id.encode();
+		if ![1, 2, 4, 8, 16].contains(&encoded.len()) {
+			return Vec::new()
+		}
+		let mut bytes = [0u8; 16];
+		bytes[..encoded.len()].copy_from_slice(&encoded);
+		format!("{}", u128::from_le_bytes(bytes)).into_bytes()
+	}
+
+	/// Transfers `amount` of `asset` from `source` to `dest`.
+	pub fn transfer(
+		asset: &AssetKindOf<T>,
+		source: &T::AccountId,
+		dest: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+		preservation: Preservation,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		match asset {
+			NativeOrAsset::Native =>
+				<T::NativeBalance as fungible::Mutate<T::AccountId>>::transfer(
+					source,
+					dest,
+					amount,
+					preservation,
+				),
+			NativeOrAsset::Asset(id) =>
+				<T::Fungibles as fungibles::Mutate<T::AccountId>>::transfer(
+					id.clone(),
+					source,
+					dest,
+					amount,
+					preservation,
+				),
+		}
+	}
+
+	/// Burns exactly `amount` of `asset` from `who`.
+	pub fn burn_from(
+		asset: &AssetKindOf<T>,
+		who: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> Result<AssetBalanceOf<T>, DispatchError> {
+		match asset {
+			NativeOrAsset::Native =>
+				<T::NativeBalance as fungible::Mutate<T::AccountId>>::burn_from(
+					who,
+					amount,
+					Precision::Exact,
+					Fortitude::Polite,
+				),
+			NativeOrAsset::Asset(id) =>
+				<T::Fungibles as fungibles::Mutate<T::AccountId>>::burn_from(
+					id.clone(),
+					who,
+					amount,
+					Precision::Exact,
+					Fortitude::Polite,
+				),
+		}
+	}
+}
@@ -0,0 +1,198 @@
+//! The StableSwap invariant of Curve, for pools of assets that should trade close to 1:1.
+//!
+//! With `n` assets of reserves `x_i` and an amplification coefficient `A`, the invariant `D` of a
+//! pool, the total of its reserves if they were balanced, satisfies
+//! `A * n^n * sum(x_i) + D = A * n^n * D + D^(n + 1) / (n^n * prod(x_i))`.
+//! The higher `A`, the longer the pool trades close to 1:1 before prices move like a constant
+//! product pool's. It is solved for `D`, or for one reserve given `D` and the others, with
+//! Newton's method as Curve does. Everything is worked out in `U256`, and `None` means the
+//! reserves are empty, the maths overflowed or it didn't converge.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+	dispatch::Vec,
+	sp_runtime::{
+		traits::AtLeast32BitUnsigned, FixedPointNumber, FixedU128, RuntimeDebug,
+		SaturatedConversion,
+	},
+};
+use scale_info::TypeInfo;
+use sp_core::U256;
+
+// Newton's method converges in a handful of rounds, this only stops it looping forever
+const MAX_ITERATIONS: usize = 255;
+
+/// The amplification coefficient of a StableSwap pool, moving linearly from `initial` at block
+/// `start` to `target` at block `end`, as governance ramps it.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub struct Amplification<BlockNumber> {
+	pub initial: u32,
+	pub target: u32,
+	pub start: BlockNumber,
+	pub end: BlockNumber,
+}
+
+impl<BlockNumber: AtLeast32BitUnsigned + Copy> Amplification<BlockNumber> {
+	/// An amplification coefficient that stays at `value`.
+	pub fn new(value: u32, now: BlockNumber) -> Self {
+		Self { initial: value, target: value, start: now, end: now }
+	}
+
+	/// The amplification coefficient at block `now`.
+	pub fn at(&self, now: BlockNumber) -> u32 {
+		if now >= self.end {
+			return self.target
+		}
+		if now <= self.start {
+			return self.initial
+		}
+		let elapsed = (now - self.start).saturated_into::<u64>();
+		let duration = (self.end - self.start).saturated_into::<u64>();
+		let progress = |from: u32, to: u32| {
+			(u64::from(to - from) * elapsed / duration).saturated_into::<u32>()
+		};
+		if self.target >= self.initial {
+			self.initial.saturating_add(progress(self.initial, self.target))
+		} else {
+			self.initial.saturating_sub(progress(self.target, self.initial))
+		}
+	}
+}
+
+// `A * n^n`, which the invariant is written in terms of
+fn ann(amplification: u32, n: usize) -> Option<U256> {
+	(0..n).try_fold(U256::from(amplification), |ann, _| ann.checked_mul(U256::from(n)))
+}
+
+// `D^(n + 1) / (n^n * prod(x_i))`, worked out one reserve at a time to keep it small
+fn d_product(reserves: &[U256], d: U256) -> Option<U256> {
+	let n = U256::from(reserves.len());
+	reserves
+		.iter()
+		.try_fold(d, |d_p, reserve| d_p.checked_mul(d)?.checked_div(reserve.checked_mul(n)?))
+}
+
+fn converged(a: U256, b: U256) -> bool {
+	let difference = if a > b { a - b } else { b - a };
+	difference <= U256::one()
+}
+
+/// The invariant `D` of `reserves`, which is zero for empty reserves.
+pub fn compute_d(reserves: &[U256], amplification: u32) -> Option<U256> {
+	let sum = reserves
+		.iter()
+		.try_fold(U256::zero(), |sum, reserve| sum.checked_add(*reserve))?;
+	if sum.is_zero() {
+		return Some(U256::zero())
+	}
+	let n = U256::from(reserves.len());
+	let ann = ann(amplification, reserves.len())?;
+
+	// `D = (A * n^n * S + n * D_P) * D / ((A * n^n - 1) * D + (n + 1) * D_P)`
+	let mut d = sum;
+	for _ in 0..MAX_ITERATIONS {
+		let d_p = d_product(reserves, d)?;
+		let numerator = ann.checked_mul(sum)?.checked_add(d_p.checked_mul(n)?)?.checked_mul(d)?;
+		let denominator = ann
+			.checked_sub(U256::one())?
+			.checked_mul(d)?
+			.checked_add(n.checked_add(U256::one())?.checked_mul(d_p)?)?;
+		let previous = d;
+		d = numerator.checked_div(denominator)?;
+		if converged(d, previous) {
+			return Some(d)
+		}
+	}
+	None
+}
+
+/// The reserve of the asset at `index` that keeps the invariant at `d`, given the other
+/// `reserves`. The reserve at `index` itself is ignored.
+pub fn compute_y(reserves: &[U256], index: usize, amplification: u32, d: U256) -> Option<U256> {
+	let n = U256::from(reserves.len());
+	let ann = ann(amplification, reserves.len())?;
+
+	// `y^2 + (b - D) * y = c`, with `b = S' + D / (A * n^n)` and
+	// `c = D^(n + 1) / (n^n * P' * A * n^n)` over the other reserves
+	let mut c = d;
+	let mut sum = U256::zero();
+	for (_, reserve) in reserves.iter().enumerate().filter(|(i, _)| *i != index) {
+		sum = sum.checked_add(*reserve)?;
+		c = c.checked_mul(d)?.checked_div(reserve.checked_mul(n)?)?;
+	}
+	c = c.checked_mul(d)?.checked_div(ann.checked_mul(n)?)?;
+	let b = sum.checked_add(d.checked_div(ann)?)?;
+
+	let mut y = d;
+	for _ in 0..MAX_ITERATIONS {
+		let previous = y;
+		y = y
+			.checked_mul(y)?
+			.checked_add(c)?
+			.checked_div(y.checked_mul(U256::from(2u32))?.checked_add(b)?.checked_sub(d)?)?;
+		if converged(y, previous) {
+			return Some(y)
+		}
+	}
+	None
+}
+
+/// The marginal price of the asset at `i` in the asset at `j`, how much of `j` an infinitely
+/// small amount of `i` is worth.
+///
+/// It is the ratio of the partial derivatives of the invariant,
+/// `(A * n^n * x_i + D_P) * x_j / ((A * n^n * x_j + D_P) * x_i)`.
+pub fn spot_price(reserves: &[U256], i: usize, j: usize, amplification: u32) -> Option<FixedU128> {
+	let d = compute_d(reserves, amplification)?;
+	let d_p = d_product(reserves, d)?;
+	let ann = ann(amplification, reserves.len())?;
+	let (x_i, x_j) = (*reserves.get(i)?, *reserves.get(j)?);
+
+	let numerator = ann.checked_mul(x_i)?.checked_add(d_p)?.checked_mul(x_j)?;
+	let denominator = ann.checked_mul(x_j)?.checked_add(d_p)?.checked_mul(x_i)?;
+	let price = numerator
+		.checked_mul(U256::from(FixedU128::accuracy()))?
+		.checked_div(denominator)?;
+	(price <= U256::from(u128::MAX)).then(|| FixedU128::from_inner(price.low_u128()))
+}
+
+/// The LP tokens minted for depositing into a pool with `supply` LP tokens, taking its reserves
+/// from `old_reserves` to `new_reserves`. The first deposit mints `D`, later ones the share of
+/// the growth of `D` they bring.
+///
+/// As Curve does, the part of a deposit that is off the pool's current ratio is charged the swap
+/// fee, scaled by `n / (4 * (n - 1))`. Otherwise depositing one asset and withdrawing all of them
+/// would be a swap without a fee. The fee stays in the pool.
+pub fn mint_amount(
+	old_reserves: &[U256],
+	new_reserves: &[U256],
+	supply: U256,
+	amplification: u32,
+	fee_tier: u16,
+) -> Option<U256> {
+	let d_0 = compute_d(old_reserves, amplification)?;
+	let d_1 = compute_d(new_reserves, amplification)?;
+	if d_1 <= d_0 {
+		return Some(U256::zero())
+	}
+	if supply.is_zero() {
+		return Some(d_1)
+	}
+
+	let n = U256::from(new_reserves.len());
+	let basis = U256::from(10_000u32);
+	let fee = U256::from(fee_tier).checked_mul(n)? /
+		n.checked_sub(U256::one())?.checked_mul(U256::from(4u32))?;
+	let charged = old_reserves
+		.iter()
+		.zip(new_reserves)
+		.map(|(old, new)| {
+			let ideal = d_1.checked_mul(*old)?.checked_div(d_0)?;
+			let difference = if ideal > *new { ideal - *new } else { *new - ideal };
+			new.checked_sub(fee.checked_mul(difference)? / basis)
+		})
+		.collect::<Option<Vec<_>>>()?;
+	let d_2 = compute_d(&charged, amplification)?;
+
+	Some(supply.checked_mul(d_2.saturating_sub(d_0))? / d_0)
+}
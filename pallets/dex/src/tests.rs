@@ -1,9 +1,28 @@
 use crate::{
+	migrations,
 	mock::{self, *},
-	Error, Event,
+	Amplification, Call as DexCall, CheckDexDeadline, Error, Event, Farms, FeeTiers, FlashLoanLock,
+	HoldReason, LpToPair,
+	NativeOrAsset::{self, Asset, Native},
+	NativeOrAssetUnion, NextPositionId, Observations, PoolCount, PoolInfo, PoolKind, PoolMap,
+	PoolPair, PoolStatus, Positions, PriceWindows, Quote, RewardVesting, Stakes,
+};
+use frame_support::{
+	assert_err, assert_noop, assert_ok,
+	dispatch::DispatchInfo,
+	traits::{
+		fungible, fungibles::Inspect, ConstU32, GetStorageVersion, OnRuntimeUpgrade, StorageVersion,
+	},
+	BoundedVec,
+};
+use sp_core::U256;
+use sp_runtime::{
+	traits::SignedExtension, transaction_validity::InvalidTransaction, DispatchError,
+	FixedPointNumber, FixedU128, Perbill, Permill,
 };
-use frame_support::{assert_err, assert_noop, assert_ok, traits::fungibles::Inspect};
-use sp_runtime::Percent;
+
+// the swap fee of every pool in these tests unless stated otherwise, 3%
+const FEE_TIER: u16 = 300;
 
 #[test]
 fn simple_add_remove_liquidity() {
@@ -17,21 +36,52 @@ fn simple_add_remove_liquidity() {
 		assert_eq!(Assets::total_balance(2, &1), 1000);
 
 		// deposit 500 of each asset
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), 1, 2, 500, 500));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
 
-		// Lp issued sqr(500*500) = 500
+		// Lp issued sqr(500*500) = 500, of which the minimum liquidity of 10 is locked
 		System::assert_last_event(mock::RuntimeEvent::Dex(Event::LiquidityAdded {
-			asset_a: 1,
-			asset_b: 2,
+			asset_a: Asset(1),
+			asset_b: Asset(2),
+			fee_tier: FEE_TIER,
 			amount_a: 500,
 			amount_b: 500,
-			amount_lp: 500,
+			amount_lp: 490,
 		}));
 		assert_eq!(Assets::total_balance(1, &1), 500);
 		assert_eq!(Assets::total_balance(2, &1), 500);
-		assert_eq!(Assets::total_balance(Dex::get_lp_id(&1, &2).unwrap(), &1), 500);
+		assert_eq!(
+			Assets::total_balance(Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap(), &1),
+			490
+		);
 
-		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), 1, 2, 500));
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			490,
+			0,
+			0
+		));
+	});
+}
+
+#[test]
+fn interface_mints_assets() {
+	new_test_ext().execute_with(|| {
+		use pba_interface::DexInterface;
+		assert_ok!(<Dex as DexInterface>::mint_asset(1, 7, 500));
+		assert_ok!(<Dex as DexInterface>::mint_asset(1, 7, 250));
+		assert_eq!(<Dex as DexInterface>::asset_balance(1, 7), 750);
 	});
 }
 
@@ -44,34 +94,70 @@ fn simple_swap_withdraw() {
 		// setting up account 1 with 1000 of asset type 1 and 2
 		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
 		// deposit 500 of each asset
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), 1, 2, 500, 500));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
 
-		// Lp issued sqr(500*500) = 500
+		// Lp issued sqr(500*500) = 500, less the minimum liquidity of 10
 		assert_eq!(Assets::total_balance(2, &1), 500);
-		assert_eq!(Assets::total_balance(Dex::get_lp_id(&1, &2).unwrap(), &1), 500);
+		assert_eq!(
+			Assets::total_balance(Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap(), &1),
+			490
+		);
 
 		// create a new account to swap
 		assert_ok!(Dex::setup_account(2, vec![(1, 1000)]));
 
 		// swap for 100 of asset 2
 		// should be 103 input
-		assert_ok!(Dex::swap_in_for_exact_out(RuntimeOrigin::signed(2), 1, 2, 150, 100));
+		assert_ok!(Dex::swap_in_for_exact_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			150,
+			100
+		));
 
 		// account two should have 100 of asset 2
 		assert_eq!(Assets::total_balance(2, &2), 100);
-		// dex should have 629 of asset 1 and 400 of asset 2
-		assert_eq!(Assets::total_balance(1, &Dex::account_id()), 629);
-		assert_eq!(Assets::total_balance(2, &&Dex::account_id()), 400);
+		// the pool's account should have 629 of asset 1 and 400 of asset 2
+		let pool_account =
+			Dex::pool_account(&Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap());
+		assert_eq!(Assets::total_balance(1, &pool_account), 629);
+		assert_eq!(Assets::total_balance(2, &pool_account), 400);
 
 		// should fail because the max input they are providing is too low
 		assert_noop!(
-			Dex::swap_in_for_exact_out(RuntimeOrigin::signed(2), 1, 2, 20, 100),
+			Dex::swap_in_for_exact_out(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				20,
+				100
+			),
 			Error::<Test>::SlippageTooHigh
 		);
 
 		// withdraw liquidity from account 1 should get more total tokens due to the fee paid by
 		// account 2
-		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), 1, 2, 500));
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			490,
+			0,
+			0
+		));
 
 		let account1_net_worth = Assets::total_balance(1, &1) + Assets::total_balance(2, &1);
 		assert!(account1_net_worth > 1000);
@@ -91,39 +177,187 @@ fn rewards_check() {
 		assert_ok!(Dex::setup_account(3, vec![(1, 10_000), (2, 10_000)]));
 
 		// account 1 and 2 add liquidity
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), 1, 2, 500, 500));
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(2), 1, 2, 500, 500));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
 
 		// account 3 does a bunch of swaps to increase the rewards
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 1, 2, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 2, 1, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 1, 2, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 2, 1, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 1, 2, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 2, 1, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 1, 2, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 2, 1, 150, 0));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			150,
+			0
+		));
 
 		//there should be more liquidity in the pool
-		let pool_total = Assets::total_balance(1, &Dex::account_id()) +
-			Assets::total_balance(2, &Dex::account_id());
+		let pool_account =
+			Dex::pool_account(&Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap());
+		let pool_total =
+			Assets::total_balance(1, &pool_account) + Assets::total_balance(2, &pool_account);
 		assert!(pool_total > 1000);
 
 		// account 2 removes liquidity
-		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(2), 1, 2, 500));
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			0,
+			0
+		));
 
 		// account 3 does a bunch of swaps to increase the rewards
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 1, 2, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 2, 1, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 1, 2, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 2, 1, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 1, 2, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 2, 1, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 1, 2, 150, 0));
-		assert_ok!(Dex::swap_exact_in_for_out(RuntimeOrigin::signed(3), 2, 1, 150, 0));
-
-		// account 1 removes liquidity
-		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), 1, 2, 500));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			150,
+			0
+		));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			150,
+			0
+		));
+
+		// account 1 removes liquidity, less the minimum liquidity it locked when creating the pool
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			490,
+			0,
+			0
+		));
 
 		// account 1 should have more rewards than account 2
 		let account1_rewards = Assets::total_balance(1, &1) + Assets::total_balance(2, &1);
@@ -143,7 +377,16 @@ fn add_pool_same_asset_fail() {
 
 		// can't create pool with the same asset
 		assert_err!(
-			Dex::add_liquidity(RuntimeOrigin::signed(1), 1, 1, 500, 500),
+			Dex::add_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(1),
+				FEE_TIER,
+				500,
+				500,
+				0,
+				0
+			),
 			Error::<Test>::SameAsset
 		);
 	});
@@ -156,20 +399,59 @@ fn parameters_order_no_diff() {
 		System::set_block_number(1);
 
 		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), 1, 2, 500, 500));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
 
 		// should work even though the parameters are in a different order
-		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), 2, 1, 500));
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			490,
+			0,
+			0
+		));
 
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), 1, 2, 500, 500));
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), 2, 1, 500, 500));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
 
 		// should have added liquidity to the same pool
-		let account_1_lp = Assets::total_balance(Dex::get_lp_id(&1, &2).unwrap(), &1);
+		let account_1_lp =
+			Assets::total_balance(Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap(), &1);
 		assert_eq!(account_1_lp, 1000);
 
 		// should both represent the same lp token
-		assert_eq!(Dex::get_lp_id(&1, &2).unwrap(), Dex::get_lp_id(&2, &1).unwrap());
+		assert_eq!(
+			Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap(),
+			Dex::get_lp_id(&Asset(2), &Asset(1), FEE_TIER).unwrap()
+		);
 	});
 }
 
@@ -180,6 +462,113 @@ fn changing_fee() {
 		System::set_block_number(1);
 
 		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+
+		// the default tier is allowed alongside the standard ones
+		assert_eq!(FeeTiers::<Test>::get().into_inner(), vec![5, 30, 100, FEE_TIER]);
+
+		let fee_tiers: BoundedVec<u16, ConstU32<8>> = vec![30, 100].try_into().unwrap();
+		assert_noop!(
+			Dex::set_fee_tiers(RuntimeOrigin::signed(1), fee_tiers.clone()),
+			Error::<Test>::NotAllowedToSetFee
+		);
+		assert_noop!(
+			Dex::set_fee_tiers(RuntimeOrigin::root(), vec![30, 10_000].try_into().unwrap()),
+			Error::<Test>::InvalidFeeTier
+		);
+		assert_ok!(Dex::set_fee_tiers(RuntimeOrigin::root(), fee_tiers.clone()));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::FeeTiersUpdated { fee_tiers }));
+
+		// new pools can only use an allowed tier
+		assert_noop!(
+			Dex::add_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				500,
+				500,
+				0,
+				0
+			),
+			Error::<Test>::InvalidFeeTier
+		);
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			30,
+			500,
+			500,
+			0,
+			0
+		));
+	});
+}
+
+#[test]
+fn fee_tiers_are_separate_pools() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000)]));
+
+		// the same pair once per tier, each with its own LP token
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			30,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ne!(
+			Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap(),
+			Dex::get_lp_id(&Asset(1), &Asset(2), 30).unwrap()
+		);
+		assert_eq!(Dex::pools().len(), 2);
+
+		// 100 in pays a fee of 1 in the 0.3% pool and of 3 in the 3% pool
+		assert_eq!(
+			Dex::quote_exact_in(Asset(1), Asset(2), 30, 100)
+				.map(|quote| (quote.amount_out, quote.fee)),
+			Some((83, 1))
+		);
+		assert_eq!(
+			Dex::quote_exact_in(Asset(1), Asset(2), FEE_TIER, 100)
+				.map(|quote| (quote.amount_out, quote.fee)),
+			Some((82, 3))
+		);
+
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			30,
+			100,
+			83
+		));
+		assert_eq!(Assets::total_balance(2, &2), 83);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), 30), Some((600, 417)));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((500, 500)));
+
+		// a tier without a pool has no pool
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), 5), None);
+		assert_noop!(
+			Dex::swap_exact_in_for_out(RuntimeOrigin::signed(2), Asset(1), Asset(2), 5, 100, 0),
+			Error::<Test>::NoneValue
+		);
 	});
 }
 
@@ -191,18 +580,53 @@ fn empty_pool_fail() {
 
 		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
 
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), 1, 2, 500, 500));
-		assert_ok!(Dex::remove_liquidity(RuntimeOrigin::signed(1), 1, 2, 500));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			490,
+			0,
+			0
+		));
 
-		// can't remove liquidity from an empty pool
+		// can't remove liquidity once all of it is withdrawn
 		assert_err!(
-			Dex::remove_liquidity(RuntimeOrigin::signed(1), 1, 2, 500),
+			Dex::remove_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				500,
+				0,
+				0
+			),
 			Error::<Test>::InsufficientLPBalance
 		);
 
-		// can't swap from an empty pool
+		// the locked minimum liquidity keeps the pool from emptying completely
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((10, 10)));
+
+		// can't swap from a pool that doesn't exist
 		assert_err!(
-			Dex::swap_exact_in_for_out(RuntimeOrigin::signed(1), 1, 2, 500, 0),
+			Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(3),
+				FEE_TIER,
+				500,
+				0
+			),
 			Error::<Test>::NoneValue
 		);
 	});
@@ -214,29 +638,3349 @@ fn test_ratio() {
 		System::set_block_number(1);
 		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
 
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), 1, 2, 500, 500));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
 
 		// should be 1:1 ratio
-		let rate11 = Percent::from_rational(1u16, 1u16);
-		assert_ok!(Dex::price_oracle(RuntimeOrigin::signed(1), 1, 2));
+		assert_ok!(Dex::price_oracle(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER));
 		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PriceOracleEvent {
-			asset_in: 1,
-			asset_out: 2,
-			rate: rate11,
+			asset_in: Asset(1),
+			asset_out: Asset(2),
+			rate: FixedU128::one(),
+			inverse_rate: FixedU128::one(),
+			fee_tier: FEE_TIER,
 		}));
 
-		assert_ok!(Dex::add_liquidity(RuntimeOrigin::signed(1), 1, 2, 500, 0));
-		assert_ok!(Dex::price_oracle(RuntimeOrigin::signed(1), 1, 2));
+		// both directions are reported, including the one where the price is above 1
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			30,
+			500,
+			250,
+			0,
+			0
+		));
+		assert_ok!(Dex::price_oracle(RuntimeOrigin::signed(1), Asset(1), Asset(2), 30));
 		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PriceOracleEvent {
-			asset_in: 1,
-			asset_out: 2,
-			rate: Percent::from_rational(1u16, 2u16),
+			asset_in: Asset(1),
+			asset_out: Asset(2),
+			rate: FixedU128::from_rational(1, 2),
+			inverse_rate: FixedU128::from_u32(2),
+			fee_tier: 30,
 		}));
-		assert_ok!(Dex::price_oracle(RuntimeOrigin::signed(1), 2, 1));
+		assert_ok!(Dex::price_oracle(RuntimeOrigin::signed(1), Asset(2), Asset(1), 30));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PriceOracleEvent {
+			asset_in: Asset(2),
+			asset_out: Asset(1),
+			rate: FixedU128::from_u32(2),
+			inverse_rate: FixedU128::from_rational(1, 2),
+			fee_tier: 30,
+		}));
+	});
+}
+
+#[test]
+fn price_oracle_uses_asset_decimals() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+
+		// asset 1 has 12 decimals and asset 2 has 6, so 500 raw units of asset 1 are worth a
+		// million times less than 500 raw units of asset 2
+		assert_ok!(Assets::force_set_metadata(
+			RuntimeOrigin::root(),
+			1.into(),
+			b"One".to_vec(),
+			b"ONE".to_vec(),
+			12,
+			false
+		));
+		assert_ok!(Assets::force_set_metadata(
+			RuntimeOrigin::root(),
+			2.into(),
+			b"Two".to_vec(),
+			b"TWO".to_vec(),
+			6,
+			false
+		));
+
+		assert_eq!(
+			Dex::spot_price(&Asset(1), &Asset(2), FEE_TIER),
+			Ok((FixedU128::from_u32(1_000_000), FixedU128::from_rational(1, 1_000_000)))
+		);
+		assert_ok!(Dex::price_oracle(RuntimeOrigin::signed(1), Asset(2), Asset(1), FEE_TIER));
 		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PriceOracleEvent {
-			asset_in: 2,
-			asset_out: 1,
-			rate: Percent::from_rational(2u16, 1u16),
+			asset_in: Asset(2),
+			asset_out: Asset(1),
+			rate: FixedU128::from_rational(1, 1_000_000),
+			inverse_rate: FixedU128::from_u32(1_000_000),
+			fee_tier: FEE_TIER,
+		}));
+	});
+}
+
+#[test]
+fn multi_hop_swap_exact_in() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 2000), (3, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(2),
+			Asset(3),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000)]));
+
+		// 100 of asset 1 less the 3% fee buys 82 of asset 2 in the first pool, and 82 of asset 2
+		// less the fee buys 69 of asset 3 in the second pool
+		let path: BoundedVec<NativeOrAsset<u32>, ConstU32<4>> =
+			vec![Asset(1), Asset(2), Asset(3)].try_into().unwrap();
+		let fee_tiers: BoundedVec<u16, ConstU32<4>> = vec![FEE_TIER, FEE_TIER].try_into().unwrap();
+		assert_ok!(Dex::swap_exact_in_along_path(
+			RuntimeOrigin::signed(2),
+			path.clone(),
+			fee_tiers.clone(),
+			100,
+			0
+		));
+
+		System::assert_has_event(mock::RuntimeEvent::Dex(Event::SwapEvent {
+			asset_in: Asset(1),
+			asset_out: Asset(2),
+			fee_tier: FEE_TIER,
+			amount_in: 100,
+			amount_out: 82,
+		}));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PathSwapEvent {
+			path: path.clone(),
+			fee_tiers: fee_tiers.clone(),
+			amount_in: 100,
+			amount_out: 69,
+		}));
+
+		// only the ends of the path move in and out of the caller's account
+		assert_eq!(Assets::total_balance(1, &2), 900);
+		assert_eq!(Assets::total_balance(2, &2), 0);
+		assert_eq!(Assets::total_balance(3, &2), 69);
+
+		// slippage is only checked on the final output
+		assert_noop!(
+			Dex::swap_exact_in_along_path(RuntimeOrigin::signed(2), path, fee_tiers, 100, 1000),
+			Error::<Test>::SlippageTooHigh
+		);
+	});
+}
+
+#[test]
+fn multi_hop_swap_exact_out() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 2000), (3, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(2),
+			Asset(3),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000)]));
+
+		let path: BoundedVec<NativeOrAsset<u32>, ConstU32<4>> =
+			vec![Asset(1), Asset(2), Asset(3)].try_into().unwrap();
+		let fee_tiers: BoundedVec<u16, ConstU32<4>> = vec![FEE_TIER, FEE_TIER].try_into().unwrap();
+		assert_noop!(
+			Dex::swap_in_for_exact_out_along_path(
+				RuntimeOrigin::signed(2),
+				path.clone(),
+				fee_tiers.clone(),
+				50,
+				100
+			),
+			Error::<Test>::SlippageTooHigh
+		);
+		assert_ok!(Dex::swap_in_for_exact_out_along_path(
+			RuntimeOrigin::signed(2),
+			path,
+			fee_tiers,
+			500,
+			100
+		));
+
+		assert_eq!(Assets::total_balance(3, &2), 100);
+		assert_eq!(Assets::total_balance(2, &2), 0);
+		assert!(Assets::total_balance(1, &2) < 900);
+	});
+}
+
+#[test]
+fn multi_hop_invalid_path_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		let fee_tiers: BoundedVec<u16, ConstU32<4>> = vec![FEE_TIER, FEE_TIER].try_into().unwrap();
+
+		// a single asset is not a path
+		let path: BoundedVec<NativeOrAsset<u32>, ConstU32<4>> = vec![Asset(1)].try_into().unwrap();
+		assert_noop!(
+			Dex::swap_exact_in_along_path(
+				RuntimeOrigin::signed(1),
+				path,
+				Default::default(),
+				100,
+				0
+			),
+			Error::<Test>::InvalidPath
+		);
+
+		// every hop needs exactly one fee tier
+		let path: BoundedVec<NativeOrAsset<u32>, ConstU32<4>> =
+			vec![Asset(1), Asset(2)].try_into().unwrap();
+		assert_noop!(
+			Dex::swap_exact_in_along_path(
+				RuntimeOrigin::signed(1),
+				path,
+				fee_tiers.clone(),
+				100,
+				0
+			),
+			Error::<Test>::InvalidPath
+		);
+
+		// going back through the same pool is not allowed
+		let path: BoundedVec<NativeOrAsset<u32>, ConstU32<4>> =
+			vec![Asset(1), Asset(2), Asset(1)].try_into().unwrap();
+		assert_noop!(
+			Dex::swap_exact_in_along_path(
+				RuntimeOrigin::signed(1),
+				path,
+				fee_tiers.clone(),
+				100,
+				0
+			),
+			Error::<Test>::InvalidPath
+		);
+
+		// every hop needs a pool
+		let path: BoundedVec<NativeOrAsset<u32>, ConstU32<4>> =
+			vec![Asset(1), Asset(2), Asset(3)].try_into().unwrap();
+		assert_noop!(
+			Dex::swap_exact_in_along_path(RuntimeOrigin::signed(1), path, fee_tiers, 100, 0),
+			Error::<Test>::NoPool
+		);
+	});
+}
+
+#[test]
+fn quotes_match_swaps() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+
+		// 100 in pays a fee of 3, and 97 at the spot price would have bought 97
+		assert_eq!(
+			Dex::quote_exact_in(Asset(1), Asset(2), FEE_TIER, 100),
+			Some(Quote {
+				amount_in: 100,
+				amount_out: 82,
+				fee: 3,
+				price_impact: Permill::from_rational(15u32, 97u32)
+			})
+		);
+
+		// 100 out needs 125 before the fee of 4, and 100 at the spot price
+		assert_eq!(
+			Dex::quote_exact_out(Asset(1), Asset(2), FEE_TIER, 100),
+			Some(Quote {
+				amount_in: 129,
+				amount_out: 100,
+				fee: 4,
+				price_impact: Permill::from_percent(20)
+			})
+		);
+
+		// quotes are read only
+		assert_eq!(Dex::get_reserves(Asset(2), Asset(1), FEE_TIER), Some((500, 500)));
+		assert_ok!(Dex::swap_in_for_exact_out(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			129,
+			100
+		));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((629, 400)));
+
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		let info = PoolInfo {
+			lp_id,
+			asset_1: Asset(1),
+			amount_1: 629,
+			asset_2: Asset(2),
+			amount_2: 400,
+			fee_tier: FEE_TIER,
+			lp_supply: 500,
+		};
+		assert_eq!(Dex::pool_info(Asset(2), Asset(1), FEE_TIER), Some(info.clone()));
+		assert_eq!(Dex::pools(), vec![info]);
+
+		// no pool, no quote
+		assert_eq!(Dex::quote_exact_in(Asset(1), Asset(3), FEE_TIER, 100), None);
+		assert_eq!(Dex::pool_info(Asset(1), Asset(3), FEE_TIER), None);
+	});
+}
+
+#[test]
+fn twap_ignores_same_block_price_moves() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		Timestamp::set_timestamp(6_000);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_ok!(Dex::setup_account(2, vec![(1, 10_000), (2, 10_000)]));
+
+		// 12 seconds later a swap moves the pool from 500:500 to 600:418
+		Timestamp::set_timestamp(18_000);
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			100,
+			0
+		));
+
+		// 12 seconds at 1:1 followed by 12 seconds at 418/600
+		Timestamp::set_timestamp(30_000);
+		let spot = FixedU128::checked_from_rational(418u128, 600u128).unwrap();
+		let expected =
+			FixedU128::from_inner((FixedU128::one().into_inner() + spot.into_inner()) * 12 / 24);
+		assert_eq!(Dex::consult_twap(&Asset(1), &Asset(2), FEE_TIER, 24), Ok(expected));
+
+		// a large swap in the current block moves the spot price but not the average
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			5_000,
+			0
+		));
+		assert_eq!(Dex::consult_twap(&Asset(1), &Asset(2), FEE_TIER, 24), Ok(expected));
+
+		assert_ok!(Dex::twap_oracle(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER, 24));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::TwapOracleEvent {
+			rate: expected,
+			asset_in: Asset(1),
+			asset_out: Asset(2),
+			fee_tier: FEE_TIER,
+			window: 24,
+		}));
+	});
+}
+
+#[test]
+fn twap_window_must_be_covered() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		Timestamp::set_timestamp(6_000);
+		assert_ok!(Dex::setup_account(1, vec![(1, 10_000), (2, 10_000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+
+		assert_noop!(
+			Dex::twap_oracle(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER, 0),
+			Error::<Test>::InvalidTwapWindow
+		);
+		assert_noop!(
+			Dex::twap_oracle(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER, 6),
+			Error::<Test>::NoObservationForWindow
+		);
+
+		// only the first touch of a block records an observation
+		for block in 2..8u64 {
+			Timestamp::set_timestamp(block * 6_000);
+			assert_ok!(Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				10,
+				0
+			));
+			assert_ok!(Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(1),
+				Asset(2),
+				Asset(1),
+				FEE_TIER,
+				10,
+				0
+			));
+		}
+
+		// the ring buffer only keeps the 4 most recent blocks, from 24 to 42 seconds
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		let mut timestamps: Vec<u64> =
+			Observations::<Test>::get(lp_id).iter().map(|o| o.timestamp).collect();
+		timestamps.sort();
+		assert_eq!(timestamps, vec![24, 30, 36, 42]);
+		assert_ok!(Dex::consult_twap(&Asset(1), &Asset(2), FEE_TIER, 18));
+		assert_noop!(
+			Dex::consult_twap(&Asset(1), &Asset(2), FEE_TIER, 19),
+			Error::<Test>::NoObservationForWindow
+		);
+	});
+}
+
+#[test]
+fn protocol_fee_is_off_by_default() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 10_000), (2, 10_000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 100_000), (2, 100_000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			10_000,
+			10_000,
+			0,
+			0
+		));
+
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().k_last, 0);
+
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			3000,
+			0
+		));
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			5000,
+			0,
+			0
+		));
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().lp_supply, 5000);
+	});
+}
+
+#[test]
+fn protocol_fee_mints_share_of_growth() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 10_000), (2, 10_000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 100_000), (2, 100_000)]));
+
+		// only the permission origin can turn the fee on
+		assert_noop!(
+			Dex::set_protocol_fee(RuntimeOrigin::signed(1), Some(9), Perbill::from_percent(20)),
+			Error::<Test>::NotAllowedToSetFee
+		);
+		assert_ok!(Dex::set_protocol_fee(
+			RuntimeOrigin::root(),
+			Some(9),
+			Perbill::from_percent(20)
+		));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::ProtocolFeeUpdated {
+			recipient: Some(9),
+			share: Perbill::from_percent(20),
+		}));
+
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			10_000,
+			10_000,
+			0,
+			0
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().k_last, 100_000_000);
+
+		// swaps grow the reserves to 9063:11724 without minting anything yet
+		for _ in 0..4 {
+			assert_ok!(Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				3000,
+				0
+			));
+			assert_ok!(Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(2),
+				Asset(2),
+				Asset(1),
+				FEE_TIER,
+				3000,
+				0
+			));
+		}
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((9063, 11724)));
+		assert_eq!(Assets::total_balance(lp_id, &9), 0);
+
+		// sqrt(k) grew from 10000 to 10307, 20% of that growth is worth 59 LP tokens
+		// 10_000 * 20% * 307 / (80% * 10307 + 20% * 10000) = 614000 / 10245
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			5000,
+			0,
+			0
+		));
+		System::assert_has_event(mock::RuntimeEvent::Dex(Event::ProtocolFeeMinted {
+			lp_id,
+			recipient: 9,
+			amount: 59,
+		}));
+		assert_eq!(Assets::total_balance(lp_id, &9), 59);
+
+		let pool = PoolMap::<Test>::get(lp_id).unwrap();
+		assert_eq!(pool.lp_supply, 5059);
+		assert_eq!(pool.k_last, pool.pool_pair.amount_1 * pool.pool_pair.amount_2);
+
+		// turning the fee off stops minting and clears `k_last` on the next liquidity change
+		assert_ok!(Dex::set_protocol_fee(RuntimeOrigin::root(), None, Perbill::from_percent(20)));
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			0,
+			0
+		));
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().k_last, 0);
+		assert_eq!(Assets::total_balance(lp_id, &9), 59);
+	});
+}
+
+#[test]
+fn first_deposit_locks_minimum_liquidity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		assert_eq!(Assets::total_balance(lp_id, &Dex::locked_liquidity_account()), 10);
+		assert_eq!(Assets::total_balance(lp_id, &1), 490);
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().lp_supply, 500);
+
+		// withdrawing everything else leaves the locked share of the pool behind
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			490,
+			0,
+			0
+		));
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().lp_supply, 10);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((10, 10)));
+
+		// and the pool can be refilled on top of it
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_eq!(Assets::total_balance(lp_id, &1), 500);
+	});
+}
+
+#[test]
+fn first_depositor_inflation_attack_fails() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 10_000), (2, 10_000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (2, 1000)]));
+
+		// the attack starts by opening the pool with a dust supply of LP tokens
+		assert_noop!(
+			Dex::add_liquidity(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER, 1, 1, 0, 0),
+			Error::<Test>::InsufficientLiquidityMinted
+		);
+		assert_noop!(
+			Dex::add_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				10,
+				10,
+				0,
+				0
+			),
+			Error::<Test>::InsufficientLiquidityMinted
+		);
+
+		// the smallest pool possible leaves the attacker with 1 of its 11 LP tokens
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			11,
+			11,
+			0,
+			0
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		assert_eq!(Assets::total_balance(lp_id, &1), 1);
+
+		// donating to the pool's account doesn't move its reserves
+		let pool_account = Dex::pool_account(&lp_id);
+		assert_ok!(Assets::transfer(RuntimeOrigin::signed(1), 1.into(), pool_account, 5_000));
+		assert_ok!(Assets::transfer(RuntimeOrigin::signed(1), 2.into(), pool_account, 5_000));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((11, 11)));
+
+		// so the victim's deposit doesn't round down and can be withdrawn in full
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_eq!(Assets::total_balance(lp_id, &2), 500);
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			0,
+			0
+		));
+		assert_eq!(Assets::total_balance(1, &2), 1000);
+		assert_eq!(Assets::total_balance(2, &2), 1000);
+	});
+}
+
+#[test]
+fn add_liquidity_deposits_at_pool_ratio() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			250,
+			0,
+			0
+		));
+
+		// 100 of asset 1 is worth 50 of asset 2, below the 80 the caller was willing to pay
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			100,
+			80,
+			0,
+			0
+		));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::LiquidityAdded {
+			asset_a: Asset(1),
+			asset_b: Asset(2),
+			fee_tier: FEE_TIER,
+			amount_a: 100,
+			amount_b: 50,
+			amount_lp: 71,
+		}));
+		assert_eq!(Assets::total_balance(1, &2), 900);
+		assert_eq!(Assets::total_balance(2, &2), 950);
+
+		// 40 of asset 2 is only worth 80 of asset 1, so that is all that is taken
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(2),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			40,
+			100,
+			0,
+			0
+		));
+		assert_eq!(Assets::total_balance(1, &2), 820);
+		assert_eq!(Assets::total_balance(2, &2), 910);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((680, 340)));
+
+		// the amount actually deposited must not fall below the minimums
+		assert_noop!(
+			Dex::add_liquidity(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				100,
+				80,
+				0,
+				60
+			),
+			Error::<Test>::InsufficientAmountB
+		);
+		assert_noop!(
+			Dex::add_liquidity(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				100,
+				40,
+				90,
+				0
+			),
+			Error::<Test>::InsufficientAmountA
+		);
+	});
+}
+
+#[test]
+fn remove_liquidity_checks_minimums() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			400,
+			100,
+			0,
+			0
+		));
+
+		// 100 of the 200 LP tokens are worth 200 of asset 1 and 50 of asset 2
+		assert_noop!(
+			Dex::remove_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(2),
+				Asset(1),
+				FEE_TIER,
+				100,
+				51,
+				0
+			),
+			Error::<Test>::InsufficientAmountA
+		);
+		assert_noop!(
+			Dex::remove_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(2),
+				Asset(1),
+				FEE_TIER,
+				100,
+				0,
+				201
+			),
+			Error::<Test>::InsufficientAmountB
+		);
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(2),
+			Asset(1),
+			FEE_TIER,
+			100,
+			50,
+			200
+		));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::LiquidityRemoved {
+			asset_a: Asset(2),
+			asset_b: Asset(1),
+			fee_tier: FEE_TIER,
+			amount_a: 50,
+			amount_b: 200,
+		}));
+	});
+}
+
+#[test]
+fn expired_dex_calls_are_invalid() {
+	new_test_ext().execute_with(|| {
+		let dex_call = mock::RuntimeCall::Dex(crate::Call::swap_exact_in_for_out {
+			asset_in: Asset(1),
+			asset_out: Asset(2),
+			fee_tier: FEE_TIER,
+			exact_in: 100,
+			min_out: 0,
+		});
+		let other_call = mock::RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+		let info = DispatchInfo::default();
+
+		// valid for every block up to and including the deadline
+		System::set_block_number(3);
+		let valid = CheckDexDeadline::<Test>::new(Some(5)).validate(&1, &dex_call, &info, 0);
+		assert_eq!(valid.map(|v| v.longevity), Ok(3));
+		System::set_block_number(5);
+		assert_ok!(CheckDexDeadline::<Test>::new(Some(5)).pre_dispatch(&1, &dex_call, &info, 0));
+
+		// rejected once the deadline has passed
+		System::set_block_number(6);
+		assert_eq!(
+			CheckDexDeadline::<Test>::new(Some(5)).validate(&1, &dex_call, &info, 0),
+			Err(InvalidTransaction::Stale.into())
+		);
+		assert_eq!(
+			CheckDexDeadline::<Test>::new(Some(5)).pre_dispatch(&1, &dex_call, &info, 0),
+			Err(InvalidTransaction::Stale.into())
+		);
+
+		// no deadline, or a call to another pallet, is never rejected
+		assert_ok!(CheckDexDeadline::<Test>::new(None).validate(&1, &dex_call, &info, 0));
+		assert_ok!(CheckDexDeadline::<Test>::new(Some(5)).validate(&1, &other_call, &info, 0));
+	});
+}
+
+#[test]
+fn zap_in_and_out_with_a_single_asset() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (2, 1000)]));
+		assert_noop!(
+			Dex::zap_in(RuntimeOrigin::signed(2), Asset(1), 100, Asset(2), FEE_TIER, 0),
+			Error::<Test>::NoPool
+		);
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+
+		// 49 of the 100 is swapped for 45 of asset 2, then 49 and 45 are deposited
+		assert_eq!(Dex::zap_swap_amount(100, 1000, FEE_TIER), Ok(49));
+		assert_noop!(
+			Dex::zap_in(RuntimeOrigin::signed(2), Asset(1), 100, Asset(2), FEE_TIER, 48),
+			Error::<Test>::SlippageTooHigh
+		);
+		assert_ok!(Dex::zap_in(RuntimeOrigin::signed(2), Asset(1), 100, Asset(2), FEE_TIER, 47));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::ZappedIn {
+			asset_in: Asset(1),
+			other_asset: Asset(2),
+			fee_tier: FEE_TIER,
+			amount_in: 100,
+			amount_lp: 47,
+		}));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		assert_eq!(Assets::total_balance(lp_id, &2), 47);
+		// the 2 left over from rounding stay with the caller
+		assert_eq!(Assets::total_balance(1, &2), 902);
+		assert_eq!(Assets::total_balance(2, &2), 1000);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((1098, 1000)));
+
+		// the 44 of asset 2 removed is swapped back for 45 of asset 1
+		assert_noop!(
+			Dex::zap_out(RuntimeOrigin::signed(2), Asset(1), Asset(2), FEE_TIER, 47, 95),
+			Error::<Test>::SlippageTooHigh
+		);
+		assert_ok!(Dex::zap_out(RuntimeOrigin::signed(2), Asset(1), Asset(2), FEE_TIER, 47, 94));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::ZappedOut {
+			asset_out: Asset(1),
+			other_asset: Asset(2),
+			fee_tier: FEE_TIER,
+			amount_lp: 47,
+			amount_out: 94,
+		}));
+		assert_eq!(Assets::total_balance(lp_id, &2), 0);
+		assert_eq!(Assets::total_balance(1, &2), 996);
+		assert_eq!(Assets::total_balance(2, &2), 1000);
+	});
+}
+
+#[test]
+fn staked_lp_earns_farm_rewards() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(Dex::rewards_account(), vec![(3, 10_000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+
+		assert_noop!(Dex::stake_lp(RuntimeOrigin::signed(1), lp_id, 300), Error::<Test>::NoFarm);
+		assert_noop!(
+			Dex::set_farm(RuntimeOrigin::signed(1), lp_id, Asset(3), 10),
+			Error::<Test>::NotAllowedToSetFarm
+		);
+		assert_noop!(Dex::set_farm(RuntimeOrigin::root(), 99, Asset(3), 10), Error::<Test>::NoPool);
+		assert_ok!(Dex::set_farm(RuntimeOrigin::root(), lp_id, Asset(3), 10));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::FarmUpdated {
+			lp_id,
+			reward_asset: Asset(3),
+			reward_per_block: 10,
+		}));
+
+		// staked LP tokens are frozen
+		assert_noop!(
+			Dex::stake_lp(RuntimeOrigin::signed(1), lp_id, 991),
+			Error::<Test>::InsufficientLPBalance
+		);
+		assert_ok!(Dex::stake_lp(RuntimeOrigin::signed(1), lp_id, 300));
+		assert_eq!(Assets::total_balance(lp_id, &1), 990);
+		assert_eq!(Dex::staked_balance(&lp_id, &1), Some(300));
+		assert_noop!(
+			Dex::remove_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				700,
+				0,
+				0
+			),
+			Error::<Test>::InsufficientLPBalance
+		);
+
+		// the 20 rewards of blocks 1 to 3 all go to account 1, the 20 of blocks 3 to 5 are
+		// shared 1:2
+		System::set_block_number(3);
+		assert_ok!(Dex::stake_lp(RuntimeOrigin::signed(2), lp_id, 600));
+		System::set_block_number(5);
+		assert_eq!(Dex::pending_rewards(&lp_id, &1), 26);
+		assert_eq!(Dex::pending_rewards(&lp_id, &2), 13);
+
+		assert_ok!(Dex::claim_rewards(RuntimeOrigin::signed(1), lp_id));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::RewardsClaimed {
+			who: 1,
+			lp_id,
+			reward_asset: Asset(3),
+			amount: 26,
+			released: 26,
+		}));
+		assert_eq!(Assets::total_balance(3, &1), 26);
+
+		// rewards earned before unstaking can still be claimed, but nothing more accrues
+		assert_noop!(
+			Dex::unstake_lp(RuntimeOrigin::signed(2), lp_id, 601),
+			Error::<Test>::InsufficientStake
+		);
+		assert_ok!(Dex::unstake_lp(RuntimeOrigin::signed(2), lp_id, 600));
+		assert_eq!(Dex::staked_balance(&lp_id, &2), None);
+		System::set_block_number(7);
+		assert_eq!(Dex::pending_rewards(&lp_id, &2), 13);
+		assert_ok!(Dex::claim_rewards(RuntimeOrigin::signed(2), lp_id));
+		assert_eq!(Assets::total_balance(3, &2), 13);
+		assert!(!Stakes::<Test>::contains_key(lp_id, 2u64));
+	});
+}
+
+#[test]
+fn claimed_rewards_vest_linearly() {
+	new_test_ext().execute_with(|| {
+		mock::RewardVestingPeriod::set(10);
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(Dex::rewards_account(), vec![(3, 10_000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		assert_ok!(Dex::set_farm(RuntimeOrigin::root(), lp_id, Asset(3), 10));
+		assert_ok!(Dex::stake_lp(RuntimeOrigin::signed(1), lp_id, 100));
+
+		// the 50 claimed at block 6 only start vesting then
+		System::set_block_number(6);
+		assert_ok!(Dex::claim_rewards(RuntimeOrigin::signed(1), lp_id));
+		assert_eq!(Assets::total_balance(3, &1), 0);
+
+		// half of them has vested by block 11, the other 25 restart vesting with the new 50
+		System::set_block_number(11);
+		assert_ok!(Dex::claim_rewards(RuntimeOrigin::signed(1), lp_id));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::RewardsClaimed {
+			who: 1,
+			lp_id,
+			reward_asset: Asset(3),
+			amount: 50,
+			released: 25,
+		}));
+		assert_eq!(Assets::total_balance(3, &1), 25);
+
+		// after unstaking, claiming releases the rest once it has vested
+		assert_ok!(Dex::unstake_lp(RuntimeOrigin::signed(1), lp_id, 100));
+		System::set_block_number(21);
+		assert_ok!(Dex::claim_rewards(RuntimeOrigin::signed(1), lp_id));
+		assert_eq!(Assets::total_balance(3, &1), 100);
+		assert!(!RewardVesting::<Test>::contains_key(Asset(3u32), 1u64));
+	});
+}
+
+#[test]
+fn native_currency_pool() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(<Balances as fungible::Mutate<_>>::mint_into(&1, 1000));
+		assert_ok!(Dex::setup_account(1, vec![(2, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(2, 1000)]));
+
+		// the native currency pairs with an asset like any other asset
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Native,
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::LiquidityAdded {
+			asset_a: Native,
+			asset_b: Asset(2),
+			fee_tier: FEE_TIER,
+			amount_a: 500,
+			amount_b: 500,
+			amount_lp: 490,
+		}));
+		assert_eq!(Balances::free_balance(1), 500);
+		let lp_id = Dex::get_lp_id(&Native, &Asset(2), FEE_TIER).unwrap();
+		assert_eq!(Balances::free_balance(Dex::pool_account(&lp_id)), 500);
+
+		// 100 of asset 2 less the 3% fee buys 82 of the native currency
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(2),
+			Native,
+			FEE_TIER,
+			100,
+			0
+		));
+		assert_eq!(Balances::free_balance(2), 82);
+		assert_eq!(Assets::total_balance(2, &2), 900);
+		assert_eq!(Dex::get_reserves(Native, Asset(2), FEE_TIER), Some((418, 600)));
+
+		// the LP token of a native pool is still an asset
+		let lp_id = Dex::get_lp_id(&Native, &Asset(2), FEE_TIER).unwrap();
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Native,
+			Asset(2),
+			FEE_TIER,
+			490,
+			0,
+			0
+		));
+		assert_eq!(Assets::total_balance(lp_id, &1), 0);
+		assert!(Balances::free_balance(1) > 500);
+	});
+}
+
+fn remark() -> Box<RuntimeCall> {
+	Box::new(RuntimeCall::System(frame_system::Call::remark { remark: vec![] }))
+}
+
+// a call the mock's `FlashLoanCallFilter` allows, which sends 1 of asset 1 to account 1
+fn tip() -> Box<RuntimeCall> {
+	Box::new(RuntimeCall::Assets(pallet_assets::Call::transfer {
+		id: 1.into(),
+		target: 1,
+		amount: 1,
+	}))
+}
+
+#[test]
+fn flash_swap_repaid_in_either_asset() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+
+		// taking 100 of asset 2 needs 116 of asset 1 back, as 3% of the 115 would be the fee
+		assert_noop!(
+			Dex::flash_swap(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				0,
+				100,
+				115,
+				0,
+				tip()
+			),
+			Error::<Test>::InsufficientRepayment
+		);
+		assert_ok!(Dex::flash_swap(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			0,
+			100,
+			116,
+			0,
+			tip()
+		));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::FlashSwapped {
+			who: 2,
+			asset_a: Asset(1),
+			asset_b: Asset(2),
+			fee_tier: FEE_TIER,
+			amount_a_out: 0,
+			amount_b_out: 100,
+			amount_a_in: 116,
+			amount_b_in: 0,
+		}));
+		assert_eq!(Assets::total_balance(1, &2), 883);
+		assert_eq!(Assets::total_balance(2, &2), 1100);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((1116, 900)));
+
+		// repaying in the same asset only has to cover the amount taken and the fee
+		assert_noop!(
+			Dex::flash_swap(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				100,
+				0,
+				103,
+				0,
+				tip()
+			),
+			Error::<Test>::InsufficientRepayment
+		);
+		assert_ok!(Dex::flash_swap(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			100,
+			0,
+			104,
+			0,
+			tip()
+		));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((1120, 900)));
+	});
+}
+
+#[test]
+fn flash_swap_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 2000), (2, 1000), (3, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (2, 1000)]));
+		for asset in [Asset(2), Asset(3)] {
+			assert_ok!(Dex::add_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				asset,
+				FEE_TIER,
+				1000,
+				1000,
+				0,
+				0
+			));
+		}
+
+		assert_noop!(
+			Dex::flash_swap(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				0,
+				0,
+				0,
+				0,
+				tip()
+			),
+			Error::<Test>::InsufficientOutputAmount
+		);
+		assert_noop!(
+			Dex::flash_swap(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				1000,
+				0,
+				2000,
+				0,
+				tip()
+			),
+			Error::<Test>::InsufficientLiquidity
+		);
+
+		// the call must pass the `FlashLoanCallFilter`
+		assert_noop!(
+			Dex::flash_swap(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				0,
+				100,
+				200,
+				0,
+				remark()
+			),
+			Error::<Test>::CallNotAllowed
+		);
+
+		// the call can't trade with the pool being swapped, nor with any other
+		for asset_out in [Asset(2), Asset(3)] {
+			let swap = Box::new(RuntimeCall::Dex(DexCall::swap_exact_in_for_out {
+				asset_in: Asset(1),
+				asset_out,
+				fee_tier: FEE_TIER,
+				exact_in: 10,
+				min_out: 0,
+			}));
+			assert_noop!(
+				Dex::flash_swap(
+					RuntimeOrigin::signed(2),
+					Asset(1),
+					Asset(2),
+					FEE_TIER,
+					0,
+					100,
+					200,
+					0,
+					swap
+				),
+				Error::<Test>::CallFailed
+			);
+		}
+		assert!(!FlashLoanLock::<Test>::get());
+	});
+}
+
+fn repay(amount: u128) -> Option<Box<RuntimeCall>> {
+	Some(Box::new(RuntimeCall::Assets(pallet_assets::Call::transfer {
+		id: 1.into(),
+		target: Dex::account_id(),
+		amount,
+	})))
+}
+
+#[test]
+fn flash_loan_fee_goes_to_pools() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 2000), (2, 1000), (3, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(3),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+
+		// the fee on 1000 is 30, which is split 20 / 10 by the pools' reserves of asset 1
+		assert_ok!(Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 1000, repay(1030)));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::FlashLoanEvent {
+			asset_id: Asset(1),
+			amount: 1000,
+			fee: 30,
+		}));
+		assert_eq!(Assets::total_balance(1, &2), 970);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((1020, 1000)));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(3), FEE_TIER), Some((510, 500)));
+
+		// without a call the runtime's receiver handles the loan, the mock's pays it back
+		assert_ok!(Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 100, None));
+		assert_eq!(Assets::total_balance(1, &2), 967);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((1022, 1000)));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(3), FEE_TIER), Some((511, 500)));
+	});
+}
+
+#[test]
+fn flash_loans_lend_from_active_pools_of_every_kind() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1_000_000), (2, 1_000_000), (3, 1_000_000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 10_000)]));
+		// a concentrated pool and a constant product pool holding 88_298 of asset 1 each
+		assert_ok!(Dex::create_concentrated_pool(
+			RuntimeOrigin::signed(1),
+			Asset(2),
+			Asset(1),
+			30,
+			FixedU128::from_rational(1, 4)
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), 30).unwrap();
+		assert_ok!(Dex::mint_position(
+			RuntimeOrigin::signed(1),
+			lp_id,
+			13_500,
+			14_220,
+			10_000_000,
+			88_298,
+			360_334
+		));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(3),
+			FEE_TIER,
+			88_298,
+			88_298,
+			0,
+			0
+		));
+		// and a pool whose swaps are paused
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+		assert_ok!(Dex::set_pool_status(
+			RuntimeOrigin::root(),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			PoolStatus::SwapsPaused
+		));
+
+		// the paused pool doesn't lend
+		assert_noop!(
+			Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 2 * 88_298 + 1, None),
+			Error::<Test>::InsufficientLiquidity
+		);
+		// nor share the fee of 3000, which the others split by their equal reserves
+		assert_ok!(Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 100_000, repay(103_000)));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((1000, 1000)));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(3), FEE_TIER), Some((89_798, 88_298)));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), 30), Some((89_798, 360_334)));
+		// the concentrated pool's share is earned by the liquidity in range, less rounding
+		assert_ok!(Dex::collect_fees(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::FeesCollected {
+			who: 1,
+			position_id: 0,
+			amount_1: 1499,
+			amount_2: 0,
+		}));
+	});
+}
+
+#[test]
+fn flash_loan_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+
+		assert_noop!(
+			Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 1000, repay(1029)),
+			Error::<Test>::InsufficientRepayment
+		);
+		// only what the pools hold can be lent
+		assert_noop!(
+			Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 1001, repay(1031)),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_noop!(
+			Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 100, Some(remark())),
+			Error::<Test>::CallNotAllowed
+		);
+	});
+}
+
+#[test]
+fn flash_loan_locks_dex() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+
+		// the loaned funds can't be used to trade with or change the pools they came from
+		let swap = Box::new(RuntimeCall::Dex(DexCall::swap_exact_in_for_out {
+			asset_in: Asset(1),
+			asset_out: Asset(2),
+			fee_tier: FEE_TIER,
+			exact_in: 500,
+			min_out: 0,
+		}));
+		assert_noop!(
+			Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 500, Some(swap)),
+			Error::<Test>::CallFailed
+		);
+
+		FlashLoanLock::<Test>::put(true);
+		assert_noop!(
+			Dex::add_liquidity(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				10,
+				10,
+				0,
+				0
+			),
+			Error::<Test>::FlashLoanInProgress
+		);
+		assert_noop!(
+			Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(2),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				10,
+				0
+			),
+			Error::<Test>::FlashLoanInProgress
+		);
+		assert_noop!(
+			Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 100, repay(103)),
+			Error::<Test>::FlashLoanInProgress
+		);
+		FlashLoanLock::<Test>::kill();
+
+		// the lock is released once the loan is repaid
+		assert_ok!(Dex::flash_loan(RuntimeOrigin::signed(2), Asset(1), 100, repay(103)));
+		assert!(!FlashLoanLock::<Test>::get());
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			10,
+			0
+		));
+	});
+}
+
+#[test]
+fn create_and_destroy_pool() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		mock::PoolCreationDeposit::set(100);
+		assert_ok!(<Balances as fungible::Mutate<_>>::mint_into(&1, 1000));
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		let lp_id = Dex::next_lp_id();
+		let on_hold = || {
+			<Balances as fungible::hold::Inspect<_>>::balance_on_hold(
+				&RuntimeHoldReason::Dex(HoldReason::PoolCreation),
+				&1,
+			)
+		};
+
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PoolCreated {
+			who: 1,
+			asset_a: Asset(1),
+			asset_b: Asset(2),
+			fee_tier: FEE_TIER,
+			lp_id,
+			deposit: 100,
+		}));
+		assert_eq!(on_hold(), 100);
+		assert!(Assets::asset_exists(lp_id));
+		assert_noop!(
+			Dex::create_pool(RuntimeOrigin::signed(1), Asset(2), Asset(1), FEE_TIER),
+			Error::<Test>::PoolExists
+		);
+		// there is nothing to swap with until the first deposit
+		assert_noop!(
+			Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				10,
+				0
+			),
+			Error::<Test>::InsufficientLiquidity
+		);
+
+		// only its creator can destroy the empty pool, and gets the deposit back
+		assert_noop!(
+			Dex::destroy_pool(RuntimeOrigin::signed(2), Asset(1), Asset(2), FEE_TIER),
+			Error::<Test>::NotPoolCreator
+		);
+		assert_ok!(Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PoolDestroyed {
+			asset_a: Asset(1),
+			asset_b: Asset(2),
+			fee_tier: FEE_TIER,
+			lp_id,
+			depositor: 1,
+			deposit: 100,
+		}));
+		assert_eq!(on_hold(), 0);
+		assert_eq!(Balances::free_balance(1), 1000);
+		assert!(!PoolMap::<Test>::contains_key(lp_id));
+		assert!(!Assets::asset_exists(lp_id));
+
+		// depositing into a pool that doesn't exist creates it and takes the deposit as well
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		assert_eq!(on_hold(), 100);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((500, 500)));
+		assert_noop!(
+			Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER),
+			Error::<Test>::PoolNotEmpty
+		);
+
+		// once only the locked minimum liquidity is left, the pool can be destroyed, and the
+		// reserves it stood for go to the creator along with the deposit
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			490,
+			0,
+			0
+		));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((10, 10)));
+		assert_ok!(Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER));
+		assert_eq!(on_hold(), 0);
+		assert_eq!(Assets::total_balance(1, &1), 1000);
+		assert_eq!(Assets::total_balance(2, &1), 1000);
+		assert_eq!(Assets::total_balance(lp_id, &Dex::locked_liquidity_account()), 0);
+		assert!(!Assets::asset_exists(lp_id));
+		assert_eq!(System::providers(&Dex::pool_account(&lp_id)), 0);
+	});
+}
+
+#[test]
+fn create_pool_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		mock::PoolCreationDeposit::set(100);
+		mock::MaxPools::set(1);
+		assert_ok!(<Balances as fungible::Mutate<_>>::mint_into(&1, 1000));
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000), (3, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (3, 1000)]));
+
+		assert_noop!(
+			Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), 1),
+			Error::<Test>::InvalidFeeTier
+		);
+		assert_noop!(
+			Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER),
+			Error::<Test>::NoPool
+		);
+		// the deposit has to be paid
+		assert!(Dex::create_pool(RuntimeOrigin::signed(2), Asset(1), Asset(3), FEE_TIER).is_err());
+
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER));
+		assert_noop!(
+			Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(3), FEE_TIER),
+			Error::<Test>::TooManyPools
+		);
+		assert_noop!(
+			Dex::add_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(3),
+				FEE_TIER,
+				500,
+				500,
+				0,
+				0
+			),
+			Error::<Test>::TooManyPools
+		);
+
+		// destroying a pool makes room for another. The `PauseOrigin` can destroy any pool
+		assert_ok!(Dex::destroy_pool(RuntimeOrigin::root(), Asset(1), Asset(2), FEE_TIER));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(3), FEE_TIER));
+	});
+}
+
+#[test]
+fn pool_status() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			500,
+			500,
+			0,
+			0
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		let swap = || {
+			Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				10,
+				0,
+			)
+		};
+
+		assert_noop!(
+			Dex::set_pool_status(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				PoolStatus::SwapsPaused
+			),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Dex::set_pool_status(
+				RuntimeOrigin::root(),
+				Asset(1),
+				Asset(3),
+				FEE_TIER,
+				PoolStatus::SwapsPaused
+			),
+			Error::<Test>::NoPool
+		);
+
+		// swaps are paused, but liquidity can still be taken out
+		assert_ok!(Dex::set_pool_status(
+			RuntimeOrigin::root(),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			PoolStatus::SwapsPaused
+		));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PoolStatusChanged {
+			lp_id,
+			status: PoolStatus::SwapsPaused,
+		}));
+		assert_noop!(swap(), Error::<Test>::SwapsPaused);
+		assert_eq!(Dex::quote_exact_in(Asset(1), Asset(2), FEE_TIER, 10), None);
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			90,
+			0,
+			0
+		));
+
+		// a frozen pool can't be touched at all
+		assert_ok!(Dex::set_pool_status(
+			RuntimeOrigin::root(),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			PoolStatus::Frozen
+		));
+		assert_noop!(swap(), Error::<Test>::PoolFrozen);
+		assert_noop!(
+			Dex::remove_liquidity(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER, 90, 0, 0),
+			Error::<Test>::PoolFrozen
+		);
+		assert_noop!(
+			Dex::add_liquidity(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				100,
+				100,
+				0,
+				0
+			),
+			Error::<Test>::PoolFrozen
+		);
+
+		assert_ok!(Dex::set_pool_status(
+			RuntimeOrigin::root(),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			PoolStatus::Active
+		));
+		assert_ok!(swap());
+	});
+}
+
+#[test]
+fn circuit_breaker() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 10_000), (2, 10_000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		let swap = |amount| {
+			Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				amount,
+				0,
+			)
+		};
+		let reset = || {
+			Dex::set_pool_status(
+				RuntimeOrigin::root(),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				PoolStatus::Active,
+			)
+		};
+
+		// a single swap moving the price by more than 10% fails, without pausing the pool
+		mock::MaxPriceImpact::set(Permill::from_percent(10));
+		assert_ok!(swap(10));
+		assert_noop!(swap(100), Error::<Test>::CircuitBreakerTripped);
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().status, PoolStatus::Active);
+
+		// small swaps that add up to more than a 10% move within the window go through, but
+		// pause swaps after the last of them
+		mock::MaxPriceImpact::set(Permill::zero());
+		mock::MaxPriceMove::set(Permill::from_percent(10));
+		assert_ok!(swap(30));
+		let reserves = Dex::get_reserves(Asset(1), Asset(2), FEE_TIER);
+		assert_ok!(swap(30));
+		System::assert_has_event(mock::RuntimeEvent::Dex(Event::CircuitBreakerTripped { lp_id }));
+		assert_ne!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), reserves);
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().status, PoolStatus::SwapsPaused);
+		assert_noop!(swap(10), Error::<Test>::SwapsPaused);
+
+		// a move that is rolled back doesn't pause the pool
+		assert_ok!(reset());
+		assert_ok!(swap(30));
+		let reserves = Dex::get_reserves(Asset(1), Asset(2), FEE_TIER);
+		// account 3 can't pay for the swap, so it fails after the pool was updated
+		assert!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(3),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			50,
+			0
+		)
+		.is_err());
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), reserves);
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().status, PoolStatus::Active);
+
+		// once the window is over the price is measured from where it is now
+		assert_ok!(reset());
+		assert_ok!(swap(30));
+		System::set_block_number(11);
+		assert_ok!(swap(30));
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().status, PoolStatus::Active);
+	});
+}
+
+#[test]
+fn lp_token_lifecycle() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		mock::MetadataDepositBase::set(10);
+		mock::MetadataDepositPerByte::set(1);
+		assert_ok!(<Balances as fungible::Mutate<_>>::mint_into(&1, 1000));
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000), (3, 1000)]));
+		assert_ok!(Assets::force_set_metadata(
+			RuntimeOrigin::root(),
+			1.into(),
+			b"One".to_vec(),
+			b"ONE".to_vec(),
+			12,
+			false
+		));
+		assert_ok!(Assets::force_set_metadata(
+			RuntimeOrigin::root(),
+			2.into(),
+			b"Two".to_vec(),
+			b"TWO".to_vec(),
+			6,
+			false
+		));
+		let metadata = |lp_id| {
+			use frame_support::traits::fungibles::metadata::Inspect;
+			(Assets::name(lp_id), Assets::symbol(lp_id), Assets::decimals(lp_id))
+		};
+
+		// the LP token is named after the pool and has the mean of its assets' decimals
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(2), Asset(1), FEE_TIER));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		assert_eq!(metadata(lp_id), (b"ONE/TWO 300bps LP".to_vec(), b"ONE-TWO-LP".to_vec(), 9));
+		assert_eq!(Assets::minimum_balance(lp_id), 1);
+		// the metadata deposit of 10 + 27 bytes, and the existential deposit of the DEX's account
+		assert_eq!(Balances::free_balance(1), 962);
+
+		// the native currency goes by its symbol, and an asset without metadata by its id
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(3), Native, FEE_TIER));
+		let native_lp_id = Dex::get_lp_id(&Native, &Asset(3), FEE_TIER).unwrap();
+		assert_eq!(
+			metadata(native_lp_id),
+			(b"UNIT/3 300bps LP".to_vec(), b"UNIT-3-LP".to_vec(), 6)
+		);
+		assert_eq!(NativeOrAssetUnion::<Test>::symbol(&Asset(4_000_000)), b"4000000".to_vec());
+
+		// destroying the pool destroys its LP token and refunds the metadata deposit
+		assert_ok!(Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER));
+		assert!(!Assets::asset_exists(lp_id));
+		assert_eq!(Balances::free_balance(1), 962 - 35 + 37);
+	});
+}
+
+#[test]
+fn emptied_pool_destroys_its_lp_token() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		mock::MetadataDepositBase::set(10);
+		mock::MetadataDepositPerByte::set(1);
+		assert_ok!(<Balances as fungible::Mutate<_>>::mint_into(&1, 1000));
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (2, 1000)]));
+		let deposit = |who, amount| {
+			Dex::add_liquidity(
+				RuntimeOrigin::signed(who),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				amount,
+				amount,
+				0,
+				0,
+			)
+		};
+		assert_ok!(deposit(1, 1000));
+		assert_ok!(deposit(2, 500));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		// the metadata deposit of 10 + 19 bytes, and the existential deposit of the DEX's account
+		assert_eq!(Balances::free_balance(1), 970);
+		// account 2 earns farm rewards it doesn't claim until later
+		assert_ok!(Dex::setup_account(Dex::rewards_account(), vec![(3, 1000)]));
+		assert_ok!(Dex::set_farm(RuntimeOrigin::root(), lp_id, Asset(3), 10));
+		assert_ok!(Dex::stake_lp(RuntimeOrigin::signed(2), lp_id, 100));
+		System::set_block_number(3);
+		assert_ok!(Dex::unstake_lp(RuntimeOrigin::signed(2), lp_id, 100));
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			100,
+			0
+		));
+
+		// every LP withdraws all they can, which leaves the locked minimum liquidity
+		for who in [1, 2] {
+			assert_noop!(
+				Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER),
+				Error::<Test>::PoolNotEmpty
+			);
+			assert_ok!(Dex::remove_liquidity(
+				RuntimeOrigin::signed(who),
+				Asset(1),
+				Asset(2),
+				FEE_TIER,
+				Assets::total_balance(lp_id, &who),
+				0,
+				0
+			));
+		}
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().lp_supply, 10);
+		// the rewards have to be claimed from the farm first
+		assert_noop!(
+			Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER),
+			Error::<Test>::PoolNotEmpty
+		);
+		assert_ok!(Dex::claim_rewards(RuntimeOrigin::signed(2), lp_id));
+		assert!(PriceWindows::<Test>::contains_key(lp_id));
+
+		// the pool goes, and its LP token and farm with it, refunding the metadata deposit
+		assert_ok!(Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER));
+		assert!(!PoolMap::<Test>::contains_key(lp_id));
+		assert!(!Farms::<Test>::contains_key(lp_id));
+		assert!(!PriceWindows::<Test>::contains_key(lp_id));
+		assert_eq!(LpToPair::<Test>::get(lp_id), None);
+		assert_eq!(PoolCount::<Test>::get(), 0);
+		assert!(!Assets::asset_exists(lp_id));
+		assert_eq!(Balances::free_balance(1), 999);
+		// nothing is left behind in the pool's account
+		let pool_account = Dex::pool_account(&lp_id);
+		for asset in [1, 2] {
+			assert_eq!(Assets::total_balance(asset, &pool_account), 0);
+			assert_eq!(Assets::total_balance(asset, &1) + Assets::total_balance(asset, &2), 2000);
+		}
+	});
+}
+
+#[test]
+fn lp_id_allocation() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 1000), (3, 1000)]));
+		assert_noop!(Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER), Error::<Test>::NoPool);
+
+		// LP tokens get consecutive ids from the reserved range, whichever order the assets are in
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(2), Asset(1), FEE_TIER));
+		assert_eq!(Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER), Ok(FIRST_LP_ASSET_ID));
+		assert_eq!(Dex::get_lp_id(&Asset(2), &Asset(1), FEE_TIER), Ok(FIRST_LP_ASSET_ID));
+		assert_eq!(LpToPair::<Test>::get(FIRST_LP_ASSET_ID), Some((Asset(1), Asset(2), FEE_TIER)));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), 30));
+		assert_eq!(Dex::get_lp_id(&Asset(1), &Asset(2), 30), Ok(FIRST_LP_ASSET_ID + 1));
+
+		// an id someone already created an asset with is skipped
+		assert_ok!(Assets::force_create(
+			RuntimeOrigin::root(),
+			(FIRST_LP_ASSET_ID + 2).into(),
+			1,
+			true,
+			1
+		));
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(3), FEE_TIER));
+		assert_eq!(Dex::get_lp_id(&Asset(1), &Asset(3), FEE_TIER), Ok(FIRST_LP_ASSET_ID + 3));
+
+		// ids of destroyed pools aren't handed out again
+		assert_ok!(Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER));
+		assert_noop!(Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER), Error::<Test>::NoPool);
+		assert_eq!(LpToPair::<Test>::get(FIRST_LP_ASSET_ID), None);
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), FEE_TIER));
+		assert_eq!(Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER), Ok(FIRST_LP_ASSET_ID + 4));
+	});
+}
+
+// a pool as stored before `Pool::kind`, with `lp_supply` of 100
+fn old_pool(pool_pair: PoolPair<Test>, fee_tier: u16) -> migrations::OldPool<Test> {
+	migrations::OldPool {
+		pool_pair,
+		fee_tier,
+		lp_supply: 100,
+		price_cumulative_1: 0,
+		price_cumulative_2: 0,
+		block_timestamp_last: 0,
+		k_last: 0,
+		status: PoolStatus::Active,
+	}
+}
+
+#[test]
+fn migrate_to_v1() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<Dex>();
+		// pools of the first release, under ids hashed from their pairs, all charging `FeeV0`
+		let pool = |asset_1, asset_2| migrations::PoolV0::<Test> {
+			pool_pair: migrations::PoolPairV0 { asset_1, amount_1: 100, asset_2, amount_2: 200 },
+			lp_supply: 100,
+		};
+		migrations::PoolMapV0::<Test>::insert(3_141_592, pool(1, 2));
+		migrations::PoolMapV0::<Test>::insert(2_718_281, pool(1, 3));
+		migrations::FeeV0::<Test>::put(3);
+
+		migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Dex::on_chain_storage_version(), 1);
+		assert!(!migrations::FeeV0::<Test>::exists());
+		assert_eq!(PoolCount::<Test>::get(), 2);
+		// the global fee of 3% became every pool's tier
+		assert_eq!(Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER), Ok(3_141_592));
+		assert_eq!(Dex::get_lp_id(&Asset(3), &Asset(1), FEE_TIER), Ok(2_718_281));
+		assert_eq!(LpToPair::<Test>::get(2_718_281), Some((Asset(1), Asset(3), FEE_TIER)));
+		let pool = migrations::OldPoolMap::<Test>::get(3_141_592).unwrap();
+		assert_eq!(
+			(pool.pool_pair.amount_1, pool.pool_pair.amount_2, pool.lp_supply),
+			(100, 200, 100)
+		);
+		assert_eq!((pool.k_last, pool.status), (0, PoolStatus::Active));
+		// new pools get ids from the reserved range
+		assert_eq!(Dex::next_lp_id(), FIRST_LP_ASSET_ID);
+	});
+}
+
+#[test]
+fn pool_accounts() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 1000), (2, 2000), (3, 1000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 1000), (2, 1000)]));
+		for asset in [Asset(1), Asset(3)] {
+			assert_ok!(Dex::add_liquidity(
+				RuntimeOrigin::signed(1),
+				asset,
+				Asset(2),
+				FEE_TIER,
+				1000,
+				1000,
+				0,
+				0
+			));
+		}
+		let pool_1_2 = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		let pool_2_3 = Dex::get_lp_id(&Asset(2), &Asset(3), FEE_TIER).unwrap();
+		assert_ne!(Dex::pool_account(&pool_1_2), Dex::pool_account(&pool_2_3));
+		// every pool's account holds exactly its reserves
+		let audit = || {
+			for (lp_id, pool) in PoolMap::<Test>::iter() {
+				let pool_account = Dex::pool_account(&lp_id);
+				let pair = pool.pool_pair;
+				assert_eq!(
+					NativeOrAssetUnion::<Test>::balance(&pair.asset_1, &pool_account),
+					pair.amount_1
+				);
+				assert_eq!(
+					NativeOrAssetUnion::<Test>::balance(&pair.asset_2, &pool_account),
+					pair.amount_2
+				);
+			}
+		};
+		audit();
+
+		// the middle asset of a path moves straight from one pool's account to the next
+		let path: BoundedVec<_, _> = vec![Asset(1), Asset(2), Asset(3)].try_into().unwrap();
+		let fee_tiers: BoundedVec<_, _> = vec![FEE_TIER, FEE_TIER].try_into().unwrap();
+		assert_ok!(Dex::swap_exact_in_along_path(
+			RuntimeOrigin::signed(2),
+			path.clone(),
+			fee_tiers.clone(),
+			100,
+			0
+		));
+		audit();
+		assert_ok!(Dex::swap_in_for_exact_out_along_path(
+			RuntimeOrigin::signed(2),
+			path,
+			fee_tiers,
+			1000,
+			50
+		));
+		audit();
+		assert_eq!(Assets::total_balance(2, &Dex::account_id()), 0);
+
+		// a loan bigger than any one pool's reserves is lent from both, and paid back to them
+		let (reserve_1, _) = Dex::get_reserves(Asset(2), Asset(1), FEE_TIER).unwrap();
+		let (reserve_3, _) = Dex::get_reserves(Asset(2), Asset(3), FEE_TIER).unwrap();
+		let amount = reserve_1 + reserve_3 / 2;
+		let fee = Dex::calculate_fees(&amount, FEE_TIER).unwrap();
+		let repay = Box::new(RuntimeCall::Assets(pallet_assets::Call::transfer {
+			id: 2.into(),
+			target: Dex::account_id(),
+			amount: amount + fee,
+		}));
+		assert_ok!(Dex::flash_loan(RuntimeOrigin::signed(2), Asset(2), amount, Some(repay)));
+		audit();
+		assert_eq!(Assets::total_balance(2, &Dex::account_id()), 0);
+		assert_noop!(
+			Dex::flash_loan(RuntimeOrigin::signed(2), Asset(2), reserve_1 + reserve_3 + 1, None),
+			Error::<Test>::InsufficientLiquidity
+		);
+
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			990,
+			0,
+			0
+		));
+		audit();
+	});
+}
+
+#[test]
+fn migrate_to_v2() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(1).put::<Dex>();
+		// a pool whose reserves are still in the DEX's account
+		assert_ok!(Dex::setup_account(Dex::account_id(), vec![(1, 100), (2, 300)]));
+		let pool_pair = PoolPair::<Test>::new(Asset(1), 100, Asset(2), 300).unwrap();
+		migrations::OldPoolMap::<Test>::insert(7, old_pool(pool_pair, FEE_TIER));
+
+		migrations::v2::MigrateToV2::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Dex::on_chain_storage_version(), 2);
+		assert_eq!(Assets::total_balance(1, &Dex::pool_account(&7)), 100);
+		assert_eq!(Assets::total_balance(2, &Dex::pool_account(&7)), 300);
+		assert_eq!(Assets::total_balance(1, &Dex::account_id()), 0);
+		assert_eq!(Assets::total_balance(2, &Dex::account_id()), 0);
+	});
+}
+
+#[test]
+fn skim_and_sync() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 10_000), (2, 10_000), (3, 10_000)]));
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			1000,
+			1000,
+			0,
+			0
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), FEE_TIER).unwrap();
+		let pool_account = Dex::pool_account(&lp_id);
+		let donate = |asset: u32, amount| {
+			assert_ok!(Assets::transfer(
+				RuntimeOrigin::signed(1),
+				asset.into(),
+				pool_account,
+				amount
+			));
+		};
+
+		// donations aren't part of the reserves, so anyone can skim them
+		donate(1, 100);
+		donate(2, 50);
+		assert_ok!(Dex::skim(RuntimeOrigin::signed(2), lp_id, 3));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::Skimmed {
+			lp_id,
+			to: 3,
+			amounts: vec![100, 50],
+		}));
+		assert_eq!(Assets::total_balance(1, &3), 100);
+		assert_eq!(Assets::total_balance(2, &3), 50);
+		assert_eq!(
+			Dex::pool_balances(&lp_id, &PoolMap::<Test>::get(lp_id).unwrap()),
+			vec![1000, 1000]
+		);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((1000, 1000)));
+
+		// or add them to the reserves
+		donate(1, 50);
+		assert_ok!(Dex::sync(RuntimeOrigin::signed(2), lp_id));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::Synced {
+			lp_id,
+			reserves: vec![1050, 1000],
+		}));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((1050, 1000)));
+		assert_ok!(Dex::skim(RuntimeOrigin::signed(2), lp_id, 3));
+		assert_eq!(Assets::total_balance(1, &3), 100);
+
+		// a sync repairs the reserves however far they drifted, so it isn't held to the
+		// circuit breaker
+		mock::MaxPriceImpact::set(Permill::from_percent(10));
+		mock::MaxPriceMove::set(Permill::from_percent(10));
+		donate(1, 500);
+		assert_ok!(Dex::sync(RuntimeOrigin::signed(2), lp_id));
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), FEE_TIER), Some((1550, 1000)));
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().status, PoolStatus::Active);
+
+		// including when the account holds less than the reserves, which no skim can fix
+		assert_ok!(Assets::burn(
+			RuntimeOrigin::signed(Dex::account_id()),
+			2.into(),
+			pool_account,
+			200
+		));
+		assert_ok!(Dex::sync(RuntimeOrigin::signed(2), lp_id));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::Synced {
+			lp_id,
+			reserves: vec![1550, 800],
+		}));
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().status, PoolStatus::Active);
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			10,
+			0
+		));
+
+		// skimming and syncing stay open while swaps are paused, but not once the pool is frozen
+		assert_ok!(Dex::set_pool_status(
+			RuntimeOrigin::root(),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			PoolStatus::SwapsPaused
+		));
+		donate(2, 20);
+		assert_ok!(Dex::skim(RuntimeOrigin::signed(2), lp_id, 3));
+		assert_eq!(Assets::total_balance(2, &3), 70);
+		assert_ok!(Dex::sync(RuntimeOrigin::signed(2), lp_id));
+		assert_ok!(Dex::set_pool_status(
+			RuntimeOrigin::root(),
+			Asset(1),
+			Asset(2),
+			FEE_TIER,
+			PoolStatus::Frozen
+		));
+		assert_noop!(Dex::sync(RuntimeOrigin::signed(2), lp_id), Error::<Test>::PoolFrozen);
+		assert_noop!(Dex::skim(RuntimeOrigin::signed(2), lp_id, 3), Error::<Test>::PoolFrozen);
+
+		// a pool without liquidity has no reserves to sync
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(3), FEE_TIER));
+		let empty_lp_id = Dex::get_lp_id(&Asset(1), &Asset(3), FEE_TIER).unwrap();
+		assert_noop!(
+			Dex::sync(RuntimeOrigin::signed(2), empty_lp_id),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_noop!(Dex::sync(RuntimeOrigin::signed(2), 7), Error::<Test>::NoPool);
+	});
+}
+
+// the amplification coefficient of the StableSwap pool of `lp_id`
+fn amplification(lp_id: u32) -> Amplification<u64> {
+	match PoolMap::<Test>::get(lp_id).unwrap().kind {
+		PoolKind::StableSwap(stable) => stable.amplification,
+		PoolKind::ConstantProduct => panic!("not a StableSwap pool"),
+	}
+}
+
+#[test]
+fn stable_swap_pools() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let balances = (1..=4).map(|asset| (asset, 1_000_000)).collect::<Vec<_>>();
+		assert_ok!(Dex::setup_account(1, balances.clone()));
+		assert_ok!(Dex::setup_account(2, balances));
+		let assets = |assets: Vec<u32>| -> BoundedVec<_, _> {
+			assets.into_iter().map(Asset).collect::<Vec<_>>().try_into().unwrap()
+		};
+		let amounts = |amounts: Vec<(u32, u128)>| -> BoundedVec<_, _> {
+			amounts
+				.into_iter()
+				.map(|(asset, amount)| (Asset(asset), amount))
+				.collect::<Vec<_>>()
+				.try_into()
+				.unwrap()
+		};
+
+		assert_noop!(
+			Dex::create_stable_pool(RuntimeOrigin::signed(1), assets(vec![1, 2]), 5, 0),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_noop!(
+			Dex::create_stable_pool(RuntimeOrigin::signed(1), assets(vec![1, 2]), 5, 10_001),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_noop!(
+			Dex::create_stable_pool(RuntimeOrigin::signed(1), assets(vec![1]), 5, 100),
+			Error::<Test>::InvalidStablePool
+		);
+		assert_noop!(
+			Dex::create_stable_pool(RuntimeOrigin::signed(1), assets(vec![1, 2, 1]), 5, 100),
+			Error::<Test>::InvalidStablePool
+		);
+
+		// every pair of the pool's assets is swapped through it
+		assert_ok!(Dex::create_stable_pool(
+			RuntimeOrigin::signed(1),
+			assets(vec![3, 1, 2]),
+			5,
+			100
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), 5).unwrap();
+		assert_eq!(lp_id, FIRST_LP_ASSET_ID);
+		assert_eq!(Dex::get_lp_id(&Asset(3), &Asset(1), 5), Ok(lp_id));
+		assert_eq!(Dex::get_lp_id(&Asset(2), &Asset(3), 5), Ok(lp_id));
+		assert_eq!(amplification(lp_id), Amplification::new(100, 1));
+		assert_noop!(
+			Dex::create_pool(RuntimeOrigin::signed(1), Asset(3), Asset(2), 5),
+			Error::<Test>::PoolExists
+		);
+		assert_noop!(
+			Dex::create_stable_pool(RuntimeOrigin::signed(1), assets(vec![3, 2]), 5, 100),
+			Error::<Test>::PoolExists
+		);
+
+		// the first deposit must fund every asset
+		assert_noop!(
+			Dex::add_stable_liquidity(
+				RuntimeOrigin::signed(1),
+				lp_id,
+				amounts(vec![(1, 100_000), (2, 100_000)]),
+				0
+			),
+			Error::<Test>::InvalidStablePool
+		);
+		assert_noop!(
+			Dex::add_stable_liquidity(
+				RuntimeOrigin::signed(1),
+				lp_id,
+				amounts(vec![(1, 100_000), (2, 100_000), (4, 100_000)]),
+				0
+			),
+			Error::<Test>::AssetNotInPool
+		);
+		// balanced reserves have an invariant of their total
+		assert_ok!(Dex::add_stable_liquidity(
+			RuntimeOrigin::signed(1),
+			lp_id,
+			amounts(vec![(1, 100_000), (2, 100_000), (3, 100_000)]),
+			0
+		));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::StableLiquidityAdded {
+			who: 1,
+			lp_id,
+			amounts: amounts(vec![(1, 100_000), (2, 100_000), (3, 100_000)]).into_inner(),
+			amount_lp: 299_990,
+		}));
+		assert_eq!(PoolMap::<Test>::get(lp_id).unwrap().lp_supply, 300_000);
+
+		// close to 1:1 it trades with far less slippage than a constant product pool
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(4),
+			5,
+			100_000,
+			100_000,
+			0,
+			0
+		));
+		let stable_quote = Dex::quote_exact_in(Asset(1), Asset(2), 5, 1000).unwrap();
+		let constant_product_quote = Dex::quote_exact_in(Asset(1), Asset(4), 5, 1000).unwrap();
+		assert_eq!(stable_quote.amount_out, 998);
+		assert_eq!(constant_product_quote.amount_out, 990);
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			5,
+			1000,
+			998
+		));
+		assert_eq!(Assets::total_balance(2, &2), 1_000_998);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), 5), Some((101_000, 99_002)));
+		let quote = Dex::quote_exact_out(Asset(3), Asset(1), 5, 1000).unwrap();
+		assert_ok!(Dex::swap_in_for_exact_out(
+			RuntimeOrigin::signed(2),
+			Asset(3),
+			Asset(1),
+			5,
+			quote.amount_in,
+			1000
+		));
+		assert_eq!(Assets::total_balance(3, &2), 1_000_000 - quote.amount_in);
+		assert!(quote.amount_in > 1000 && quote.amount_in < 1010);
+
+		// the pool's account holds exactly its reserves
+		let pool = PoolMap::<Test>::get(lp_id).unwrap();
+		let reserves = pool.reserves().into_iter().map(|(_, reserve)| reserve).collect::<Vec<_>>();
+		assert_eq!(Dex::pool_balances(&lp_id, &pool), reserves);
+
+		// deposits may be of any of the assets, and the pair calls reach the pool too
+		assert_ok!(Dex::add_stable_liquidity(
+			RuntimeOrigin::signed(2),
+			lp_id,
+			amounts(vec![(3, 10_000)]),
+			0
+		));
+		let lp_balance = Assets::total_balance(lp_id, &2);
+		assert!(lp_balance > 9_900 && lp_balance < 10_000);
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(2),
+			Asset(2),
+			Asset(1),
+			5,
+			1000,
+			1000,
+			0,
+			0
+		));
+		assert!(Assets::total_balance(lp_id, &2) > lp_balance);
+
+		// withdrawals pay out every reserve in proportion
+		let lp_balance = Assets::total_balance(lp_id, &2);
+		let pool = PoolMap::<Test>::get(lp_id).unwrap();
+		assert_noop!(
+			Dex::remove_stable_liquidity(
+				RuntimeOrigin::signed(2),
+				lp_id,
+				lp_balance,
+				amounts(vec![(4, 1)])
+			),
+			Error::<Test>::AssetNotInPool
+		);
+		assert_noop!(
+			Dex::remove_stable_liquidity(
+				RuntimeOrigin::signed(2),
+				lp_id,
+				lp_balance,
+				amounts(vec![(3, 1_000_000)])
+			),
+			Error::<Test>::SlippageTooHigh
+		);
+		assert_ok!(Dex::remove_stable_liquidity(
+			RuntimeOrigin::signed(2),
+			lp_id,
+			lp_balance,
+			BoundedVec::default()
+		));
+		let expected = pool
+			.reserves()
+			.into_iter()
+			.map(|(asset, reserve)| (asset, reserve * lp_balance / pool.lp_supply))
+			.collect::<Vec<_>>();
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::StableLiquidityRemoved {
+			who: 2,
+			lp_id,
+			amount_lp: lp_balance,
+			amounts: expected,
+		}));
+		assert_eq!(Assets::total_balance(lp_id, &2), 0);
+
+		// only constant product pools can be zapped into
+		assert_noop!(
+			Dex::zap_in(RuntimeOrigin::signed(2), Asset(1), 1000, Asset(2), 5, 0),
+			Error::<Test>::UnsupportedPoolKind
+		);
+		assert_noop!(
+			Dex::add_stable_liquidity(
+				RuntimeOrigin::signed(1),
+				Dex::get_lp_id(&Asset(1), &Asset(4), 5).unwrap(),
+				amounts(vec![(1, 1000)]),
+				0
+			),
+			Error::<Test>::UnsupportedPoolKind
+		);
+
+		// destroying an empty pool frees every one of its pairs
+		assert_ok!(Dex::create_stable_pool(
+			RuntimeOrigin::signed(1),
+			assets(vec![2, 3, 4]),
+			FEE_TIER,
+			100
+		));
+		assert_ok!(Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(4), Asset(2), FEE_TIER));
+		for (asset_a, asset_b) in [(2, 3), (2, 4), (3, 4)] {
+			assert_noop!(
+				Dex::get_lp_id(&Asset(asset_a), &Asset(asset_b), FEE_TIER),
+				Error::<Test>::NoPool
+			);
+		}
+	});
+}
+
+#[test]
+fn amplification_ramp() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let balances = (1..=3).map(|asset| (asset, 1_000_000)).collect::<Vec<_>>();
+		assert_ok!(Dex::setup_account(1, balances));
+		let assets: BoundedVec<_, _> = vec![Asset(1), Asset(2)].try_into().unwrap();
+		assert_ok!(Dex::create_stable_pool(RuntimeOrigin::signed(1), assets, 5, 100));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), 5).unwrap();
+		let amounts: BoundedVec<_, _> =
+			vec![(Asset(1), 100_000), (Asset(2), 100_000)].try_into().unwrap();
+		assert_ok!(Dex::add_stable_liquidity(RuntimeOrigin::signed(1), lp_id, amounts, 0));
+		let quote_before = Dex::quote_exact_in(Asset(1), Asset(2), 5, 50_000).unwrap();
+
+		assert_noop!(
+			Dex::ramp_amplification(RuntimeOrigin::signed(1), lp_id, 1000, 21),
+			DispatchError::BadOrigin
+		);
+		// ramps take at least `MinAmplificationRamp` blocks and move at most tenfold
+		assert_noop!(
+			Dex::ramp_amplification(RuntimeOrigin::root(), lp_id, 1000, 10),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_noop!(
+			Dex::ramp_amplification(RuntimeOrigin::root(), lp_id, 1001, 21),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_noop!(
+			Dex::ramp_amplification(RuntimeOrigin::root(), lp_id, 9, 21),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_noop!(
+			Dex::ramp_amplification(RuntimeOrigin::root(), lp_id, 0, 21),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_ok!(Dex::create_pool(RuntimeOrigin::signed(1), Asset(1), Asset(3), 5));
+		assert_noop!(
+			Dex::ramp_amplification(
+				RuntimeOrigin::root(),
+				Dex::get_lp_id(&Asset(1), &Asset(3), 5).unwrap(),
+				1000,
+				21
+			),
+			Error::<Test>::UnsupportedPoolKind
+		);
+
+		assert_ok!(Dex::ramp_amplification(RuntimeOrigin::root(), lp_id, 1000, 21));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::AmplificationRamped {
+			lp_id,
+			initial: 100,
+			target: 1000,
+			end: 21,
+		}));
+		assert_eq!(amplification(lp_id).at(11), 550);
+		assert_eq!(amplification(lp_id).at(30), 1000);
+
+		// a new ramp starts from wherever the current one has got to
+		System::set_block_number(11);
+		assert_ok!(Dex::ramp_amplification(RuntimeOrigin::root(), lp_id, 200, 31));
+		assert_eq!(
+			amplification(lp_id),
+			Amplification { initial: 550, target: 200, start: 11, end: 31 }
+		);
+		assert_eq!(amplification(lp_id).at(21), 375);
+
+		// a higher coefficient keeps prices closer to 1:1
+		System::set_block_number(31);
+		assert_ok!(Dex::ramp_amplification(RuntimeOrigin::root(), lp_id, 2000, 41));
+		System::set_block_number(41);
+		let quote_after = Dex::quote_exact_in(Asset(1), Asset(2), 5, 50_000).unwrap();
+		assert!(quote_after.amount_out > quote_before.amount_out);
+	});
+}
+
+#[test]
+fn migrate_to_v3() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(2).put::<Dex>();
+		let pool_pair = PoolPair::<Test>::new(Asset(1), 100, Asset(2), 300).unwrap();
+		migrations::OldPoolMap::<Test>::insert(7, old_pool(pool_pair, FEE_TIER));
+
+		migrations::v3::MigrateToV3::<Test>::on_runtime_upgrade();
+
+		assert_eq!(Dex::on_chain_storage_version(), 3);
+		let pool = PoolMap::<Test>::get(7).unwrap();
+		assert!(matches!(pool.kind, PoolKind::ConstantProduct));
+		assert_eq!(pool.reserves(), vec![(Asset(1), 100), (Asset(2), 300)]);
+		assert_eq!(pool.lp_supply, 100);
+		assert_eq!(pool.fee_tier, FEE_TIER);
+	});
+}
+
+// `actual` is within the rounding of the weighted pool maths of `expected`
+fn assert_about(actual: u128, expected: u128) {
+	assert!(actual.abs_diff(expected) <= 2, "{actual} is not about {expected}");
+}
+
+#[test]
+fn weighted_pools() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let balances = (1..=4).map(|asset| (asset, 10_000_000)).collect::<Vec<_>>();
+		assert_ok!(Dex::setup_account(1, balances.clone()));
+		assert_ok!(Dex::setup_account(2, balances));
+		let weighted = |assets: Vec<(u32, u32)>| -> BoundedVec<_, _> {
+			assets
+				.into_iter()
+				.map(|(asset, percent)| (Asset(asset), Perbill::from_percent(percent)))
+				.collect::<Vec<_>>()
+				.try_into()
+				.unwrap()
+		};
+		let amounts = |amounts: Vec<(u32, u128)>| -> BoundedVec<_, _> {
+			amounts
+				.into_iter()
+				.map(|(asset, amount)| (Asset(asset), amount))
+				.collect::<Vec<_>>()
+				.try_into()
+				.unwrap()
+		};
+
+		for assets in [
+			vec![(1, 100)],
+			vec![(1, 80), (2, 30)],
+			vec![(1, 80), (2, 10)],
+			vec![(1, 100), (2, 0)],
+			vec![(1, 50), (1, 50)],
+		] {
+			assert_noop!(
+				Dex::create_weighted_pool(RuntimeOrigin::signed(1), weighted(assets), 30),
+				Error::<Test>::InvalidWeightedPool
+			);
+		}
+
+		// an 80/20 pool holds four times the value of the first asset as of the second
+		assert_ok!(Dex::create_weighted_pool(
+			RuntimeOrigin::signed(1),
+			weighted(vec![(2, 20), (1, 80)]),
+			30
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), 30).unwrap();
+		let pool = PoolMap::<Test>::get(lp_id).unwrap();
+		assert_eq!(pool.weight(&Asset(1)), Some(Perbill::from_percent(80)));
+		assert_eq!(pool.weight(&Asset(2)), Some(Perbill::from_percent(20)));
+
+		// the first deposit must fund every asset
+		assert_noop!(
+			Dex::add_weighted_liquidity(
+				RuntimeOrigin::signed(1),
+				lp_id,
+				1_000_000,
+				amounts(vec![(1, 800_000)])
+			),
+			Error::<Test>::InvalidWeightedPool
+		);
+		assert_noop!(
+			Dex::add_weighted_liquidity(
+				RuntimeOrigin::signed(1),
+				lp_id,
+				10,
+				amounts(vec![(1, 800_000), (2, 200_000)])
+			),
+			Error::<Test>::InsufficientLiquidityMinted
+		);
+		assert_ok!(Dex::add_weighted_liquidity(
+			RuntimeOrigin::signed(1),
+			lp_id,
+			1_000_000,
+			amounts(vec![(1, 800_000), (2, 200_000)])
+		));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::WeightedLiquidityAdded {
+			who: 1,
+			lp_id,
+			amounts: amounts(vec![(1, 800_000), (2, 200_000)]).into_inner(),
+			amount_lp: 999_990,
+		}));
+		// with their values at their weights, the assets are priced the same
+		assert_eq!(
+			Dex::spot_price(&Asset(1), &Asset(2), 30),
+			Ok((FixedU128::one(), FixedU128::one()))
+		);
+
+		// swaps follow the weighted product
+		let quote = Dex::quote_exact_in(Asset(2), Asset(1), 30, 10_000).unwrap();
+		assert_about(quote.amount_out, 9670);
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(2),
+			Asset(1),
+			30,
+			10_000,
+			quote.amount_out
+		));
+		assert_eq!(Assets::total_balance(1, &2), 10_000_000 + quote.amount_out);
+		let quote = Dex::quote_exact_out(Asset(1), Asset(2), 30, 10_000).unwrap();
+		assert_about(quote.amount_in, 9730);
+		assert_ok!(Dex::swap_in_for_exact_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			30,
+			quote.amount_in,
+			10_000
+		));
+		assert_eq!(Dex::get_reserves(Asset(2), Asset(1), 30).unwrap().0, 200_000);
+		// at most half a reserve goes in and a third comes out
+		assert_eq!(Dex::quote_exact_in(Asset(2), Asset(1), 30, 200_000), None);
+		assert_noop!(
+			Dex::swap_exact_in_for_out(
+				RuntimeOrigin::signed(2),
+				Asset(2),
+				Asset(1),
+				30,
+				200_000,
+				0
+			),
+			Error::<Test>::TradeTooLarge
+		);
+		assert_noop!(
+			Dex::swap_in_for_exact_out(
+				RuntimeOrigin::signed(2),
+				Asset(2),
+				Asset(1),
+				30,
+				1_000_000,
+				300_000
+			),
+			Error::<Test>::TradeTooLarge
+		);
+
+		// joining in proportion pays the same share of every reserve, rounded up
+		let (reserve_1, reserve_2) = Dex::get_reserves(Asset(1), Asset(2), 30).unwrap();
+		assert_noop!(
+			Dex::add_weighted_liquidity(
+				RuntimeOrigin::signed(2),
+				lp_id,
+				100_000,
+				amounts(vec![(2, 19_999)])
+			),
+			Error::<Test>::SlippageTooHigh
+		);
+		assert_ok!(Dex::add_weighted_liquidity(
+			RuntimeOrigin::signed(2),
+			lp_id,
+			100_000,
+			amounts(vec![(2, 20_000)])
+		));
+		assert_eq!(
+			Dex::get_reserves(Asset(1), Asset(2), 30),
+			Some((reserve_1 + (reserve_1 + 9) / 10, reserve_2 + 20_000))
+		);
+		assert_eq!(Assets::total_balance(lp_id, &2), 100_000);
+		assert_noop!(
+			Dex::add_liquidity(RuntimeOrigin::signed(2), Asset(1), Asset(2), 30, 10, 10, 0, 0),
+			Error::<Test>::UnsupportedPoolKind
+		);
+
+		// a single asset can be deposited, its part beyond its weight paying the fee
+		assert_noop!(
+			Dex::add_weighted_liquidity_single(
+				RuntimeOrigin::signed(2),
+				lp_id,
+				Asset(2),
+				110_001,
+				0
+			),
+			Error::<Test>::TradeTooLarge
+		);
+		assert_noop!(
+			Dex::add_weighted_liquidity_single(RuntimeOrigin::signed(2), lp_id, Asset(3), 100, 0),
+			Error::<Test>::AssetNotInPool
+		);
+		assert_ok!(Dex::add_weighted_liquidity_single(
+			RuntimeOrigin::signed(2),
+			lp_id,
+			Asset(2),
+			10_000,
+			9_790
+		));
+		assert_about(Assets::total_balance(lp_id, &2), 109_799);
+
+		// or withdrawn
+		let balance_1 = Assets::total_balance(1, &2);
+		assert_ok!(Dex::remove_weighted_liquidity_single(
+			RuntimeOrigin::signed(2),
+			lp_id,
+			10_000,
+			Asset(1),
+			9_890
+		));
+		assert_about(Assets::total_balance(1, &2) - balance_1, 9895);
+		assert_noop!(
+			Dex::remove_weighted_liquidity_single(
+				RuntimeOrigin::signed(1),
+				lp_id,
+				500_000,
+				Asset(2),
+				0
+			),
+			Error::<Test>::TradeTooLarge
+		);
+
+		// the pool's account holds exactly its reserves
+		let pool = PoolMap::<Test>::get(lp_id).unwrap();
+		let reserves = pool.reserves().into_iter().map(|(_, reserve)| reserve).collect::<Vec<_>>();
+		assert_eq!(Dex::pool_balances(&lp_id, &pool), reserves);
+
+		// leaving in proportion pays out every reserve, through the pair call as well
+		let lp_balance = Assets::total_balance(lp_id, &2);
+		assert_noop!(
+			Dex::remove_stable_liquidity(
+				RuntimeOrigin::signed(2),
+				lp_id,
+				lp_balance,
+				BoundedVec::default()
+			),
+			Error::<Test>::UnsupportedPoolKind
+		);
+		assert_ok!(Dex::remove_weighted_liquidity(
+			RuntimeOrigin::signed(2),
+			lp_id,
+			lp_balance / 2,
+			amounts(vec![(1, 1)])
+		));
+		let expected = pool
+			.reserves()
+			.into_iter()
+			.map(|(asset, reserve)| (asset, reserve * (lp_balance / 2) / pool.lp_supply))
+			.collect::<Vec<_>>();
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::WeightedLiquidityRemoved {
+			who: 2,
+			lp_id,
+			amount_lp: lp_balance / 2,
+			amounts: expected,
+		}));
+		let lp_balance = Assets::total_balance(lp_id, &2);
+		assert_ok!(Dex::remove_liquidity(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			30,
+			lp_balance,
+			0,
+			0
+		));
+		assert_eq!(Assets::total_balance(lp_id, &2), 0);
+	});
+}
+
+#[test]
+fn weighted_pool_of_four_assets() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let balances = (1..=6).map(|asset| (asset, 10_000_000)).collect::<Vec<_>>();
+		assert_ok!(Dex::setup_account(1, balances));
+		let assets: BoundedVec<_, _> = (1..=4)
+			.map(|asset| (Asset(asset), Perbill::from_percent(25)))
+			.collect::<Vec<_>>()
+			.try_into()
+			.unwrap();
+		assert_ok!(Dex::create_weighted_pool(RuntimeOrigin::signed(1), assets, 5));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), 5).unwrap();
+		for (asset_a, asset_b) in [(1, 3), (1, 4), (2, 3), (2, 4), (3, 4)] {
+			assert_eq!(Dex::get_lp_id(&Asset(asset_a), &Asset(asset_b), 5), Ok(lp_id));
+		}
+		let amounts: BoundedVec<_, _> = (1..=4)
+			.map(|asset| (Asset(asset), 1_000_000))
+			.collect::<Vec<_>>()
+			.try_into()
+			.unwrap();
+		assert_ok!(Dex::add_weighted_liquidity(
+			RuntimeOrigin::signed(1),
+			lp_id,
+			1_000_000,
+			amounts
+		));
+
+		// assets of equal weight trade like a constant product pool of the two
+		assert_ok!(Dex::add_liquidity(
+			RuntimeOrigin::signed(1),
+			Asset(5),
+			Asset(6),
+			5,
+			1_000_000,
+			1_000_000,
+			0,
+			0
+		));
+		let constant_product_out =
+			Dex::quote_exact_in(Asset(5), Asset(6), 5, 10_000).unwrap().amount_out;
+		let weighted_out = Dex::quote_exact_in(Asset(1), Asset(3), 5, 10_000).unwrap().amount_out;
+		assert!(weighted_out <= constant_product_out && weighted_out + 1 >= constant_product_out);
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(1),
+			Asset(1),
+			Asset(3),
+			5,
+			10_000,
+			weighted_out
+		));
+		assert_eq!(
+			Dex::get_reserves(Asset(1), Asset(3), 5),
+			Some((1_010_000, 1_000_000 - weighted_out))
+		);
+		// the assets the swap didn't touch keep their prices against each other
+		assert_eq!(
+			Dex::spot_price(&Asset(2), &Asset(4), 5),
+			Ok((FixedU128::one(), FixedU128::one()))
+		);
+		assert!(Dex::spot_price(&Asset(1), &Asset(2), 5).unwrap().0 < FixedU128::one());
+
+		// only constant product pools can be zapped into
+		assert_noop!(
+			Dex::zap_in(RuntimeOrigin::signed(1), Asset(1), 1000, Asset(2), 5, 0),
+			Error::<Test>::UnsupportedPoolKind
+		);
+		assert_noop!(
+			Dex::add_stable_liquidity(
+				RuntimeOrigin::signed(1),
+				lp_id,
+				vec![(Asset(1), 1000)].try_into().unwrap(),
+				0
+			),
+			Error::<Test>::UnsupportedPoolKind
+		);
+	});
+}
+
+#[test]
+fn concentrated_liquidity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Dex::setup_account(1, vec![(1, 10_000_000), (2, 10_000_000)]));
+		assert_ok!(Dex::setup_account(2, vec![(1, 10_000_000), (2, 10_000_000)]));
+		let quarter = FixedU128::from_rational(1, 4);
+
+		assert_noop!(
+			Dex::create_concentrated_pool(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(1),
+				30,
+				quarter
+			),
+			Error::<Test>::SameAsset
+		);
+		assert_noop!(
+			Dex::create_concentrated_pool(RuntimeOrigin::signed(1), Asset(2), Asset(1), 7, quarter),
+			Error::<Test>::InvalidFeeTier
+		);
+		assert_noop!(
+			Dex::create_concentrated_pool(
+				RuntimeOrigin::signed(1),
+				Asset(2),
+				Asset(1),
+				30,
+				FixedU128::from_inner(0)
+			),
+			Error::<Test>::InvalidPrice
+		);
+
+		// asset 2 at a quarter of asset 1 is asset 1 at four of asset 2, a square root of two
+		assert_ok!(Dex::create_concentrated_pool(
+			RuntimeOrigin::signed(1),
+			Asset(2),
+			Asset(1),
+			30,
+			quarter
+		));
+		let lp_id = Dex::get_lp_id(&Asset(1), &Asset(2), 30).unwrap();
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::ConcentratedPoolCreated {
+			who: 1,
+			asset_a: Asset(2),
+			asset_b: Asset(1),
+			fee_tier: 30,
+			price: quarter,
+			lp_id,
+			deposit: 0,
+		}));
+		let concentrated = |lp_id: u32| {
+			let PoolKind::Concentrated(concentrated) = PoolMap::<Test>::get(lp_id).unwrap().kind
+			else {
+				panic!("the pool is a concentrated pool")
+			};
+			concentrated
+		};
+		assert_eq!(concentrated(lp_id).sqrt_price, U256::from(2) << 96);
+		assert_eq!(concentrated(lp_id).tick, 13_863);
+		assert_eq!(
+			Dex::spot_price(&Asset(1), &Asset(2), 30),
+			Ok((FixedU128::from_u32(4), quarter))
+		);
+		// positions take the place of LP tokens
+		assert!(!Assets::asset_exists(lp_id));
+		assert_noop!(
+			Dex::create_concentrated_pool(
+				RuntimeOrigin::signed(1),
+				Asset(1),
+				Asset(2),
+				30,
+				quarter
+			),
+			Error::<Test>::PoolExists
+		);
+
+		// ticks are multiples of twice the fee tier
+		for (tick_lower, tick_upper) in [(13_501, 14_220), (14_220, 13_500), (13_500, 13_500)] {
+			assert_noop!(
+				Dex::mint_position(
+					RuntimeOrigin::signed(1),
+					lp_id,
+					tick_lower,
+					tick_upper,
+					10_000_000,
+					u128::MAX,
+					u128::MAX
+				),
+				Error::<Test>::InvalidTickRange
+			);
+		}
+		assert_noop!(
+			Dex::mint_position(RuntimeOrigin::signed(1), lp_id, 13_500, 14_220, 0, 0, 0),
+			Error::<Test>::InsufficientLiquidityMinted
+		);
+		assert_noop!(
+			Dex::mint_position(
+				RuntimeOrigin::signed(1),
+				lp_id,
+				13_500,
+				14_220,
+				10_000_000,
+				88_297,
+				u128::MAX
+			),
+			Error::<Test>::SlippageTooHigh
+		);
+
+		// a range around the price takes both assets, one below it only the second and one
+		// above it only the first
+		assert_ok!(Dex::mint_position(
+			RuntimeOrigin::signed(1),
+			lp_id,
+			13_500,
+			14_220,
+			10_000_000,
+			88_298,
+			360_334
+		));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PositionMinted {
+			who: 1,
+			lp_id,
+			position_id: 0,
+			tick_lower: 13_500,
+			tick_upper: 14_220,
+			liquidity: 10_000_000,
+			amount_1: 88_298,
+			amount_2: 360_334,
+		}));
+		assert_ok!(Dex::mint_position(
+			RuntimeOrigin::signed(1),
+			lp_id,
+			12_000,
+			13_200,
+			5_000_000,
+			0,
+			563_322
+		));
+		assert_ok!(Dex::mint_position(
+			RuntimeOrigin::signed(1),
+			lp_id,
+			14_400,
+			15_000,
+			5_000_000,
+			71_928,
+			0
+		));
+		assert_eq!(concentrated(lp_id).liquidity, 10_000_000);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), 30), Some((160_226, 923_656)));
+		assert_eq!(Assets::total_balance(1, &1), 10_000_000 - 160_226);
+		assert_eq!(Assets::total_balance(2, &1), 10_000_000 - 923_656);
+		assert_eq!(Positions::<Test>::iter_prefix(1).count(), 3);
+		assert_eq!(NextPositionId::<Test>::get(), 3);
+		// the pool is only joined through positions
+		assert_noop!(
+			Dex::add_liquidity(RuntimeOrigin::signed(1), Asset(1), Asset(2), 30, 1000, 1000, 0, 0),
+			Error::<Test>::UnsupportedPoolKind
+		);
+		assert_noop!(
+			Dex::sync(RuntimeOrigin::signed(1), lp_id),
+			Error::<Test>::UnsupportedPoolKind
+		);
+
+		// buying asset 1 moves the price up out of the first range, over the gap above it and
+		// into the third
+		let quote = Dex::quote_exact_in(Asset(2), Asset(1), 30, 500_000).unwrap();
+		assert_eq!(quote.amount_out, 120_783);
+		assert_ok!(Dex::swap_exact_in_for_out(
+			RuntimeOrigin::signed(2),
+			Asset(2),
+			Asset(1),
+			30,
+			500_000,
+			120_783
+		));
+		assert_eq!(Assets::total_balance(1, &2), 10_000_000 + 120_783);
+		assert_eq!(Assets::total_balance(2, &2), 10_000_000 - 500_000);
+		assert_eq!(concentrated(lp_id).tick, 14_668);
+		assert_eq!(concentrated(lp_id).liquidity, 5_000_000);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), 30), Some((39_443, 1_423_656)));
+
+		// and selling it back crosses down into the first range again
+		assert_eq!(
+			Dex::quote_exact_out(Asset(1), Asset(2), 30, 280_000).unwrap().amount_in,
+			66_953
+		);
+		assert_ok!(Dex::swap_in_for_exact_out(
+			RuntimeOrigin::signed(2),
+			Asset(1),
+			Asset(2),
+			30,
+			66_953,
+			280_000
+		));
+		assert_eq!(concentrated(lp_id).tick, 14_080);
+		assert_eq!(concentrated(lp_id).liquidity, 10_000_000);
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), 30), Some((106_396, 1_143_656)));
+		// the positions can't pay out more than they hold
+		assert_noop!(
+			Dex::swap_in_for_exact_out(
+				RuntimeOrigin::signed(2),
+				Asset(2),
+				Asset(1),
+				30,
+				u128::MAX,
+				106_396
+			),
+			Error::<Test>::InsufficientLiquidity
+		);
+
+		// every position earned the fees paid while the price was in its range
+		assert_ok!(Dex::collect_fees(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::FeesCollected {
+			who: 1,
+			position_id: 0,
+			amount_1: 103,
+			amount_2: 1081,
+		}));
+		assert_eq!(Assets::total_balance(1, &1), 10_000_000 - 160_226 + 103);
+		assert_eq!(Assets::total_balance(2, &1), 10_000_000 - 923_656 + 1081);
+		assert_ok!(Dex::collect_fees(RuntimeOrigin::signed(1), 0));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::FeesCollected {
+			who: 1,
+			position_id: 0,
+			amount_1: 0,
+			amount_2: 0,
+		}));
+
+		// only the owner can burn a position, and no more of it than there is
+		assert_noop!(
+			Dex::burn_position(RuntimeOrigin::signed(2), 2, 5_000_000, 0, 0),
+			Error::<Test>::NoPosition
+		);
+		assert_noop!(
+			Dex::burn_position(RuntimeOrigin::signed(1), 2, 5_000_001, 0, 0),
+			Error::<Test>::InsufficientPositionLiquidity
+		);
+		assert_noop!(
+			Dex::burn_position(RuntimeOrigin::signed(1), 2, 5_000_000, 71_928, 0),
+			Error::<Test>::SlippageTooHigh
+		);
+		// burning a whole position pays out its fees as well
+		assert_ok!(Dex::burn_position(RuntimeOrigin::signed(1), 2, 5_000_000, 71_927, 0));
+		System::assert_has_event(mock::RuntimeEvent::Dex(Event::PositionBurned {
+			who: 1,
+			position_id: 2,
+			liquidity: 5_000_000,
+			amount_1: 71_927,
+			amount_2: 0,
+		}));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::FeesCollected {
+			who: 1,
+			position_id: 2,
+			amount_1: 97,
+			amount_2: 418,
+		}));
+		assert!(!Positions::<Test>::contains_key(1, 2));
+		let ticks: Vec<_> = concentrated(lp_id).ticks.iter().map(|(tick, _)| *tick).collect();
+		assert_eq!(ticks, vec![12_000, 13_200, 13_500, 14_220]);
+
+		// a pool can't be destroyed while it has positions
+		assert_noop!(
+			Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), 30),
+			Error::<Test>::PoolNotEmpty
+		);
+		assert_ok!(Dex::burn_position(RuntimeOrigin::signed(1), 0, 5_000_000, 0, 0));
+		System::assert_last_event(mock::RuntimeEvent::Dex(Event::PositionBurned {
+			who: 1,
+			position_id: 0,
+			liquidity: 5_000_000,
+			amount_1: 17_131,
+			amount_2: 289_415,
 		}));
+		assert_eq!(Positions::<Test>::get(1, 0).unwrap().liquidity, 5_000_000);
+		assert_eq!(concentrated(lp_id).liquidity, 5_000_000);
+		assert_ok!(Dex::burn_position(RuntimeOrigin::signed(1), 0, 5_000_000, 0, 0));
+		assert_ok!(Dex::burn_position(RuntimeOrigin::signed(1), 1, 5_000_000, 0, 563_321));
+		assert_eq!(concentrated(lp_id).liquidity, 0);
+		assert!(concentrated(lp_id).ticks.is_empty());
+		// what rounding left in the pool stays there until the pool is destroyed, and then goes
+		// to its creator
+		assert_eq!(Dex::get_reserves(Asset(1), Asset(2), 30), Some((7, 6)));
+		assert_eq!(Assets::total_balance(1, &1) + Assets::total_balance(1, &2), 20_000_000 - 7);
+		assert_eq!(Assets::total_balance(2, &1) + Assets::total_balance(2, &2), 20_000_000 - 6);
+		assert_ok!(Dex::destroy_pool(RuntimeOrigin::signed(1), Asset(1), Asset(2), 30));
+		assert!(!PoolMap::<Test>::contains_key(lp_id));
+		assert_eq!(Assets::total_balance(1, &1) + Assets::total_balance(1, &2), 20_000_000);
+		assert_eq!(Assets::total_balance(2, &1) + Assets::total_balance(2, &2), 20_000_000);
 	});
 }
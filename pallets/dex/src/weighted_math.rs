@@ -0,0 +1,166 @@
+//! The weighted product invariant of Balancer, for pools of any number of assets held at fixed
+//! shares of the pool's value.
+//!
+//! With reserves `b_i` and normalized weights `w_i` adding up to one, a pool trades so that
+//! `prod(b_i^w_i)` stays the same, and the spot price of asset `i` in asset `o` is
+//! `(b_o / w_o) / (b_i / w_i)`. Two assets weighing half each give the constant product. The
+//! formulas are those of Balancer V1, with powers of non-integer exponents worked out in
+//! `FixedU128` as its `bpow` does. Balances are raw `u128` amounts, only ever multiplied by
+//! ratios, and `None` means the maths is out of range or overflowed. Results are rounded in the
+//! pool's favour.
+
+use frame_support::sp_runtime::{
+	traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Saturating, Zero},
+	FixedPointNumber, FixedU128, Perbill,
+};
+
+/// The most that can be paid into a reserve in one trade, as a share of it. It keeps the bases
+/// of the powers below two, where their series converges.
+pub const MAX_IN_RATIO: Perbill = Perbill::from_percent(50);
+
+/// The most that can be taken out of a reserve in one trade, as a share of it.
+pub const MAX_OUT_RATIO: Perbill = Perbill::from_parts(333_333_333);
+
+// the series for the fractional part of a power stops at terms smaller than this, 10^-10
+const POW_PRECISION: FixedU128 = FixedU128::from_inner(100_000_000);
+
+// the series converges well before this, it only stops it looping forever
+const MAX_ITERATIONS: u32 = 255;
+
+// `a / b` for weights
+fn weight_ratio(a: Perbill, b: Perbill) -> Option<FixedU128> {
+	FixedU128::checked_from_rational(a.deconstruct(), b.deconstruct())
+}
+
+// `|a - b|` and whether it is negative
+fn difference(a: FixedU128, b: FixedU128) -> (FixedU128, bool) {
+	if a >= b {
+		(a.saturating_sub(b), false)
+	} else {
+		(b.saturating_sub(a), true)
+	}
+}
+
+// `base^exponent` for a whole `exponent`, by squaring
+fn pow_whole(mut base: FixedU128, mut exponent: u128) -> Option<FixedU128> {
+	let mut power = FixedU128::one();
+	while exponent > 0 {
+		if exponent & 1 == 1 {
+			power = power.checked_mul(&base)?;
+		}
+		exponent >>= 1;
+		if exponent > 0 {
+			base = base.checked_mul(&base)?;
+		}
+	}
+	Some(power)
+}
+
+// `base^exponent` for an `exponent` below one, summing the binomial series of `(1 + x)^exponent`
+// with `x = base - 1` until its terms are below `POW_PRECISION`
+fn pow_fraction(base: FixedU128, exponent: FixedU128) -> Option<FixedU128> {
+	let one = FixedU128::one();
+	let (x, x_negative) = difference(base, one);
+	let mut term = one;
+	let mut sum = one;
+	let mut negative = false;
+	for k in 1..=MAX_ITERATIONS {
+		let big_k = FixedU128::saturating_from_integer(k);
+		let (c, c_negative) = difference(exponent, big_k.saturating_sub(one));
+		term = term.checked_mul(&c.checked_mul(&x)?)?.checked_div(&big_k)?;
+		if term.is_zero() {
+			break
+		}
+		if x_negative {
+			negative = !negative;
+		}
+		if c_negative {
+			negative = !negative;
+		}
+		sum = if negative { sum.checked_sub(&term)? } else { sum.checked_add(&term)? };
+		if term < POW_PRECISION {
+			break
+		}
+	}
+	Some(sum)
+}
+
+/// `base^exponent` for a `base` between zero and two, both exclusive.
+pub fn pow(base: FixedU128, exponent: FixedU128) -> Option<FixedU128> {
+	if base.is_zero() || base >= FixedU128::saturating_from_integer(2u32) {
+		return None
+	}
+	let whole = exponent.trunc().into_inner() / FixedU128::DIV;
+	let whole_power = pow_whole(base, whole)?;
+	let fraction = exponent.frac();
+	if fraction.is_zero() {
+		return Some(whole_power)
+	}
+	whole_power.checked_mul(&pow_fraction(base, fraction)?)
+}
+
+/// The spot price of the asset of `balance_in` and `weight_in` in the asset of `balance_out`
+/// and `weight_out`, without the fee.
+pub fn spot_price(
+	balance_in: u128,
+	weight_in: Perbill,
+	balance_out: u128,
+	weight_out: Perbill,
+) -> Option<FixedU128> {
+	FixedU128::checked_from_rational(balance_out, balance_in)?
+		.checked_mul(&weight_ratio(weight_in, weight_out)?)
+}
+
+/// The output of paying `amount_in`, after the fee, into the reserve of `balance_in`:
+/// `b_o * (1 - (b_i / (b_i + a_i))^(w_i / w_o))`.
+pub fn out_given_in(
+	balance_in: u128,
+	weight_in: Perbill,
+	balance_out: u128,
+	weight_out: Perbill,
+	amount_in: u128,
+) -> Option<u128> {
+	let base = FixedU128::checked_from_rational(balance_in, balance_in.checked_add(amount_in)?)?;
+	let power = pow(base, weight_ratio(weight_in, weight_out)?)?;
+	FixedU128::one().saturating_sub(power).checked_mul_int(balance_out)
+}
+
+/// The input, before the fee, needed to take `amount_out` out of the reserve of `balance_out`:
+/// `b_i * ((b_o / (b_o - a_o))^(w_o / w_i) - 1)`.
+pub fn in_given_out(
+	balance_in: u128,
+	weight_in: Perbill,
+	balance_out: u128,
+	weight_out: Perbill,
+	amount_out: u128,
+) -> Option<u128> {
+	let remaining = balance_out.checked_sub(amount_out).filter(|remaining| !remaining.is_zero())?;
+	let base = FixedU128::checked_from_rational(balance_out, remaining)?;
+	let power = pow(base, weight_ratio(weight_out, weight_in)?)?;
+	power
+		.saturating_sub(FixedU128::one())
+		.checked_mul_int(balance_in)?
+		.checked_add(1)
+}
+
+/// The LP tokens minted for paying `amount_in`, after the fee, into the reserve of `balance`
+/// and `weight`, out of a supply of `supply`: `s * ((b + a) / b)^w - s`.
+pub fn lp_out_given_in(
+	balance: u128,
+	weight: Perbill,
+	supply: u128,
+	amount_in: u128,
+) -> Option<u128> {
+	let base = FixedU128::checked_from_rational(balance.checked_add(amount_in)?, balance)?;
+	let power = pow(base, weight_ratio(weight, Perbill::one())?)?;
+	power.checked_mul_int(supply)?.checked_sub(supply)
+}
+
+/// The amount, before the fee, paid out of the reserve of `balance` and `weight` for burning
+/// `lp_in` of a supply of `supply`: `b - b * ((s - l) / s)^(1 / w)`.
+pub fn out_given_lp_in(balance: u128, weight: Perbill, supply: u128, lp_in: u128) -> Option<u128> {
+	let base = FixedU128::checked_from_rational(supply.checked_sub(lp_in)?, supply)?;
+	let power = pow(base, weight_ratio(Perbill::one(), weight)?)?;
+	let remaining = power.checked_mul_int(balance)?.checked_add(1)?.min(balance);
+	balance.checked_sub(remaining)
+}
@@ -0,0 +1,1031 @@
+
+//! Weights for pallet_dex
+//!
+//! THESE WEIGHTS ARE ESTIMATES. They are laid out like the output of the Substrate benchmark
+//! CLI, but no benchmark run produced them, so they must be regenerated from `benchmarking.rs`
+//! before the pallet is used on a live chain.
+
+// Command to regenerate them, with Substrate's `.maintain/frame-weight-template.hbs` of
+// `polkadot-v1.0.0` as the template:
+// ./target/release/node-template
+// benchmark
+// pallet
+// --chain=dev
+// --steps=50
+// --repeat=20
+// --pallet=pallet_dex
+// --extrinsic=*
+// --execution=wasm
+// --wasm-execution=compiled
+// --output=pallets/dex/src/weights.rs
+// --template=frame-weight-template.hbs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for pallet_dex.
+pub trait WeightInfo {
+	fn add_liquidity_new_pool() -> Weight;
+	fn add_liquidity_existing_pool() -> Weight;
+	fn remove_liquidity() -> Weight;
+	fn swap_exact_in_for_out() -> Weight;
+	fn swap_in_for_exact_out() -> Weight;
+	fn price_oracle() -> Weight;
+	fn set_fee_tiers(n: u32, ) -> Weight;
+	fn flash_loan(n: u32, ) -> Weight;
+	fn swap_exact_in_along_path(n: u32, ) -> Weight;
+	fn swap_in_for_exact_out_along_path(n: u32, ) -> Weight;
+	fn twap_oracle() -> Weight;
+	fn set_protocol_fee() -> Weight;
+	fn zap_in() -> Weight;
+	fn zap_out() -> Weight;
+	fn set_farm() -> Weight;
+	fn stake_lp() -> Weight;
+	fn unstake_lp() -> Weight;
+	fn claim_rewards() -> Weight;
+	fn flash_swap() -> Weight;
+	fn create_pool() -> Weight;
+	fn destroy_pool() -> Weight;
+	fn set_pool_status() -> Weight;
+	fn skim() -> Weight;
+	fn sync() -> Weight;
+	fn create_stable_pool(n: u32, ) -> Weight;
+	fn add_stable_liquidity(n: u32, ) -> Weight;
+	fn remove_stable_liquidity(n: u32, ) -> Weight;
+	fn ramp_amplification() -> Weight;
+	fn create_weighted_pool(n: u32, ) -> Weight;
+	fn add_weighted_liquidity(n: u32, ) -> Weight;
+	fn remove_weighted_liquidity(n: u32, ) -> Weight;
+	fn add_weighted_liquidity_single() -> Weight;
+	fn remove_weighted_liquidity_single() -> Weight;
+	fn create_concentrated_pool() -> Weight;
+	fn mint_position() -> Weight;
+	fn burn_position() -> Weight;
+	fn collect_fees() -> Weight;
+}
+
+/// Weights for pallet_dex using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:6 w:6)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:0 w:1)
+	/// Storage: Assets Metadata (r:3 w:1)
+	/// Storage: Dex PairToLp (r:1 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	fn add_liquidity_new_pool() -> Weight {
+		Weight::from_parts(131_207_000, 22_614)
+			.saturating_add(T::DbWeight::get().reads(23_u64))
+			.saturating_add(T::DbWeight::get().writes(21_u64))
+	}
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:6 w:5)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn add_liquidity_existing_pool() -> Weight {
+		Weight::from_parts(104_873_000, 16_902)
+			.saturating_add(T::DbWeight::get().reads(15_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: Assets Account (r:6 w:5)
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn remove_liquidity() -> Weight {
+		Weight::from_parts(101_316_000, 16_902)
+			.saturating_add(T::DbWeight::get().reads(15_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn swap_exact_in_for_out() -> Weight {
+		Weight::from_parts(68_254_000, 11_736)
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn swap_in_for_exact_out() -> Weight {
+		Weight::from_parts(69_087_000, 11_736)
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn price_oracle() -> Weight {
+		Weight::from_parts(24_615_000, 5_146)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+	}
+	/// Storage: Dex FeeTiers (r:0 w:1)
+	/// The range of component `n` is `[0, 8]`.
+	fn set_fee_tiers(n: u32, ) -> Weight {
+		Weight::from_parts(11_932_000, 0)
+			.saturating_add(Weight::from_parts(38_516, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:1)
+	/// Storage: Dex PoolMap (r:2000 w:1000)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:1002 w:1002)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1000 w:1000)
+	/// The range of component `n` is `[1, 1000]`.
+	fn flash_loan(n: u32, ) -> Weight {
+		Weight::from_parts(41_286_000, 6_208)
+			.saturating_add(Weight::from_parts(35_619_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().reads((4_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_866).saturating_mul(n.into()))
+	}
+	/// Storage: Dex PoolMap (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:3 w:3)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:6 w:6)
+	/// Storage: Dex PriceWindows (r:3 w:3)
+	/// Storage: Dex PairToLp (r:3 w:0)
+	/// The range of component `n` is `[2, 4]`.
+	fn swap_exact_in_along_path(n: u32, ) -> Weight {
+		Weight::from_parts(37_402_000, 7_166)
+			.saturating_add(Weight::from_parts(24_118_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+			.saturating_add(T::DbWeight::get().writes((4_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2_285).saturating_mul(n.into()))
+	}
+	/// Storage: Dex PoolMap (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:3 w:3)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:6 w:6)
+	/// Storage: Dex PriceWindows (r:3 w:3)
+	/// Storage: Dex PairToLp (r:3 w:0)
+	/// The range of component `n` is `[2, 4]`.
+	fn swap_in_for_exact_out_along_path(n: u32, ) -> Weight {
+		Weight::from_parts(38_118_000, 7_166)
+			.saturating_add(Weight::from_parts(24_377_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+			.saturating_add(T::DbWeight::get().writes((4_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2_285).saturating_mul(n.into()))
+	}
+	/// Storage: Dex PoolMap (r:1 w:0)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:0)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn twap_oracle() -> Weight {
+		Weight::from_parts(27_340_000, 6_422)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+	}
+	/// Storage: Dex ProtocolFeeShare (r:0 w:1)
+	/// Storage: Dex ProtocolFeeRecipient (r:0 w:1)
+	fn set_protocol_fee() -> Weight {
+		Weight::from_parts(13_207_000, 0)
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:5 w:5)
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn zap_in() -> Weight {
+		Weight::from_parts(163_581_000, 16_902)
+			.saturating_add(T::DbWeight::get().reads(15_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+	/// Storage: Assets Account (r:6 w:5)
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn zap_out() -> Weight {
+		Weight::from_parts(158_992_000, 16_902)
+			.saturating_add(T::DbWeight::get().reads(16_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:0)
+	/// Storage: Dex Farms (r:1 w:1)
+	fn set_farm() -> Weight {
+		Weight::from_parts(21_874_000, 7_340)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex Farms (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:0)
+	/// Storage: Assets Account (r:1 w:0)
+	/// Storage: Dex Stakes (r:1 w:1)
+	fn stake_lp() -> Weight {
+		Weight::from_parts(36_418_000, 8_924)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex Farms (r:1 w:1)
+	/// Storage: Dex Stakes (r:1 w:1)
+	fn unstake_lp() -> Weight {
+		Weight::from_parts(29_655_000, 7_882)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex Farms (r:1 w:1)
+	/// Storage: Dex Stakes (r:1 w:1)
+	/// Storage: Dex RewardVesting (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn claim_rewards() -> Weight {
+		Weight::from_parts(58_209_000, 11_514)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:1)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn flash_swap() -> Weight {
+		Weight::from_parts(84_107_000, 11_736)
+			.saturating_add(T::DbWeight::get().reads(12_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Assets Metadata (r:3 w:1)
+	/// Storage: Dex PairToLp (r:1 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	fn create_pool() -> Weight {
+		Weight::from_parts(54_318_000, 7_412)
+			.saturating_add(T::DbWeight::get().reads(14_u64))
+			.saturating_add(T::DbWeight::get().writes(12_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:1 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Assets Account (r:5 w:5)
+	/// Storage: Dex Observations (r:0 w:1)
+	/// Storage: Dex ObservationIndex (r:0 w:1)
+	/// Storage: Assets Metadata (r:1 w:1)
+	/// Storage: Dex PairToLp (r:0 w:1)
+	/// Storage: Dex LpToPair (r:1 w:1)
+	/// Storage: Dex Stakes (r:1 w:0)
+	/// Storage: Dex Farms (r:0 w:1)
+	/// Storage: Dex PriceWindows (r:0 w:1)
+	fn destroy_pool() -> Weight {
+		Weight::from_parts(93_614_000, 10_350)
+			.saturating_add(T::DbWeight::get().reads(20_u64))
+			.saturating_add(T::DbWeight::get().writes(24_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Dex PriceWindows (r:0 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn set_pool_status() -> Weight {
+		Weight::from_parts(18_437_000, 5_146)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn skim() -> Weight {
+		Weight::from_parts(43_271_000, 10_442)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:0)
+	/// Storage: Dex PriceWindows (r:0 w:1)
+	fn sync() -> Weight {
+		Weight::from_parts(31_806_000, 8_694)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PairToLp (r:6 w:6)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Assets Metadata (r:5 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	/// Storage: Dex PoolMap (r:0 w:1)
+	/// The range of component `n` is `[2, 4]`.
+	fn create_stable_pool(n: u32, ) -> Weight {
+		Weight::from_parts(49_112_000, 7_412)
+			.saturating_add(Weight::from_parts(5_926_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2_603).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:5 w:5)
+	/// Storage: Assets Account (r:10 w:10)
+	/// The range of component `n` is `[2, 4]`.
+	fn add_stable_liquidity(n: u32, ) -> Weight {
+		Weight::from_parts(61_530_000, 8_694)
+			.saturating_add(Weight::from_parts(38_204_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_180).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:5 w:5)
+	/// Storage: Assets Account (r:9 w:9)
+	/// The range of component `n` is `[2, 4]`.
+	fn remove_stable_liquidity(n: u32, ) -> Weight {
+		Weight::from_parts(48_817_000, 8_694)
+			.saturating_add(Weight::from_parts(21_395_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_180).saturating_mul(n.into()))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	fn ramp_amplification() -> Weight {
+		Weight::from_parts(16_902_000, 5_146)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PairToLp (r:6 w:6)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Assets Metadata (r:5 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	/// Storage: Dex PoolMap (r:0 w:1)
+	/// The range of component `n` is `[2, 8]`.
+	fn create_weighted_pool(n: u32, ) -> Weight {
+		Weight::from_parts(50_384_000, 7_412)
+			.saturating_add(Weight::from_parts(6_113_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2_603).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:5 w:5)
+	/// Storage: Assets Account (r:10 w:10)
+	/// The range of component `n` is `[2, 8]`.
+	fn add_weighted_liquidity(n: u32, ) -> Weight {
+		Weight::from_parts(44_267_000, 8_694)
+			.saturating_add(Weight::from_parts(19_872_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_180).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:5 w:5)
+	/// Storage: Assets Account (r:9 w:9)
+	/// The range of component `n` is `[2, 8]`.
+	fn remove_weighted_liquidity(n: u32, ) -> Weight {
+		Weight::from_parts(49_530_000, 8_694)
+			.saturating_add(Weight::from_parts(21_418_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_180).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn add_weighted_liquidity_single() -> Weight {
+		Weight::from_parts(72_315_000, 13_874)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn remove_weighted_liquidity_single() -> Weight {
+		Weight::from_parts(78_940_000, 13_874)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex PairToLp (r:1 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	fn create_concentrated_pool() -> Weight {
+		Weight::from_parts(112_406_000, 7_412)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Dex NextPositionId (r:1 w:1)
+	/// Storage: Dex Positions (r:0 w:1)
+	fn mint_position() -> Weight {
+		Weight::from_parts(96_752_000, 15_220)
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex Positions (r:1 w:1)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn burn_position() -> Weight {
+		Weight::from_parts(101_384_000, 15_220)
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex Positions (r:1 w:1)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn collect_fees() -> Weight {
+		Weight::from_parts(68_119_000, 15_220)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:6 w:6)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:0 w:1)
+	/// Storage: Assets Metadata (r:3 w:1)
+	/// Storage: Dex PairToLp (r:1 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	fn add_liquidity_new_pool() -> Weight {
+		Weight::from_parts(131_207_000, 22_614)
+			.saturating_add(RocksDbWeight::get().reads(23_u64))
+			.saturating_add(RocksDbWeight::get().writes(21_u64))
+	}
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:6 w:5)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn add_liquidity_existing_pool() -> Weight {
+		Weight::from_parts(104_873_000, 16_902)
+			.saturating_add(RocksDbWeight::get().reads(15_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	/// Storage: Assets Account (r:6 w:5)
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn remove_liquidity() -> Weight {
+		Weight::from_parts(101_316_000, 16_902)
+			.saturating_add(RocksDbWeight::get().reads(15_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn swap_exact_in_for_out() -> Weight {
+		Weight::from_parts(68_254_000, 11_736)
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn swap_in_for_exact_out() -> Weight {
+		Weight::from_parts(69_087_000, 11_736)
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:0)
+	/// Storage: Assets Metadata (r:2 w:0)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn price_oracle() -> Weight {
+		Weight::from_parts(24_615_000, 5_146)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+	}
+	/// Storage: Dex FeeTiers (r:0 w:1)
+	/// The range of component `n` is `[0, 8]`.
+	fn set_fee_tiers(n: u32, ) -> Weight {
+		Weight::from_parts(11_932_000, 0)
+			.saturating_add(Weight::from_parts(38_516, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:1)
+	/// Storage: Dex PoolMap (r:2000 w:1000)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:1002 w:1002)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1000 w:1000)
+	/// The range of component `n` is `[1, 1000]`.
+	fn flash_loan(n: u32, ) -> Weight {
+		Weight::from_parts(41_286_000, 6_208)
+			.saturating_add(Weight::from_parts(35_619_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().reads((4_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_866).saturating_mul(n.into()))
+	}
+	/// Storage: Dex PoolMap (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:3 w:3)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:6 w:6)
+	/// Storage: Dex PriceWindows (r:3 w:3)
+	/// Storage: Dex PairToLp (r:3 w:0)
+	/// The range of component `n` is `[2, 4]`.
+	fn swap_exact_in_along_path(n: u32, ) -> Weight {
+		Weight::from_parts(37_402_000, 7_166)
+			.saturating_add(Weight::from_parts(24_118_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+			.saturating_add(RocksDbWeight::get().writes((4_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2_285).saturating_mul(n.into()))
+	}
+	/// Storage: Dex PoolMap (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:3 w:3)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:6 w:6)
+	/// Storage: Dex PriceWindows (r:3 w:3)
+	/// Storage: Dex PairToLp (r:3 w:0)
+	/// The range of component `n` is `[2, 4]`.
+	fn swap_in_for_exact_out_along_path(n: u32, ) -> Weight {
+		Weight::from_parts(38_118_000, 7_166)
+			.saturating_add(Weight::from_parts(24_377_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+			.saturating_add(RocksDbWeight::get().writes((4_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2_285).saturating_mul(n.into()))
+	}
+	/// Storage: Dex PoolMap (r:1 w:0)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:0)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn twap_oracle() -> Weight {
+		Weight::from_parts(27_340_000, 6_422)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+	}
+	/// Storage: Dex ProtocolFeeShare (r:0 w:1)
+	/// Storage: Dex ProtocolFeeRecipient (r:0 w:1)
+	fn set_protocol_fee() -> Weight {
+		Weight::from_parts(13_207_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Assets Account (r:5 w:5)
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn zap_in() -> Weight {
+		Weight::from_parts(163_581_000, 16_902)
+			.saturating_add(RocksDbWeight::get().reads(15_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+	/// Storage: Assets Account (r:6 w:5)
+	/// Storage: Dex ProtocolFeeRecipient (r:1 w:0)
+	/// Storage: Dex ProtocolFeeShare (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn zap_out() -> Weight {
+		Weight::from_parts(158_992_000, 16_902)
+			.saturating_add(RocksDbWeight::get().reads(16_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:0)
+	/// Storage: Dex Farms (r:1 w:1)
+	fn set_farm() -> Weight {
+		Weight::from_parts(21_874_000, 7_340)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex Farms (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:0)
+	/// Storage: Assets Account (r:1 w:0)
+	/// Storage: Dex Stakes (r:1 w:1)
+	fn stake_lp() -> Weight {
+		Weight::from_parts(36_418_000, 8_924)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex Farms (r:1 w:1)
+	/// Storage: Dex Stakes (r:1 w:1)
+	fn unstake_lp() -> Weight {
+		Weight::from_parts(29_655_000, 7_882)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex Farms (r:1 w:1)
+	/// Storage: Dex Stakes (r:1 w:1)
+	/// Storage: Dex RewardVesting (r:1 w:1)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:2)
+	fn claim_rewards() -> Weight {
+		Weight::from_parts(58_209_000, 11_514)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:1)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Dex PriceWindows (r:1 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn flash_swap() -> Weight {
+		Weight::from_parts(84_107_000, 11_736)
+			.saturating_add(RocksDbWeight::get().reads(12_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Assets Metadata (r:3 w:1)
+	/// Storage: Dex PairToLp (r:1 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	fn create_pool() -> Weight {
+		Weight::from_parts(54_318_000, 7_412)
+			.saturating_add(RocksDbWeight::get().reads(14_u64))
+			.saturating_add(RocksDbWeight::get().writes(12_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:3 w:3)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:1 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Assets Account (r:5 w:5)
+	/// Storage: Dex Observations (r:0 w:1)
+	/// Storage: Dex ObservationIndex (r:0 w:1)
+	/// Storage: Assets Metadata (r:1 w:1)
+	/// Storage: Dex PairToLp (r:0 w:1)
+	/// Storage: Dex LpToPair (r:1 w:1)
+	/// Storage: Dex Stakes (r:1 w:0)
+	/// Storage: Dex Farms (r:0 w:1)
+	/// Storage: Dex PriceWindows (r:0 w:1)
+	fn destroy_pool() -> Weight {
+		Weight::from_parts(93_614_000, 10_350)
+			.saturating_add(RocksDbWeight::get().reads(20_u64))
+			.saturating_add(RocksDbWeight::get().writes(24_u64))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Dex PriceWindows (r:0 w:1)
+	/// Storage: Dex PairToLp (r:1 w:0)
+	fn set_pool_status() -> Weight {
+		Weight::from_parts(18_437_000, 5_146)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:0)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn skim() -> Weight {
+		Weight::from_parts(43_271_000, 10_442)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Account (r:2 w:0)
+	/// Storage: Dex PriceWindows (r:0 w:1)
+	fn sync() -> Weight {
+		Weight::from_parts(31_806_000, 8_694)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PairToLp (r:6 w:6)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Assets Metadata (r:5 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	/// Storage: Dex PoolMap (r:0 w:1)
+	/// The range of component `n` is `[2, 4]`.
+	fn create_stable_pool(n: u32, ) -> Weight {
+		Weight::from_parts(49_112_000, 7_412)
+			.saturating_add(Weight::from_parts(5_926_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2_603).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:5 w:5)
+	/// Storage: Assets Account (r:10 w:10)
+	/// The range of component `n` is `[2, 4]`.
+	fn add_stable_liquidity(n: u32, ) -> Weight {
+		Weight::from_parts(61_530_000, 8_694)
+			.saturating_add(Weight::from_parts(38_204_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_180).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:5 w:5)
+	/// Storage: Assets Account (r:9 w:9)
+	/// The range of component `n` is `[2, 4]`.
+	fn remove_stable_liquidity(n: u32, ) -> Weight {
+		Weight::from_parts(48_817_000, 8_694)
+			.saturating_add(Weight::from_parts(21_395_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_180).saturating_mul(n.into()))
+	}
+	/// Storage: Dex PoolMap (r:1 w:1)
+	fn ramp_amplification() -> Weight {
+		Weight::from_parts(16_902_000, 5_146)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PairToLp (r:6 w:6)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:3 w:3)
+	/// Storage: Assets Asset (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Assets Metadata (r:5 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	/// Storage: Dex PoolMap (r:0 w:1)
+	/// The range of component `n` is `[2, 8]`.
+	fn create_weighted_pool(n: u32, ) -> Weight {
+		Weight::from_parts(50_384_000, 7_412)
+			.saturating_add(Weight::from_parts(6_113_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2_603).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:5 w:5)
+	/// Storage: Assets Account (r:10 w:10)
+	/// The range of component `n` is `[2, 8]`.
+	fn add_weighted_liquidity(n: u32, ) -> Weight {
+		Weight::from_parts(44_267_000, 8_694)
+			.saturating_add(Weight::from_parts(19_872_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_180).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:5 w:5)
+	/// Storage: Assets Account (r:9 w:9)
+	/// The range of component `n` is `[2, 8]`.
+	fn remove_weighted_liquidity(n: u32, ) -> Weight {
+		Weight::from_parts(49_530_000, 8_694)
+			.saturating_add(Weight::from_parts(21_418_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 5_180).saturating_mul(n.into()))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn add_weighted_liquidity_single() -> Weight {
+		Weight::from_parts(72_315_000, 13_874)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn remove_weighted_liquidity_single() -> Weight {
+		Weight::from_parts(78_940_000, 13_874)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Dex FeeTiers (r:1 w:0)
+	/// Storage: Dex PoolCount (r:1 w:1)
+	/// Storage: Dex PoolDeposits (r:0 w:1)
+	/// Storage: Balances Holds (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex PairToLp (r:1 w:1)
+	/// Storage: Dex LpToPair (r:0 w:1)
+	/// Storage: Dex NextLpIndex (r:1 w:1)
+	fn create_concentrated_pool() -> Weight {
+		Weight::from_parts(112_406_000, 7_412)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	/// Storage: Dex NextPositionId (r:1 w:1)
+	/// Storage: Dex Positions (r:0 w:1)
+	fn mint_position() -> Weight {
+		Weight::from_parts(96_752_000, 15_220)
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex Positions (r:1 w:1)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Timestamp Now (r:1 w:0)
+	/// Storage: Dex Observations (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn burn_position() -> Weight {
+		Weight::from_parts(101_384_000, 15_220)
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	/// Storage: Dex FlashLoanLock (r:1 w:0)
+	/// Storage: Dex Positions (r:1 w:1)
+	/// Storage: Dex PoolMap (r:1 w:1)
+	/// Storage: Assets Asset (r:2 w:2)
+	/// Storage: Assets Account (r:4 w:4)
+	fn collect_fees() -> Weight {
+		Weight::from_parts(68_119_000, 15_220)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+}
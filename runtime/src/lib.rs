@@ -7,7 +7,7 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use codec::{Decode, Encode};
-use frame_support::traits::AsEnsureOriginWithArg;
+use frame_support::traits::{Contains, EnsureOrigin, EnsureOriginWithArg};
 use frame_system::{EnsureRoot, EnsureSigned};
 use pallet_grandpa::AuthorityId as GrandpaId;
 use sp_api::impl_runtime_apis;
@@ -31,8 +31,8 @@ use sp_version::RuntimeVersion;
 pub use frame_support::{
 	construct_runtime, parameter_types,
 	traits::{
-		ConstBool, ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness,
-		StorageInfo,
+		ConstBool, ConstU128, ConstU16, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem,
+		Randomness, StorageInfo,
 	},
 	weights::{
 		constants::{
@@ -44,6 +44,7 @@ pub use frame_support::{
 };
 pub use frame_system::Call as SystemCall;
 pub use pallet_balances::Call as BalancesCall;
+use pallet_dex::NativeOrAsset;
 pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::{ConstFeeMultiplier, CurrencyAdapter, Multiplier};
 #[cfg(any(feature = "std", test))]
@@ -63,6 +64,12 @@ pub type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::Account
 /// Balance of an account.
 pub type Balance = u128;
 
+/// Identifier of an asset in `pallet_assets`, including DEX LP tokens.
+pub type AssetId = u32;
+
+/// The first asset id of DEX LP tokens. The ids from here on are reserved for them.
+pub const FIRST_LP_ASSET_ID: AssetId = 1 << 31;
+
 /// Index of a transaction in the chain.
 pub type Nonce = u32;
 
@@ -103,12 +110,15 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// The version of the runtime specification. A full node will not attempt to use its native
 	//   runtime in substitute for the on-chain Wasm runtime unless all of `spec_name`,
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
-	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
-	//   the compatible custom types.
-	spec_version: 100,
+	// This value started at 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
+	//   the compatible custom types. It must be increased for every runtime upgrade, or
+	//   `set_code` rejects it and the DEX's storage migrations in `Executive` never run.
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	// Increased whenever the encoding of extrinsics changes, like the DEX's calls or the
+	//   `CheckDexDeadline` signed extension, so signers don't build payloads this runtime rejects.
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -246,7 +256,7 @@ impl pallet_balances::Config for Runtime {
 	type WeightInfo = pallet_balances::weights::SubstrateWeight<Runtime>;
 	type FreezeIdentifier = ();
 	type MaxFreezes = ConstU32<50>;
-	type RuntimeHoldReason = ();
+	type RuntimeHoldReason = RuntimeHoldReason;
 	type MaxHolds = ConstU32<50>;
 }
 
@@ -280,10 +290,10 @@ parameter_types! {
 impl pallet_assets::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
 	type Balance = u128;
-	type AssetId = u32;
-	type AssetIdParameter = codec::Compact<u32>;
+	type AssetId = AssetId;
+	type AssetIdParameter = codec::Compact<AssetId>;
 	type Currency = Balances;
-	type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<AccountId>>;
+	type CreateOrigin = AssetCreators;
 	type ForceOrigin = EnsureRoot<AccountId>;
 	type AssetDeposit = AssetDeposit;
 	type AssetAccountDeposit = ConstU128<1>;
@@ -291,7 +301,7 @@ impl pallet_assets::Config for Runtime {
 	type MetadataDepositPerByte = MetadataDepositPerByte;
 	type ApprovalDeposit = ApprovalDeposit;
 	type StringLimit = StringLimit;
-	type Freezer = ();
+	type Freezer = StakedLp;
 	type Extra = ();
 	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
 	type RemoveItemsLimit = ConstU32<1000>;
@@ -300,6 +310,49 @@ impl pallet_assets::Config for Runtime {
 	type BenchmarkHelper = ();
 }
 
+/// Lets any signed account create an asset, except with the ids reserved for DEX LP tokens.
+pub struct AssetCreators;
+impl EnsureOriginWithArg<RuntimeOrigin, codec::Compact<AssetId>> for AssetCreators {
+	type Success = AccountId;
+
+	fn try_origin(
+		o: RuntimeOrigin,
+		id: &codec::Compact<AssetId>,
+	) -> Result<Self::Success, RuntimeOrigin> {
+		if id.0 >= FIRST_LP_ASSET_ID {
+			return Err(o)
+		}
+		EnsureSigned::<AccountId>::try_origin(o)
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin(id: &codec::Compact<AssetId>) -> Result<RuntimeOrigin, ()> {
+		if id.0 >= FIRST_LP_ASSET_ID {
+			return Err(())
+		}
+		EnsureSigned::<AccountId>::try_successful_origin()
+	}
+}
+
+/// Hands out the DEX's LP token ids from `FIRST_LP_ASSET_ID` on.
+pub struct LpAssetIds;
+impl Convert<u32, AssetId> for LpAssetIds {
+	fn convert(index: u32) -> AssetId {
+		FIRST_LP_ASSET_ID.saturating_add(index)
+	}
+}
+
+/// Reports the LP tokens staked in the DEX's farms as frozen, so they can't be moved.
+pub struct StakedLp;
+
+impl pallet_assets::FrozenBalance<AssetId, AccountId, u128> for StakedLp {
+	fn frozen_balance(asset: AssetId, who: &AccountId) -> Option<u128> {
+		Dex::staked_balance(&asset, who)
+	}
+
+	fn died(_asset: AssetId, _who: &AccountId) {}
+}
+
 pub struct AuthorityToAccount;
 
 impl Convert<AuraId, AccountId> for AuthorityToAccount {
@@ -313,14 +366,56 @@ impl Convert<AuraId, AccountId> for AuthorityToAccount {
 
 parameter_types! {
 	pub const DexPalletId: frame_support::PalletId = frame_support::PalletId(*b"pba/dex0");
+	pub const DexMaxPriceMove: Permill = Permill::from_percent(25);
+	pub const DexMaxPriceImpact: Permill = Permill::from_percent(10);
+	pub DexNativeSymbol: Vec<u8> = b"UNIT".to_vec();
+}
+
+/// Flash loan borrowers may only move balances and assets around with the loaned funds.
+pub struct FlashLoanCalls;
+impl Contains<RuntimeCall> for FlashLoanCalls {
+	fn contains(call: &RuntimeCall) -> bool {
+		matches!(call, RuntimeCall::Balances(_) | RuntimeCall::Assets(_))
+	}
 }
+
 /// Configure the pallet-dex in pallets/dex.
 impl pallet_dex::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type RuntimeHoldReason = RuntimeHoldReason;
 	type NativeBalance = Balances;
 	type Fungibles = Assets;
 	type PalletId = DexPalletId;
 	type PermissionOrigin = EnsureRoot<AccountId>;
+	type MaxPathLength = ConstU32<4>;
+	type Timestamp = Timestamp;
+	type MaxObservations = ConstU32<64>;
+	type DefaultFeeTier = ConstU16<30>;
+	type MaxFeeTiers = ConstU32<8>;
+	type MinimumLiquidity = ConstU128<1_000>;
+	type RewardVestingPeriod = ConstU32<{ 7 * DAYS }>;
+	type NativeDecimals = ConstU8<12>;
+	type NativeSymbol = DexNativeSymbol;
+	type LpMinBalance = ConstU128<1>;
+	type LpAssetId = LpAssetIds;
+	type PoolCreationDeposit = ConstU128<{ 100 * EXISTENTIAL_DEPOSIT }>;
+	type MaxPools = ConstU32<1_000>;
+	type MaxStableAssets = ConstU32<4>;
+	type MaxAmplification = ConstU32<10_000>;
+	type MinAmplificationRamp = ConstU32<DAYS>;
+	type AmplificationOrigin = EnsureRoot<AccountId>;
+	type MaxWeightedAssets = ConstU32<8>;
+	type MaxTicks = ConstU32<64>;
+	type FlashLoanCallFilter = FlashLoanCalls;
+	type PauseOrigin = EnsureRoot<AccountId>;
+	type PriceMoveWindow = ConstU32<10>;
+	type MaxPriceMove = DexMaxPriceMove;
+	type MaxPriceImpact = DexMaxPriceImpact;
+	type FlashLoanReceiver = ();
+	type WeightInfo = pallet_dex::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -354,6 +449,7 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_dex::CheckDexDeadline<Runtime>,
 );
 
 /// Unchecked extrinsic type as expected by this runtime.
@@ -368,8 +464,16 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
+/// The storage migrations run on the next runtime upgrade.
+pub type Migrations = (
+	pallet_dex::migrations::v1::MigrateToV1<Runtime>,
+	pallet_dex::migrations::v2::MigrateToV2<Runtime>,
+	pallet_dex::migrations::v3::MigrateToV3<Runtime>,
+);
+
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
 extern crate frame_benchmarking;
@@ -554,6 +658,58 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_dex_rpc_runtime_api::DexApi<Block, AssetId, Balance> for Runtime {
+		fn quote_exact_in(
+			asset_in: NativeOrAsset<AssetId>,
+			asset_out: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+			amount_in: Balance,
+		) -> Option<pallet_dex::Quote<Balance>> {
+			Dex::quote_exact_in(asset_in, asset_out, fee_tier, amount_in)
+		}
+
+		fn quote_exact_out(
+			asset_in: NativeOrAsset<AssetId>,
+			asset_out: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+			amount_out: Balance,
+		) -> Option<pallet_dex::Quote<Balance>> {
+			Dex::quote_exact_out(asset_in, asset_out, fee_tier, amount_out)
+		}
+
+		fn get_reserves(
+			asset_a: NativeOrAsset<AssetId>,
+			asset_b: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+		) -> Option<(Balance, Balance)> {
+			Dex::get_reserves(asset_a, asset_b, fee_tier)
+		}
+
+		fn lp_id(
+			asset_a: NativeOrAsset<AssetId>,
+			asset_b: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+		) -> Option<AssetId> {
+			Dex::get_lp_id(&asset_a, &asset_b, fee_tier).ok()
+		}
+
+		fn pool_info(
+			asset_a: NativeOrAsset<AssetId>,
+			asset_b: NativeOrAsset<AssetId>,
+			fee_tier: u16,
+		) -> Option<pallet_dex::PoolInfo<AssetId, Balance>> {
+			Dex::pool_info(asset_a, asset_b, fee_tier)
+		}
+
+		fn fee_tiers() -> Vec<u16> {
+			pallet_dex::FeeTiers::<Runtime>::get().into_inner()
+		}
+
+		fn pools() -> Vec<pallet_dex::PoolInfo<AssetId, Balance>> {
+			Dex::pools()
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (